@@ -0,0 +1,216 @@
+use std::path::Path;
+
+use crate::{
+    goal::GoalSpec,
+    map::{Topology, MAX_COST},
+    Grid,
+};
+
+/// A map read from a plain text file, one row per line: `#` is an obstacle, `.` is free
+/// and `S` and `G` mark the start and goal, which must appear exactly once each. Digits
+/// from `1` to `5` are free cells costing that much to enter, `1` being the same as `.`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsciiMap {
+    pub width: u32,
+    pub height: u32,
+    pub start: (u32, u32),
+    pub goal: (u32, u32),
+    pub obstacles: Vec<(u32, u32)>,
+    /// Free cells costing more than normal terrain, with their cost
+    pub costs: Vec<((u32, u32), u32)>,
+}
+
+impl AsciiMap {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read map {}: {}", path.display(), e))?;
+
+        Self::parse(&text).map_err(|e| format!("invalid map {}: {}", path.display(), e))
+    }
+
+    /// Trailing empty lines are ignored, every other row has to be as wide as the first
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let rows: Vec<&str> = text.trim_end().lines().map(str::trim_end).collect();
+
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        if width == 0 {
+            return Err("map is empty".to_string());
+        }
+
+        let mut start = None;
+        let mut goal = None;
+        let mut obstacles = Vec::new();
+        let mut costs = Vec::new();
+
+        for (y, row) in rows.iter().enumerate() {
+            let line = y + 1;
+
+            if row.chars().count() != width {
+                return Err(format!(
+                    "line {} is {} cells wide, expected {} like the first line",
+                    line,
+                    row.chars().count(),
+                    width
+                ));
+            }
+
+            for (x, c) in row.chars().enumerate() {
+                let cell = (x as u32, y as u32);
+
+                match c {
+                    '#' => obstacles.push(cell),
+                    '.' | '1' => {}
+                    '2'..='5' => costs.push((cell, c.to_digit(10).unwrap())),
+                    'S' if start.is_some() => return Err(format!("second start on line {}", line)),
+                    'S' => start = Some(cell),
+                    'G' if goal.is_some() => return Err(format!("second goal on line {}", line)),
+                    'G' => goal = Some(cell),
+                    _ => {
+                        return Err(format!(
+                            "unexpected {:?} on line {} column {}, expected '#', '.', 'S', 'G' \
+                             or a cost from '1' to '5'",
+                            c,
+                            line,
+                            x + 1
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            width: width as u32,
+            height: rows.len() as u32,
+            start: start.ok_or("map has no start 'S'")?,
+            goal: goal.ok_or("map has no goal 'G'")?,
+            obstacles,
+            costs,
+        })
+    }
+
+    /// The map, start and goal of the main search of `grid`. Fails if the grid has
+    /// anything text can't show: more agents, a goal that isn't a single cell, negative
+    /// terrain, portals, layers or triangles.
+    pub fn from_grid(grid: &Grid) -> Result<Self, String> {
+        let map = grid.map();
+        let search = match grid.searches() {
+            [search] => search,
+            searches => return Err(format!("{} agents, text maps have one", searches.len())),
+        };
+
+        let &GoalSpec::Cell(goal) = search.goal() else {
+            return Err("goal isn't a single cell, text maps have one".to_string());
+        };
+
+        if !map.portals().is_empty() || map.layers() > 1 {
+            return Err("text maps can't have portals or layers".to_string());
+        }
+        if map.topology() != Topology::Square {
+            return Err("text maps are made of squares".to_string());
+        }
+
+        let mut obstacles = Vec::new();
+        let mut costs = Vec::new();
+        for y in 0..map.height() {
+            for x in 0..map.width() {
+                let cell = (x, y);
+
+                if map.is_obstacle(cell) {
+                    obstacles.push(cell);
+                } else if map.signed_cost(cell) < 0 {
+                    return Err(format!(
+                        "negative terrain at {:?}, text maps have none",
+                        cell
+                    ));
+                } else if map.cost(cell) > 1 {
+                    costs.push((cell, map.cost(cell)));
+                }
+            }
+        }
+
+        Ok(Self {
+            width: map.width(),
+            height: map.height(),
+            start: search.start(),
+            goal,
+            obstacles,
+            costs,
+        })
+    }
+
+    /// The map as [`Self::parse`] reads it
+    pub fn to_text(&self) -> String {
+        let mut rows = vec![vec!['.'; self.width as usize]; self.height as usize];
+
+        for &(x, y) in &self.obstacles {
+            rows[y as usize][x as usize] = '#';
+        }
+        for &((x, y), cost) in &self.costs {
+            rows[y as usize][x as usize] = char::from_digit(cost.min(MAX_COST), 10).unwrap();
+        }
+        rows[self.start.1 as usize][self.start.0 as usize] = 'S';
+        rows[self.goal.1 as usize][self.goal.0 as usize] = 'G';
+
+        rows.into_iter()
+            .map(|row| row.into_iter().chain(['\n']).collect::<String>())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map::Material, search::Algorithm};
+
+    const WEIGHTED: &str = "\
+S.2#
+.55#
+#..G
+";
+
+    #[test]
+    fn parses_costs() {
+        let map = AsciiMap::parse(WEIGHTED).unwrap();
+
+        assert_eq!((map.width, map.height), (4, 3));
+        assert_eq!((map.start, map.goal), ((0, 0), (3, 2)));
+        assert_eq!(map.obstacles, [(3, 0), (3, 1), (0, 2)]);
+        assert_eq!(map.costs, [((2, 0), 2), ((1, 1), 5), ((2, 1), 5)]);
+    }
+
+    #[test]
+    fn rejects_costs_out_of_range() {
+        for text in ["S0.G\n", "S6.G\n"] {
+            assert!(AsciiMap::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn weighted_maps_round_trip_through_grids() {
+        let map = AsciiMap::parse(WEIGHTED).unwrap();
+
+        let mut grid = Grid::new(
+            map.width,
+            map.height,
+            map.start,
+            GoalSpec::Cell(map.goal),
+            Algorithm::Dijkstra,
+        );
+        grid.paint_cells(&map.obstacles, Material::Obstacle);
+        for &(cell, cost) in &map.costs {
+            grid.paint(cell, Material::Cost(cost));
+        }
+
+        let saved = AsciiMap::from_grid(&grid).unwrap();
+        assert_eq!(saved, map);
+        assert_eq!(saved.to_text(), WEIGHTED);
+    }
+
+    #[test]
+    fn grids_text_cant_show_are_refused() {
+        let mut grid = Grid::new(4, 3, (0, 0), GoalSpec::Cell((3, 2)), Algorithm::Dijkstra);
+        grid.paint((1, 1), Material::Negative);
+
+        assert!(AsciiMap::from_grid(&grid).is_err());
+    }
+}
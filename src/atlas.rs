@@ -0,0 +1,66 @@
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{Canvas, RenderTarget, Texture, TextureCreator},
+    ttf::Font,
+};
+
+/// Every character that can be drawn from the atlas
+const GLYPHS: &str = "0123456789.ghf";
+
+/// All glyphs needed for cell labels, pre-rendered into a single texture, so drawing
+/// thousands of small labels per frame doesn't need a font render per label.
+///
+/// This relies on the font being monospace.
+pub struct GlyphAtlas<'tc> {
+    texture: Texture<'tc>,
+    point_size: u16,
+    glyph_width: u32,
+    glyph_height: u32,
+}
+
+impl<'tc> GlyphAtlas<'tc> {
+    /// Renders the glyphs with `font`, which has to be loaded at `point_size`
+    pub fn new<C>(font: &Font, point_size: u16, texture_creator: &'tc TextureCreator<C>) -> Self {
+        let surface = font.render(GLYPHS).blended(Color::BLACK).unwrap();
+
+        Self {
+            texture: surface.as_texture(texture_creator).unwrap(),
+            point_size,
+            glyph_width: surface.width() / GLYPHS.len() as u32,
+            glyph_height: surface.height(),
+        }
+    }
+
+    pub fn point_size(&self) -> u16 {
+        self.point_size
+    }
+
+    pub fn glyph_height(&self) -> u32 {
+        self.glyph_height
+    }
+
+    /// Draws `text` with its top left corner at `x`, `y`, skipping characters not in the atlas
+    pub fn draw_text<T: RenderTarget>(&self, canvas: &mut Canvas<T>, text: &str, x: i32, y: i32) {
+        for (i, c) in text.chars().enumerate() {
+            let Some(index) = GLYPHS.find(c) else {
+                continue;
+            };
+
+            let src = Rect::new(
+                (index as u32 * self.glyph_width) as i32,
+                0,
+                self.glyph_width,
+                self.glyph_height,
+            );
+            let dst = Rect::new(
+                x + (i as u32 * self.glyph_width) as i32,
+                y,
+                self.glyph_width,
+                self.glyph_height,
+            );
+
+            canvas.copy(&self.texture, src, dst).unwrap();
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::{f64::consts::SQRT_2, time::Instant};
+
+use dijkstra_visual::{
+    goal::GoalSpec,
+    map::{Material, DIAGONAL_COST, UNIT_COST},
+    movingai::{MovingAiMap, Scenario},
+    search::{Algorithm, Heuristic, SearchStatus},
+    Grid,
+};
+
+/// A grid with the obstacles of `map`, searching between the ends of `scenario`.
+/// Octile maps search diagonally, without cutting corners, like the benchmarks expect.
+pub fn scenario_grid(
+    map: &MovingAiMap,
+    scenario: &Scenario,
+    algorithm: Algorithm,
+) -> Result<Grid, String> {
+    if (scenario.width, scenario.height) != (map.width, map.height) {
+        return Err(format!(
+            "scenario is for a {}x{} map, {} is {}x{}",
+            scenario.width, scenario.height, scenario.map, map.width, map.height
+        ));
+    }
+
+    // searches panic on endpoints outside of the map
+    let in_bounds = |(x, y): (u32, u32)| x < map.width && y < map.height;
+    if !in_bounds(scenario.start) || !in_bounds(scenario.goal) {
+        return Err(format!(
+            "scenario from {:?} to {:?} is outside of the {}x{} map",
+            scenario.start, scenario.goal, map.width, map.height
+        ));
+    }
+
+    let mut grid = Grid::new(
+        map.width,
+        map.height,
+        scenario.start,
+        GoalSpec::Cell(scenario.goal),
+        algorithm,
+    );
+
+    grid.set_corner_cutting(false).set_diagonals(map.octile);
+
+    for &cell in &map.obstacles {
+        grid.paint(cell, Material::Obstacle);
+    }
+
+    Ok(grid)
+}
+
+/// Length of `path` in cells, diagonal steps counting the square root of 2
+fn path_length(path: &[(u32, u32)]) -> f64 {
+    path.windows(2)
+        .map(|step| {
+            let (a, b) = (step[0], step[1]);
+            if a.0 != b.0 && a.1 != b.1 {
+                SQRT_2
+            } else {
+                1.0
+            }
+        })
+        .sum()
+}
+
+/// Runs every scenario to completion without a window, logs those whose path doesn't
+/// have the optimal length and prints how long the searches took. Returns whether all of
+/// them found an optimal path.
+pub fn run(
+    map: &MovingAiMap,
+    scenarios: &[Scenario],
+    algorithm: Algorithm,
+    heuristic_weight: f64,
+    heuristic: Option<Heuristic>,
+) -> Result<bool, String> {
+    // diagonals are a whole number of hundredths, so paths can be this much longer than
+    // the optimal one and still be the shortest there is to this search
+    let tolerance = SQRT_2 * UNIT_COST as f64 / DIAGONAL_COST as f64 - 1.0;
+
+    let mut histogram = hdrhistogram::Histogram::<u64>::new_with_bounds(1, 60 * 1000 * 1000, 3)
+        .expect("histogram bounds are valid");
+    let mut failed = 0;
+
+    for (i, scenario) in scenarios.iter().enumerate() {
+        let mut grid = scenario_grid(map, scenario, algorithm)?;
+        grid.set_heuristic_weight(heuristic_weight);
+        grid.set_heuristic_kind(heuristic);
+
+        let started = Instant::now();
+        let status = grid.run_to_completion();
+        histogram.saturating_record(started.elapsed().as_micros() as u64);
+
+        let length = match status {
+            SearchStatus::Found => path_length(grid.searches()[0].path()),
+            _ => f64::INFINITY,
+        };
+
+        if length > scenario.optimal_length * (1.0 + tolerance) + 1e-6 {
+            tracing::warn!(
+                "scenario {} (bucket {}) from {:?} to {:?}: length {:.4}, optimal {:.4}",
+                i,
+                scenario.bucket,
+                scenario.start,
+                scenario.goal,
+                length,
+                scenario.optimal_length
+            );
+            failed += 1;
+        }
+    }
+
+    println!(
+        "{} scenarios with {:?}, {} not optimal",
+        scenarios.len(),
+        algorithm,
+        failed
+    );
+    println!(
+        "search time in µs: mean {:.0}, median {}, 95th {}, max {}",
+        histogram.mean(),
+        histogram.value_at_quantile(0.5),
+        histogram.value_at_quantile(0.95),
+        histogram.max()
+    );
+
+    Ok(failed == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scenarios_leaving_the_map_are_rejected() {
+        let map = MovingAiMap::parse("type octile\nheight 2\nwidth 3\nmap\n...\n...\n").unwrap();
+        let scenario = |start, goal| Scenario {
+            bucket: 0,
+            map: "test.map".to_string(),
+            width: 3,
+            height: 2,
+            start,
+            goal,
+            optimal_length: 2.0,
+        };
+
+        assert!(scenario_grid(&map, &scenario((0, 0), (2, 1)), Algorithm::Dijkstra).is_ok());
+        assert!(scenario_grid(&map, &scenario((3, 0), (2, 1)), Algorithm::Dijkstra).is_err());
+        assert!(scenario_grid(&map, &scenario((0, 0), (2, 2)), Algorithm::Dijkstra).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use sdl2::{
+    pixels::PixelFormatEnum,
+    rect::Rect,
+    render::{Canvas, Texture, TextureCreator},
+    video::{Window, WindowContext},
+};
+
+use dijkstra_visual::Grid;
+
+use crate::{layout::Layout, renderer::Renderer, view::View};
+
+/// Where the cells of a pane are kept between frames, so only the cells that changed
+/// are drawn again.
+///
+/// Squares are a pixel each of a streaming texture scaled up over the pane, with the
+/// gaps between them filled in afterwards, which costs the same however many cells
+/// there are. Triangles overlap, so they are drawn as they look into a texture the
+/// size of the pane instead.
+pub struct CellCanvas<'tc> {
+    texture_creator: &'tc TextureCreator<WindowContext>,
+    /// A pixel per cell of the rows shown, and what was last written to it
+    texels: Option<Texture<'tc>>,
+    pixels: Vec<u8>,
+    /// The pane as drawn, for triangles
+    target: Option<Texture<'tc>>,
+}
+
+/// A pane of the window to draw cells into, `layout` placing them within `rect`
+pub struct Pane<'a, 'tc> {
+    pub cells: &'a mut CellCanvas<'tc>,
+    pub canvas: &'a mut Canvas<Window>,
+    pub layout: &'a Layout,
+    pub rect: Rect,
+}
+
+impl Renderer for Pane<'_, '_> {
+    fn draw_cells(&mut self, view: &mut View, grid: &Grid, now: Duration) -> Result<(), String> {
+        self.cells
+            .draw(self.canvas, view, grid, self.layout, self.rect, now);
+        Ok(())
+    }
+}
+
+impl<'tc> CellCanvas<'tc> {
+    pub fn new(texture_creator: &'tc TextureCreator<WindowContext>) -> Self {
+        Self {
+            texture_creator,
+            texels: None,
+            pixels: Vec::new(),
+            target: None,
+        }
+    }
+
+    /// Draws the cells of `grid` into `pane`. `layout` places the cells within the pane.
+    /// The textures are made on first use and again whenever they no longer fit.
+    pub fn draw(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        view: &mut View,
+        grid: &Grid,
+        layout: &Layout,
+        pane: Rect,
+        now: Duration,
+    ) {
+        let shifted = layout.shifted(pane.x());
+        let Some(area) = shifted.texel_rect() else {
+            self.draw_triangles(canvas, view, grid, layout, pane, now);
+            return;
+        };
+
+        let (columns, rows) = (grid.width(), layout.rows().len() as u32);
+        let fits = |texture: &Texture| {
+            let query = texture.query();
+            (query.width, query.height) == (columns, rows)
+        };
+
+        let texels = match &mut self.texels {
+            Some(texels) if fits(texels) => texels,
+            texels => {
+                view.invalidate();
+                self.pixels = vec![0; columns as usize * rows as usize * 3];
+                texels.insert(
+                    self.texture_creator
+                        .create_texture_streaming(PixelFormatEnum::RGB24, columns, rows)
+                        .unwrap(),
+                )
+            }
+        };
+
+        let pitch = columns as usize * 3;
+        if view.paint_cells(&mut self.pixels, pitch, grid, layout, now) {
+            texels.update(None, &self.pixels, pitch).unwrap();
+        }
+
+        canvas.set_clip_rect(pane);
+        canvas.copy(texels, None, area).unwrap();
+        view.draw_gaps(canvas, &shifted);
+        canvas.set_clip_rect(None);
+    }
+
+    /// Draws the cells that changed into a texture the size of the pane, then copies it
+    /// over the pane
+    fn draw_triangles(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        view: &mut View,
+        grid: &Grid,
+        layout: &Layout,
+        pane: Rect,
+        now: Duration,
+    ) {
+        let fits = |texture: &Texture| {
+            let query = texture.query();
+            (query.width, query.height) == pane.size()
+        };
+
+        let target = match &mut self.target {
+            Some(target) if fits(target) => target,
+            target => {
+                view.invalidate();
+                target.insert(
+                    self.texture_creator
+                        .create_texture_target(None, pane.width(), pane.height())
+                        .unwrap(),
+                )
+            }
+        };
+
+        canvas
+            .with_texture_canvas(target, |target| view.draw_cells(target, grid, layout, now))
+            .unwrap();
+        canvas.copy(target, None, pane).unwrap();
+    }
+}
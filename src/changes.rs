@@ -0,0 +1,73 @@
+/// Most cells a log holds before it forgets them, making everyone catch up on every cell
+const MAX_CHANGES: usize = 1 << 20;
+
+/// Cells whose state changed, in the order they did, so whoever draws them only has to
+/// look at those. Readers keep the [`ChangeLog::mark`] they caught up to and later ask
+/// for the cells changed [`ChangeLog::since`] then.
+#[derive(Clone, Debug, Default)]
+pub struct ChangeLog {
+    cells: Vec<(u32, u32)>,
+    /// Changes before the first one still in `cells`
+    forgotten: usize,
+}
+
+impl ChangeLog {
+    pub fn push(&mut self, cell: (u32, u32)) {
+        if self.cells.len() >= MAX_CHANGES {
+            self.forget();
+        }
+
+        self.cells.push(cell);
+    }
+
+    /// Where the log is up to now
+    pub fn mark(&self) -> usize {
+        self.forgotten + self.cells.len()
+    }
+
+    /// Cells that changed since `mark`, the same cell possibly more than once. `None` if
+    /// the log forgot some of them, then any cell may have changed.
+    pub fn since(&self, mark: usize) -> Option<&[(u32, u32)]> {
+        self.cells.get(mark.checked_sub(self.forgotten)?..)
+    }
+
+    /// Drops every change, for when every cell may have changed, so no earlier mark is
+    /// caught up anymore
+    pub fn forget(&mut self) {
+        self.forgotten = self.mark() + 1;
+        self.cells.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_since_a_mark() {
+        let mut log = ChangeLog::default();
+        log.push((1, 2));
+        let mark = log.mark();
+        log.push((3, 4));
+        log.push((1, 2));
+
+        assert_eq!(log.since(mark), Some(&[(3, 4), (1, 2)][..]));
+        assert_eq!(log.since(log.mark()), Some(&[][..]));
+    }
+
+    #[test]
+    fn forgetting_invalidates_every_earlier_mark() {
+        let mut log = ChangeLog::default();
+        let empty = log.mark();
+        log.push((0, 0));
+        let mark = log.mark();
+
+        log.forget();
+        assert_eq!(log.since(empty), None);
+        assert_eq!(log.since(mark), None);
+
+        log.push((5, 5));
+        assert_eq!(log.since(mark), None);
+        assert_eq!(log.since(log.mark() - 1), Some(&[(5, 5)][..]));
+    }
+}
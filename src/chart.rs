@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::{Canvas, RenderTarget},
+};
+
+/// Sparkline of the last few values, e.g. the open set size per iteration
+#[derive(Clone, Debug)]
+pub struct Sparkline {
+    values: VecDeque<usize>,
+    capacity: usize,
+    /// Run the values belong to, see [`Sparkline::follow`]
+    run: u64,
+}
+
+impl Sparkline {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+            run: 0,
+        }
+    }
+
+    /// Appends a value, dropping the oldest one once full
+    pub fn push(&mut self, value: usize) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+
+        self.values.push_back(value);
+    }
+
+    /// Starts the chart over if `run`, e.g. how often the search restarted, isn't the
+    /// one charted so far
+    pub fn follow(&mut self, run: u64) {
+        if run != self.run {
+            self.run = run;
+            self.values.clear();
+        }
+    }
+
+    /// Smallest and largest value currently in the chart, `None` while empty
+    pub fn range(&self) -> Option<(usize, usize)> {
+        let min = self.values.iter().min()?;
+        let max = self.values.iter().max()?;
+
+        Some((*min, *max))
+    }
+
+    /// Draws the values as a polyline filling `rect`, oldest on the left. The line is
+    /// scaled to the current range, so a flat line sits at the bottom.
+    pub fn draw<T: RenderTarget>(&self, canvas: &mut Canvas<T>, rect: Rect) {
+        canvas.set_draw_color(Color::RGB(40, 40, 40));
+        canvas.fill_rect(rect).unwrap();
+
+        let Some((min, max)) = self.range() else {
+            return;
+        };
+
+        let span = (max - min).max(1) as f64;
+        let step = rect.width() as f64 / (self.capacity.max(2) - 1) as f64;
+
+        let points: Vec<_> = self
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| {
+                let t = (value - min) as f64 / span;
+
+                Point::new(
+                    rect.x() + (i as f64 * step) as i32,
+                    rect.bottom() - 1 - (t * (rect.height() - 1) as f64) as i32,
+                )
+            })
+            .collect();
+
+        canvas.set_draw_color(Color::RGB(0, 220, 120));
+        canvas.draw_lines(points.as_slice()).unwrap();
+    }
+}
@@ -0,0 +1,20 @@
+use std::{collections::BTreeMap, path::Path};
+
+/// Settings loaded from the TOML config file
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Action name to key name, e.g. `pause = "Space"`
+    pub keys: BTreeMap<String, String>,
+    /// Theme color name to color, e.g. `visited = "#ffcc00"`
+    pub colors: BTreeMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read config {}: {}", path.display(), e))?;
+
+        toml::from_str(&text).map_err(|e| format!("invalid config {}: {}", path.display(), e))
+    }
+}
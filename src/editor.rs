@@ -0,0 +1,412 @@
+use sdl2::{event::Event, mouse::MouseButton};
+
+use dijkstra_visual::{
+    goal::GoalSpec,
+    grid2d::Grid2D,
+    map::{Map, Material},
+    shape::Shape,
+    Endpoint, Grid,
+};
+
+use crate::layout::Layout;
+
+/// What clicking on the grid does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    /// Paint a square of cells around the clicked one, also while dragging
+    Brush,
+    /// Convert the whole connected region of the clicked cell
+    Fill,
+    /// Log everything known about the clicked cell
+    Inspect,
+    /// Drag out a shape, painted once the button is released
+    Shape(Shape),
+}
+
+impl std::fmt::Display for Tool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tool::Brush => write!(f, "Brush"),
+            Tool::Fill => write!(f, "Fill"),
+            Tool::Inspect => write!(f, "Inspect"),
+            Tool::Shape(shape) => write!(f, "{}", shape),
+        }
+    }
+}
+
+/// Most edits that can be undone, older ones are forgotten
+const MAX_UNDO: usize = 100;
+
+/// Widths of the square the brush paints, in cells, smallest first
+const BRUSH_SIZES: [u32; 3] = [1, 3, 5];
+
+/// A change to the map made with a single press of a mouse button, that can be undone
+#[derive(Clone, Debug)]
+enum Edit {
+    /// Cells painted or filled, with their material before and after
+    Paint(Vec<((u32, u32), Material, Material)>),
+    /// A start or goal carried from one cell to another
+    Move {
+        endpoint: Endpoint,
+        from: (u32, u32),
+        to: (u32, u32),
+    },
+    /// A goal cell added to the main search's goal or taken out of it
+    ToggleGoal((u32, u32)),
+}
+
+/// The active tool and material, every mouse interaction with the grid, and the edits
+/// they made for undo and redo. Whether an edit restarts a search is up to the [`Grid`],
+/// never to the editor.
+#[derive(Clone, Debug)]
+pub struct Editor {
+    tool: Tool,
+    material: Material,
+    /// Width of the square of cells the brush paints, one of [`BRUSH_SIZES`]
+    brush_size: u32,
+    /// Whether rectangles and circles are filled rather than just outlined
+    filled: bool,
+    /// Material painted while the button that went down on the grid with the current tool
+    /// is still held, the eraser for the right button
+    dragging: Option<Material>,
+    /// Start or goal picked up with the left button, following the mouse until dropped
+    carrying: Option<Endpoint>,
+    /// Cell a shape is being dragged out from
+    shape_from: Option<(u32, u32)>,
+    /// Cell under the mouse
+    hover: Option<(u32, u32)>,
+
+    /// Material of every cell of the real map when the button went down, to tell what
+    /// the stroke painted once it is released
+    painted_over: Option<Grid2D<Option<Material>>>,
+    /// The start or goal being carried and the cell it was picked up from
+    picked_up: Option<(Endpoint, (u32, u32))>,
+    /// Edits made, the latest last, and edits undone since, the last undone last
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            tool: Tool::Brush,
+            material: Material::Obstacle,
+            brush_size: BRUSH_SIZES[0],
+            filled: true,
+            dragging: None,
+            carrying: None,
+            shape_from: None,
+            hover: None,
+            painted_over: None,
+            picked_up: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+}
+
+impl Editor {
+    pub fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    /// Switches to `tool`, cancelling any drag of the previous one. Shapes are filled or
+    /// not like the last one.
+    pub fn set_tool(&mut self, tool: Tool) {
+        let tool = match tool {
+            Tool::Shape(shape) => Tool::Shape(shape.with_filled(self.filled)),
+            tool => tool,
+        };
+
+        if tool != self.tool {
+            self.dragging = None;
+            self.carrying = None;
+            self.shape_from = None;
+        }
+
+        self.tool = tool;
+    }
+
+    /// Switches between filled and outlined rectangles and circles
+    pub fn toggle_filled(&mut self) {
+        self.filled = !self.filled;
+
+        if let Tool::Shape(shape) = self.tool {
+            self.tool = Tool::Shape(shape.with_filled(self.filled));
+        }
+    }
+
+    pub fn material(&self) -> Material {
+        self.material
+    }
+
+    /// Cell under the mouse, as of the last mouse event
+    pub fn hover(&self) -> Option<(u32, u32)> {
+        self.hover
+    }
+
+    /// Forgets the cell under the mouse, for when the cells move without the mouse moving
+    pub fn forget_hover(&mut self) {
+        self.hover = None;
+    }
+
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn brush_size(&self) -> u32 {
+        self.brush_size
+    }
+
+    /// Switches to the next smaller brush, staying at the smallest
+    pub fn shrink_brush(&mut self) {
+        self.brush_size = BRUSH_SIZES
+            .into_iter()
+            .rev()
+            .find(|&size| size < self.brush_size)
+            .unwrap_or(BRUSH_SIZES[0]);
+    }
+
+    /// Switches to the next larger brush, staying at the largest
+    pub fn grow_brush(&mut self) {
+        self.brush_size = BRUSH_SIZES
+            .into_iter()
+            .find(|&size| size > self.brush_size)
+            .unwrap_or(BRUSH_SIZES[BRUSH_SIZES.len() - 1]);
+    }
+
+    /// Cells the active tool paints with the mouse over `cell`: a square centered on it
+    /// for the brush, the shape dragged out so far up to it, otherwise just `cell`
+    pub fn footprint(&self, grid: &Grid, cell: (u32, u32)) -> Vec<(u32, u32)> {
+        match (self.tool, self.shape_from) {
+            (Tool::Brush, _) => self.brush_cells(grid, cell),
+            (Tool::Shape(shape), Some(from)) => on_layer(grid, from, shape.cells(from, cell)),
+            _ => vec![cell],
+        }
+    }
+
+    /// Cells the brush paints when it is over `cell`
+    fn brush_cells(&self, grid: &Grid, cell: (u32, u32)) -> Vec<(u32, u32)> {
+        let radius = (self.brush_size / 2) as i64;
+
+        let mut cells = Vec::new();
+        for dx in -radius..=radius {
+            for dy in -radius..=radius {
+                let (x, y) = (cell.0 as i64 + dx, cell.1 as i64 + dy);
+                if x >= 0 && y >= 0 {
+                    cells.push((x as u32, y as u32));
+                }
+            }
+        }
+
+        on_layer(grid, cell, cells)
+    }
+
+    /// Applies a mouse event to `grid` with the active tool, other events are ignored. The
+    /// left button uses the selected material, the right one erases. Starts and goals are
+    /// picked up with the left button instead, unless inspecting. The middle button adds
+    /// or removes goal cells of the main search.
+    pub fn handle_mouse(&mut self, event: &Event, layout: &Layout, grid: &mut Grid) {
+        match *event {
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                self.hover = layout.cell_at(x, y);
+
+                if let (MouseButton::Middle, Some(cell)) = (mouse_btn, self.hover) {
+                    if grid.toggle_goal(cell) {
+                        self.record(Edit::ToggleGoal(cell));
+                    }
+                    return;
+                }
+
+                let material = match mouse_btn {
+                    MouseButton::Left => self.material,
+                    MouseButton::Right => Material::Eraser,
+                    _ => return,
+                };
+
+                let Some(cell) = self.hover else {
+                    return;
+                };
+
+                if mouse_btn == MouseButton::Left && self.tool != Tool::Inspect {
+                    self.carrying = grid.endpoint_at(cell);
+
+                    if let Some(endpoint) = self.carrying {
+                        self.picked_up = Some((endpoint, cell));
+                        return;
+                    }
+                }
+
+                self.dragging = Some(material);
+                if self.tool != Tool::Inspect {
+                    self.painted_over = Some(materials(grid.world()));
+                }
+
+                match self.tool {
+                    Tool::Brush => grid.paint_cells(&self.brush_cells(grid, cell), material),
+                    Tool::Fill => grid.fill(cell, material),
+                    Tool::Inspect => grid.inspect(cell),
+                    Tool::Shape(_) => self.shape_from = Some(cell),
+                }
+            }
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left | MouseButton::Right,
+                x,
+                y,
+                ..
+            } => {
+                // a shape released off the grid ends where the mouse last was on it
+                let to = layout.cell_at(x, y).or(self.hover);
+
+                if let (Tool::Shape(shape), Some(from), Some(to), Some(material)) =
+                    (self.tool, self.shape_from.take(), to, self.dragging)
+                {
+                    grid.paint_cells(&on_layer(grid, from, shape.cells(from, to)), material);
+                }
+
+                self.dragging = None;
+                self.carrying = None;
+                self.finish_stroke(grid);
+            }
+            Event::MouseMotion { x, y, .. } => {
+                self.hover = layout.cell_at(x, y);
+
+                if let (Some(cell), Some(endpoint)) = (self.hover, self.carrying) {
+                    grid.move_endpoint(endpoint, cell);
+                    return;
+                }
+
+                if let (Some(cell), Some(material), Tool::Brush) =
+                    (self.hover, self.dragging, self.tool)
+                {
+                    grid.paint_cells(&self.brush_cells(grid, cell), material);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Records what the button that was just released changed
+    fn finish_stroke(&mut self, grid: &Grid) {
+        if let Some(before) = self.painted_over.take() {
+            let after = materials(grid.world());
+
+            let painted: Vec<_> = (0..after.width())
+                .flat_map(|x| (0..after.height()).map(move |y| (x, y)))
+                .filter_map(|cell| match (before.get(cell), after[cell]) {
+                    (Some(&Some(from)), Some(to)) if from != to => Some((cell, from, to)),
+                    _ => None,
+                })
+                .collect();
+
+            if !painted.is_empty() {
+                self.record(Edit::Paint(painted));
+            }
+        }
+
+        if let Some((endpoint, from)) = self.picked_up.take() {
+            match endpoint_cell(grid, endpoint) {
+                Some(to) if to != from => self.record(Edit::Move { endpoint, from, to }),
+                _ => (),
+            }
+        }
+    }
+
+    fn record(&mut self, edit: Edit) {
+        self.undo.push(edit);
+        self.redo.clear();
+
+        if self.undo.len() > MAX_UNDO {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Takes back the latest edit that wasn't undone yet
+    pub fn undo(&mut self, grid: &mut Grid) {
+        if let Some(edit) = self.undo.pop() {
+            apply(grid, &edit, true);
+            self.redo.push(edit);
+        }
+    }
+
+    /// Makes the edit undone last again
+    pub fn redo(&mut self, grid: &mut Grid) {
+        if let Some(edit) = self.redo.pop() {
+            apply(grid, &edit, false);
+            self.undo.push(edit);
+        }
+    }
+
+    /// Forgets every edit, for when the map was replaced and they no longer fit it
+    pub fn clear_history(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+/// `cells` that are on the map and on the same layer as `cell`, so nothing painted spills
+/// into another layer
+fn on_layer(grid: &Grid, cell: (u32, u32), cells: Vec<(u32, u32)>) -> Vec<(u32, u32)> {
+    let map = grid.map();
+    let layer = cell.1 / map.layer_height();
+
+    cells
+        .into_iter()
+        .filter(|&other| map.in_bounds(other) && other.1 / map.layer_height() == layer)
+        .collect()
+}
+
+/// Makes `edit` to `grid`, or with `undo` takes it back
+fn apply(grid: &mut Grid, edit: &Edit, undo: bool) {
+    match edit {
+        Edit::Paint(painted) => {
+            // a call per material, so searches restart once rather than once per cell
+            let mut by_material: Vec<(Material, Vec<(u32, u32)>)> = Vec::new();
+
+            for &(cell, from, to) in painted {
+                let material = if undo { from } else { to };
+
+                match by_material.iter_mut().find(|(other, _)| *other == material) {
+                    Some((_, cells)) => cells.push(cell),
+                    None => by_material.push((material, vec![cell])),
+                }
+            }
+
+            for (material, cells) in by_material {
+                grid.paint_cells(&cells, material);
+            }
+        }
+        &Edit::Move { endpoint, from, to } => {
+            grid.move_endpoint(endpoint, if undo { from } else { to });
+        }
+        &Edit::ToggleGoal(cell) => {
+            grid.toggle_goal(cell);
+        }
+    }
+}
+
+/// Material of every cell of `map`
+fn materials(map: &Map) -> Grid2D<Option<Material>> {
+    let mut materials = Grid2D::new(map.width(), map.height(), None);
+
+    for x in 0..map.width() {
+        for y in 0..map.height() {
+            materials[(x, y)] = map.material_at((x, y));
+        }
+    }
+
+    materials
+}
+
+/// Cell `endpoint` is at, `None` for goals that aren't a single cell
+fn endpoint_cell(grid: &Grid, endpoint: Endpoint) -> Option<(u32, u32)> {
+    match endpoint {
+        Endpoint::Start(agent) => Some(grid.searches().get(agent)?.start()),
+        Endpoint::Goal(agent) => match grid.searches().get(agent)?.goal() {
+            GoalSpec::Cell(cell) => Some(*cell),
+            _ => None,
+        },
+    }
+}
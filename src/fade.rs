@@ -0,0 +1,82 @@
+use std::{collections::HashMap, time::Duration};
+
+use sdl2::pixels::Color;
+
+use dijkstra_visual::grid2d::Grid2D;
+
+/// Most cells fading at once, further changes just snap to their new color
+const MAX_FADING: usize = 4096;
+
+/// Smooths color changes of cells: a cell whose color changes blends from the old color
+/// to the new one over `duration`. Only the cells currently fading are tracked.
+#[derive(Clone, Debug)]
+pub struct Fade {
+    duration: Duration,
+    /// Color every cell should end up in as of the last frame, `None` before its first
+    target: Grid2D<Option<Color>>,
+    /// Color every fading cell started from and when it started
+    fading: HashMap<(u32, u32), (Color, Duration)>,
+}
+
+impl Fade {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            target: Grid2D::default(),
+            fading: HashMap::new(),
+        }
+    }
+
+    /// Forgets finished fades, and everything if the grid changed size. `now` is any
+    /// monotonic clock, as long as it's the same for every call.
+    pub fn begin_frame(&mut self, w: u32, h: u32, now: Duration) {
+        if !self.target.fits(w, h) {
+            self.target = Grid2D::new(w, h, None);
+            self.fading.clear();
+        }
+
+        self.fading
+            .retain(|_, (_, since)| now.saturating_sub(*since) < self.duration);
+    }
+
+    /// Cells currently fading, which change color every frame
+    pub fn fading(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.fading.keys().copied()
+    }
+
+    /// Color to draw `cell` in, given the color its state asks for
+    pub fn color(&mut self, cell: (u32, u32), target: Color, now: Duration) -> Color {
+        let previous = self.target[cell].replace(target);
+
+        // the fast path, nothing changed and nothing is fading
+        if self.fading.is_empty() && previous.is_none_or(|previous| previous == target) {
+            return target;
+        }
+
+        if let Some(previous) = previous.filter(|&previous| previous != target) {
+            let shown = self.blend(cell, previous, now);
+
+            if self.fading.len() < MAX_FADING || self.fading.contains_key(&cell) {
+                self.fading.insert(cell, (shown, now));
+            }
+        }
+
+        self.blend(cell, target, now)
+    }
+
+    /// `target`, blended with the color `cell` fades from if it is fading
+    fn blend(&self, cell: (u32, u32), target: Color, now: Duration) -> Color {
+        let Some(&(from, since)) = self.fading.get(&cell) else {
+            return target;
+        };
+
+        let t = (now.saturating_sub(since).as_secs_f64() / self.duration.as_secs_f64()).min(1.0);
+        let channel = |from: u8, to: u8| (from as f64 + (to as f64 - from as f64) * t) as u8;
+
+        Color::RGB(
+            channel(from.r, target.r),
+            channel(from.g, target.g),
+            channel(from.b, target.b),
+        )
+    }
+}
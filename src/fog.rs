@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+
+use crate::{
+    grid2d::Grid2D,
+    map::{line, Map, Material},
+};
+
+/// An agent that only senses obstacles within `radius` steps of itself, diagonal ones
+/// included. The grid's map holds what it knows, cells it hasn't sensed yet are taken to
+/// be free, and the real map is kept here. It walks the path planned on what it knows and
+/// has to plan again once it senses a wall across it.
+#[derive(Clone, Debug)]
+pub struct Fog {
+    /// The real map
+    world: Map,
+    radius: u32,
+    sensed: Grid2D<bool>,
+    position: (u32, u32),
+    /// Every cell the agent walked through, from where it started
+    trail: Vec<(u32, u32)>,
+    walked: Grid2D<bool>,
+    /// Cells still to walk of the path it is following
+    route: VecDeque<(u32, u32)>,
+    replans: usize,
+}
+
+impl Fog {
+    /// Agent at `start` that hasn't sensed anything yet
+    pub fn new(world: Map, radius: u32, start: (u32, u32)) -> Self {
+        let sensed = Grid2D::new(world.width(), world.height(), false);
+        let mut walked = sensed.clone();
+        walked[start] = true;
+
+        Self {
+            world,
+            radius,
+            sensed,
+            position: start,
+            trail: vec![start],
+            walked,
+            route: VecDeque::new(),
+            replans: 0,
+        }
+    }
+
+    pub fn world(&self) -> &Map {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut Map {
+        &mut self.world
+    }
+
+    pub fn into_world(self) -> Map {
+        self.world
+    }
+
+    pub fn radius(&self) -> u32 {
+        self.radius
+    }
+
+    pub fn position(&self) -> (u32, u32) {
+        self.position
+    }
+
+    pub fn trail(&self) -> &[(u32, u32)] {
+        &self.trail
+    }
+
+    /// How often a wall the agent sensed made it plan again
+    pub fn replans(&self) -> usize {
+        self.replans
+    }
+
+    /// Whether the agent walked through `cell`
+    pub fn walked(&self, cell: (u32, u32)) -> bool {
+        self.walked[cell]
+    }
+
+    pub fn sensed(&self, cell: (u32, u32)) -> bool {
+        self.sensed.get(cell).copied().unwrap_or(false)
+    }
+
+    /// What the agent takes `cell` to be made of if it is painted with `material`:
+    /// obstacles it hasn't sensed yet are normal terrain to it
+    pub fn believed(&self, cell: (u32, u32), material: Material) -> Material {
+        match material {
+            Material::Obstacle if !self.sensed(cell) => Material::Eraser,
+            material => material,
+        }
+    }
+
+    /// The real map as far as the agent knows it
+    pub fn known_map(&self) -> Map {
+        let mut map = self.world.clone();
+
+        for x in 0..map.width() {
+            for y in 0..map.height() {
+                if let Some(material) = self.world.material_at((x, y)) {
+                    map.set_material((x, y), self.believed((x, y), material));
+                }
+            }
+        }
+
+        map
+    }
+
+    /// Senses every cell within the radius, a square around the agent, and where the
+    /// portals among them lead, returning the obstacles it didn't know about
+    pub fn sense(&mut self) -> Vec<(u32, u32)> {
+        let (x, y) = (self.position.0 as i64, self.position.1 as i64);
+        let r = self.radius as i64;
+
+        let mut cells = Vec::new();
+        for cx in (x - r).max(0)..=(x + r).min(self.world.width() as i64 - 1) {
+            for cy in (y - r).max(0)..=(y + r).min(self.world.height() as i64 - 1) {
+                cells.push((cx as u32, cy as u32));
+            }
+        }
+
+        let exits: Vec<_> = self
+            .world
+            .portals()
+            .iter()
+            .flat_map(|portal| cells.iter().filter_map(|&cell| portal.exit(cell)))
+            .collect();
+
+        let mut found = Vec::new();
+        for cell in cells.into_iter().chain(exits) {
+            if self.sensed(cell) {
+                continue;
+            }
+
+            self.sensed[cell] = true;
+            if self.world.is_obstacle(cell) {
+                found.push(cell);
+            }
+        }
+
+        found
+    }
+
+    /// Follows `path` from now on, a path from the agent's cell found on the `known` map.
+    /// Corners of Theta* paths get the cells between them filled in, portals are stepped
+    /// through.
+    pub fn follow(&mut self, known: &Map, path: &[(u32, u32)]) {
+        self.route = path
+            .windows(2)
+            .flat_map(|segment| match known.portal_cost(segment[0], segment[1]) {
+                Some(_) => vec![segment[1]],
+                None => line(segment[0], segment[1]).into_iter().skip(1).collect(),
+            })
+            .collect();
+    }
+
+    pub fn following(&self) -> bool {
+        !self.route.is_empty()
+    }
+
+    /// Whether a step of the rest of the route runs into an obstacle of `known`
+    pub fn blocked(&self, known: &Map) -> bool {
+        std::iter::once(&self.position)
+            .chain(&self.route)
+            .zip(&self.route)
+            .any(|(&from, &to)| known.is_obstacle(to) || !known.can_step(from, to))
+    }
+
+    /// Takes the next step along the route, returns whether there was one
+    pub fn step(&mut self) -> bool {
+        let Some(next) = self.route.pop_front() else {
+            return false;
+        };
+
+        self.position = next;
+        self.trail.push(next);
+        self.walked[next] = true;
+        true
+    }
+
+    /// Drops the route, to plan a new one from where the agent is
+    pub fn replan(&mut self) {
+        self.route.clear();
+        self.replans += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Walks the route, sensing after every step and planning again like the grid does
+    /// whenever a sensed wall blocks it, until the route runs out
+    fn walk(fog: &mut Fog, known: &mut Map) {
+        while fog.step() {
+            for cell in fog.sense() {
+                known.set_material(cell, Material::Obstacle);
+            }
+
+            if fog.blocked(known) {
+                fog.replan();
+            }
+        }
+    }
+
+    #[test]
+    fn walls_sensed_across_the_route_make_the_agent_plan_again_once() {
+        let mut world = Map::new(9, 3);
+        world.set_material((5, 1), Material::Obstacle);
+
+        let mut fog = Fog::new(world, 1, (0, 1));
+        assert!(fog.sense().is_empty());
+        let mut known = fog.known_map();
+        assert!(!known.is_obstacle((5, 1)));
+
+        let straight: Vec<_> = (0..9).map(|x| (x, 1)).collect();
+        fog.follow(&known, &straight);
+        walk(&mut fog, &mut known);
+
+        // stopped as soon as the wall came into view
+        assert_eq!(fog.replans(), 1);
+        assert_eq!(fog.position(), (4, 1));
+        assert!(fog.sensed((5, 1)) && known.is_obstacle((5, 1)));
+
+        let around = [(4, 1), (4, 0), (5, 0), (6, 0), (6, 1), (7, 1), (8, 1)];
+        fog.follow(&known, &around);
+        walk(&mut fog, &mut known);
+
+        assert_eq!(fog.replans(), 1);
+        assert_eq!(fog.position(), (8, 1));
+        assert_eq!(fog.trail().len(), 11);
+        assert!(fog.walked((5, 0)) && !fog.walked((5, 1)));
+    }
+}
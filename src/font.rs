@@ -0,0 +1,143 @@
+use std::path::{Path, PathBuf};
+
+use sdl2::{
+    rwops::RWops,
+    ttf::{Font, Sdl2TtfContext},
+};
+
+/// DejaVu Sans Mono, built into the binary so text shows without any fonts installed.
+/// Its license is next to it in `assets/`.
+const EMBEDDED: &[u8] = include_bytes!("../assets/DejaVuSansMono.ttf");
+
+/// How deep into the font directories fonts are looked for
+const MAX_SEARCH_DEPTH: usize = 4;
+
+/// Where text is drawn from
+#[derive(Clone, Debug)]
+pub enum FontSource {
+    Embedded,
+    File(PathBuf),
+}
+
+impl FontSource {
+    /// The font `--font` names: a path to a font file, or the file name or family of an
+    /// installed font, looked up with fontconfig where there is one and in the usual font
+    /// directories otherwise. `None` is the embedded font.
+    pub fn find(name: Option<&str>) -> Result<Self, String> {
+        let Some(name) = name else {
+            return Ok(FontSource::Embedded);
+        };
+
+        if Path::new(name).is_file() {
+            return Ok(FontSource::File(name.into()));
+        }
+
+        fontconfig(name)
+            .or_else(|| {
+                font_dirs()
+                    .iter()
+                    .find_map(|dir| search(dir, name, MAX_SEARCH_DEPTH))
+            })
+            .map(FontSource::File)
+            .ok_or_else(|| {
+                format!(
+                    "no font {:?} found, pass the path to a .ttf or .otf file with --font",
+                    name
+                )
+            })
+    }
+
+    /// The font at `point_size`
+    pub fn load<'ttf>(
+        &self,
+        ttf: &'ttf Sdl2TtfContext,
+        point_size: u16,
+    ) -> Result<Font<'ttf, 'static>, String> {
+        match self {
+            FontSource::Embedded => RWops::from_bytes(EMBEDDED)
+                .and_then(|bytes| ttf.load_font_from_rwops(bytes, point_size)),
+            FontSource::File(path) => ttf
+                .load_font(path, point_size)
+                .map_err(|e| format!("couldn't load font {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// File of the installed font whose family or file name is `name`, if fontconfig is
+/// installed and knows one
+fn fontconfig(name: &str) -> Option<PathBuf> {
+    let output = std::process::Command::new("fc-list")
+        .args([name, "--format", "%{file}\n"])
+        .output()
+        .ok()?;
+
+    let files = String::from_utf8(output.stdout).ok()?;
+    files.lines().next().map(PathBuf::from)
+}
+
+/// Directories fonts are installed to on this system, for all users and the current one
+fn font_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    let mut dirs: Vec<PathBuf> = if cfg!(target_os = "windows") {
+        let windows = std::env::var_os("WINDIR").unwrap_or_else(|| "C:\\Windows".into());
+        let mut dirs = vec![PathBuf::from(windows).join("Fonts")];
+
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            dirs.push(PathBuf::from(local).join("Microsoft\\Windows\\Fonts"));
+        }
+
+        return dirs;
+    } else if cfg!(target_os = "macos") {
+        vec!["/System/Library/Fonts".into(), "/Library/Fonts".into()]
+    } else {
+        vec!["/usr/share/fonts".into(), "/usr/local/share/fonts".into()]
+    };
+
+    if let Some(home) = home {
+        if cfg!(target_os = "macos") {
+            dirs.push(home.join("Library/Fonts"));
+        } else {
+            dirs.push(home.join(".local/share/fonts"));
+            dirs.push(home.join(".fonts"));
+        }
+    }
+
+    dirs
+}
+
+/// A font file in `dir` or its subdirectories, `depth` levels deep at most, called `name`
+/// with or without the extension, ignoring case
+fn search(dir: &Path, name: &str, depth: usize) -> Option<PathBuf> {
+    let name = name.to_lowercase();
+    let mut subdirs = Vec::new();
+
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+            continue;
+        }
+
+        let is_font = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| ["ttf", "otf"].contains(&&*extension.to_lowercase()));
+        let matches = [path.file_name(), path.file_stem()]
+            .into_iter()
+            .flatten()
+            .any(|file| file.to_string_lossy().to_lowercase() == name);
+
+        if is_font && matches {
+            return Some(path);
+        }
+    }
+
+    if depth == 0 {
+        return None;
+    }
+
+    subdirs
+        .iter()
+        .find_map(|subdir| search(subdir, &name, depth - 1))
+}
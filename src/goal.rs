@@ -0,0 +1,135 @@
+use crate::map::Map;
+
+/// What a search has to reach, any single cell of the region will do
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalSpec {
+    Cell((u32, u32)),
+    /// `w` x `h` cells with the top left one at `x`, `y`
+    Rect {
+        x: u32,
+        y: u32,
+        w: u32,
+        h: u32,
+    },
+    /// Every cell with this x
+    Column(u32),
+    /// Every cell with this y
+    Row(u32),
+    /// Several single cells, whichever is reached first wins
+    Cells(Vec<(u32, u32)>),
+}
+
+impl GoalSpec {
+    pub fn contains(&self, cell: (u32, u32)) -> bool {
+        match *self {
+            GoalSpec::Cell(goal) => cell == goal,
+            GoalSpec::Rect { x, y, w, h } => {
+                (x..x.saturating_add(w)).contains(&cell.0)
+                    && (y..y.saturating_add(h)).contains(&cell.1)
+            }
+            GoalSpec::Column(x) => cell.0 == x,
+            GoalSpec::Row(y) => cell.1 == y,
+            GoalSpec::Cells(ref cells) => cells.contains(&cell),
+        }
+    }
+
+    /// Distance along x and y from `cell` to the nearest cell of the region, in cells
+    pub fn offset(&self, cell: (u32, u32)) -> (u32, u32) {
+        // distance along one axis to the range `min..=max`
+        let axis = |v: u32, min: u32, max: u32| {
+            if v < min {
+                min - v
+            } else {
+                v.saturating_sub(max)
+            }
+        };
+
+        match *self {
+            GoalSpec::Cell(goal) => (cell.0.abs_diff(goal.0), cell.1.abs_diff(goal.1)),
+            GoalSpec::Rect { x, y, w, h } => (
+                axis(cell.0, x, x.saturating_add(w.max(1) - 1)),
+                axis(cell.1, y, y.saturating_add(h.max(1) - 1)),
+            ),
+            GoalSpec::Column(x) => (cell.0.abs_diff(x), 0),
+            GoalSpec::Row(y) => (0, cell.1.abs_diff(y)),
+            GoalSpec::Cells(ref cells) => cells
+                .iter()
+                .map(|goal| (cell.0.abs_diff(goal.0), cell.1.abs_diff(goal.1)))
+                .min_by_key(|&(dx, dy)| dx as u64 * dx as u64 + dy as u64 * dy as u64)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Euclidean distance from `cell` to the nearest cell of the region, in cells
+    pub fn distance(&self, cell: (u32, u32)) -> f64 {
+        let (dx, dy) = self.offset(cell);
+
+        ((dx as f64).powi(2) + (dy as f64).powi(2)).sqrt()
+    }
+
+    /// Every cell of the region that lies inside `map`
+    pub fn cells(&self, map: &Map) -> Vec<(u32, u32)> {
+        let (x, y, w, h) = match *self {
+            GoalSpec::Cell(goal) => (goal.0, goal.1, 1, 1),
+            GoalSpec::Rect { x, y, w, h } => (x, y, w, h),
+            GoalSpec::Column(x) => (x, 0, 1, map.height()),
+            GoalSpec::Row(y) => (0, y, map.width(), 1),
+            GoalSpec::Cells(ref cells) => {
+                return cells
+                    .iter()
+                    .copied()
+                    .filter(|&cell| map.in_bounds(cell))
+                    .collect();
+            }
+        };
+
+        (x..x.saturating_add(w).min(map.width()))
+            .flat_map(|x| (y..y.saturating_add(h).min(map.height())).map(move |y| (x, y)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECT: GoalSpec = GoalSpec::Rect {
+        x: 10,
+        y: 20,
+        w: 3,
+        h: 2,
+    };
+
+    #[test]
+    fn rects_contain_their_cells() {
+        assert!(RECT.contains((10, 20)));
+        assert!(RECT.contains((12, 21)));
+        assert!(!RECT.contains((13, 21)));
+        assert!(!RECT.contains((12, 22)));
+        assert!(!RECT.contains((9, 20)));
+    }
+
+    #[test]
+    fn offsets_are_to_the_nearest_cell_of_rects() {
+        assert_eq!(RECT.offset((11, 20)), (0, 0));
+        assert_eq!(RECT.offset((0, 0)), (10, 20));
+        assert_eq!(RECT.offset((20, 25)), (8, 4));
+    }
+
+    #[test]
+    fn rects_reaching_past_the_largest_coordinate_dont_overflow() {
+        let huge = GoalSpec::Rect {
+            x: u32::MAX - 1,
+            y: 5,
+            w: u32::MAX,
+            h: u32::MAX,
+        };
+
+        assert!(huge.contains((u32::MAX - 1, u32::MAX - 1)));
+        assert!(!huge.contains((3, 5)));
+        assert_eq!(huge.offset((u32::MAX - 4, 0)), (3, 5));
+        assert_eq!(huge.offset((u32::MAX, u32::MAX)), (0, 0));
+        assert!(huge.cells(&Map::new(4, 4)).is_empty());
+    }
+}
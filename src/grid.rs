@@ -0,0 +1,1382 @@
+use std::path::Path;
+
+use crate::{
+    ascii::AsciiMap,
+    changes::ChangeLog,
+    fog::Fog,
+    goal::GoalSpec,
+    grid2d::Grid2D,
+    map::{line, Map, Material, Portal, Topology, MAX_COST, UNIT_COST},
+    maze::{self, MazeKind},
+    rng::Rng,
+    search::{format_dist, Algorithm, Heuristic, Search, SearchStatus},
+    shape::Shape,
+    terrain::{self, TerrainKind},
+};
+
+/// Maps with more cells than this skip the connectivity check of [`Grid::summary`]
+const MAX_SUMMARY_CELLS: u64 = 4_000_000;
+
+/// Overview of the map and the main search on it, to tell at a glance whether the
+/// search can succeed at all
+#[derive(Clone, Copy, Debug)]
+pub struct MapSummary {
+    pub width: u32,
+    pub height: u32,
+    pub obstacles: usize,
+    /// Number of 4-connected regions of free cells, `None` if the map was too big to check
+    pub components: Option<usize>,
+    /// Whether the main start and goal share a region, `None` if not checked
+    pub connected: Option<bool>,
+}
+
+impl std::fmt::Display for MapSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cells = (self.width as usize * self.height as usize).max(1);
+
+        write!(
+            f,
+            "{}x{}, {} obstacles ({:.1}%)",
+            self.width,
+            self.height,
+            self.obstacles,
+            self.obstacles as f64 / cells as f64 * 100.0
+        )?;
+
+        if let Some(components) = self.components {
+            write!(f, ", {} free regions", components)?;
+        }
+
+        match self.connected {
+            Some(true) => write!(f, ", goal reachable"),
+            Some(false) => write!(f, ", goal NOT reachable"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Start or single goal cell of an agent, by the agent's index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    Start(usize),
+    Goal(usize),
+}
+
+/// The map together with the searches of every agent running on it. Serializing it keeps
+/// the map and where every agent searches from and to, not how far they got.
+#[derive(Debug)]
+pub struct Grid {
+    map: Map,
+    /// One independent search per agent, the first one is the main search
+    searches: Vec<Search>,
+    /// The main agent only knowing the map around where it walked, `map` is what it
+    /// knows then
+    fog: Option<Fog>,
+    /// Cells whose material changed, in either map, or the agent in the fog walked from
+    /// or to
+    changes: ChangeLog,
+}
+
+impl Grid {
+    pub fn new(w: u32, h: u32, start: (u32, u32), goal: GoalSpec, algorithm: Algorithm) -> Self {
+        let map = Map::new(w, h);
+        let search = Search::new(&map, start, goal, algorithm);
+
+        Self {
+            map,
+            searches: vec![search],
+            fog: None,
+            changes: ChangeLog::default(),
+        }
+    }
+
+    /// A grid on a copy of the map with the same agents, searching with `algorithm`
+    /// instead, to compare the two. Without the fog, a copy can't know less than this one.
+    pub fn with_algorithm(&self, algorithm: Algorithm) -> Grid {
+        let searches = self
+            .searches
+            .iter()
+            .map(|other| {
+                let mut search =
+                    Search::new(&self.map, other.start(), other.goal().clone(), algorithm);
+                search.set_diagonals(&self.map, other.diagonals());
+                search.set_heuristic_weight(&self.map, other.heuristic_weight());
+                search.set_heuristic_kind(&self.map, other.heuristic_kind());
+                search.set_flow_field(&self.map, other.flow_field());
+                if !other.extra_starts().is_empty() {
+                    search.set_extra_starts(&self.map, other.extra_starts().to_vec());
+                }
+                search
+            })
+            .collect();
+
+        Self {
+            map: self.map.clone(),
+            searches,
+            fog: None,
+            changes: ChangeLog::default(),
+        }
+    }
+
+    /// Whether `other` runs on the same map with the same agents moving the same way,
+    /// whatever the algorithm is
+    pub fn same_setup(&self, other: &Grid) -> bool {
+        self.map == other.map
+            && self.searches.len() == other.searches.len()
+            && self.searches.iter().zip(&other.searches).all(|(a, b)| {
+                a.start() == b.start()
+                    && a.goal() == b.goal()
+                    && a.extra_starts() == b.extra_starts()
+                    && a.diagonals() == b.diagonals()
+                    && a.heuristic_weight() == b.heuristic_weight()
+                    && a.heuristic_kind() == b.heuristic_kind()
+                    && a.flow_field() == b.flow_field()
+            })
+    }
+
+    /// Adds another agent searching from `start` to `goal` on the same map, moving like
+    /// the main one
+    pub fn add_agent(&mut self, start: (u32, u32), goal: (u32, u32)) {
+        let main = &self.searches[0];
+
+        let mut search = Search::new(&self.map, start, GoalSpec::Cell(goal), main.algorithm());
+        search.set_diagonals(&self.map, main.diagonals());
+        search.set_heuristic_weight(&self.map, main.heuristic_weight());
+        search.set_heuristic_kind(&self.map, main.heuristic_kind());
+        search.set_flow_field(&self.map, main.flow_field());
+
+        self.searches.push(search);
+    }
+
+    /// Reads a grid saved with [`Self::save`], its searches start from scratch
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read grid {}: {}", path.display(), e))?;
+
+        serde_json::from_str(&text).map_err(|e| format!("invalid grid {}: {}", path.display(), e))
+    }
+
+    /// Writes the grid as JSON for [`Self::load`], or as a text map like
+    /// [`AsciiMap`] reads if `path` ends in `.txt`, which only keeps the map, start and goal
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = if path.extension().is_some_and(|extension| extension == "txt") {
+            AsciiMap::from_grid(self)
+                .map_err(|e| format!("couldn't save grid {} as text: {}", path.display(), e))?
+                .to_text()
+        } else {
+            serde_json::to_string(self).expect("grids always serialize")
+        };
+
+        std::fs::write(path, text)
+            .map_err(|e| format!("couldn't write grid {}: {}", path.display(), e))
+    }
+
+    /// Writes the main search's found path to `path`, every cell with the cost of getting
+    /// there from the start, costs in steps. Theta* paths only list their corners. The file
+    /// is CSV with an `x,y,cost` header if `path` ends in `.csv`, JSON otherwise, which
+    /// also lists every cell the search settled as `settle_order`, in the order it did.
+    pub fn export_path(&self, path: &Path) -> Result<(), String> {
+        let search = &self.searches[0];
+        let (Some(total), cells) = (search.path_cost(), search.path()) else {
+            return Err("no path found yet".to_string());
+        };
+
+        let mut cost = 0;
+        let costs = std::iter::once(0).chain(cells.windows(2).map(|step| {
+            cost += match search.algorithm() {
+                Algorithm::Theta => self.map.segment_cost(step[0], step[1]) as i64,
+                Algorithm::BellmanFord => self.map.signed_step_cost(step[0], step[1]),
+                Algorithm::Dijkstra
+                | Algorithm::AStar
+                | Algorithm::Bfs
+                | Algorithm::Dfs
+                | Algorithm::Bidirectional
+                | Algorithm::BidirectionalAStar
+                | Algorithm::Jps
+                | Algorithm::DStarLite
+                | Algorithm::Lpa
+                | Algorithm::Ida
+                | Algorithm::FloydWarshall => self.map.step_cost(step[0], step[1]) as i64,
+            };
+            cost
+        }));
+        let steps = |cost: i64| cost as f64 / UNIT_COST as f64;
+
+        let text = if path.extension().is_some_and(|extension| extension == "csv") {
+            let mut csv = String::from("x,y,cost\n");
+            for (&(x, y), cost) in cells.iter().zip(costs) {
+                csv.push_str(&format!("{},{},{}\n", x, y, steps(cost)));
+            }
+            csv
+        } else {
+            let cells: Vec<_> = cells
+                .iter()
+                .zip(costs)
+                .map(|(&(x, y), cost)| serde_json::json!({ "x": x, "y": y, "cost": steps(cost) }))
+                .collect();
+
+            serde_json::json!({
+                "cost": steps(total),
+                "path": cells,
+                "settle_order": search.settle_order(),
+            })
+            .to_string()
+        };
+
+        std::fs::write(path, text)
+            .map_err(|e| format!("couldn't write path {}: {}", path.display(), e))
+    }
+
+    /// Switches every search to `algorithm`, restarting them
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) -> &mut Grid {
+        for search in self.searches.iter_mut() {
+            search.set_algorithm(&self.map, algorithm);
+        }
+
+        self
+    }
+
+    /// Multiplies the heuristic of every search by `weight`, restarting them
+    pub fn set_heuristic_weight(&mut self, weight: f64) -> &mut Grid {
+        for search in self.searches.iter_mut() {
+            search.set_heuristic_weight(&self.map, weight);
+        }
+
+        self
+    }
+
+    /// Measures the heuristic of every search like `kind`, see
+    /// [`Search::set_heuristic_kind`]. Restarts them.
+    pub fn set_heuristic_kind(&mut self, kind: Option<Heuristic>) -> &mut Grid {
+        for search in self.searches.iter_mut() {
+            search.set_heuristic_kind(&self.map, kind);
+        }
+
+        self
+    }
+
+    /// Lets every search go on past the goal until every cell it can reach has a distance,
+    /// see [`Search::set_flow_field`]. Restarts them.
+    pub fn set_flow_field(&mut self, flow_field: bool) -> &mut Grid {
+        for search in self.searches.iter_mut() {
+            search.set_flow_field(&self.map, flow_field);
+        }
+
+        self
+    }
+
+    /// Lets the main search grow from more cells at once, see [`Search::set_extra_starts`].
+    /// Restarts it.
+    pub fn set_extra_starts(&mut self, starts: Vec<(u32, u32)>) -> &mut Grid {
+        self.searches[0].set_extra_starts(&self.map, starts);
+        self
+    }
+
+    /// Changes the shape of the cells and with it which ones are neighbors, restarting
+    /// every search
+    pub fn set_topology(&mut self, topology: Topology) -> &mut Grid {
+        self.map.set_topology(topology);
+        if let Some(fog) = &mut self.fog {
+            fog.world_mut().set_topology(topology);
+        }
+        self.clear_search();
+        self
+    }
+
+    /// Splits the rows into `layers` equally high layers, restarting every search. Returns
+    /// whether they could be.
+    pub fn set_layers(&mut self, layers: u32) -> bool {
+        if !self.map.set_layers(layers) {
+            return false;
+        }
+
+        if let Some(fog) = &mut self.fog {
+            fog.world_mut().set_layers(layers);
+        }
+        self.clear_search();
+        true
+    }
+
+    /// Adds stairs from `cell` down to the layer below, see [`Map::stairs`]. Returns
+    /// whether there was a layer below.
+    pub fn add_stairs(&mut self, cell: (u32, u32)) -> bool {
+        self.map
+            .stairs(cell)
+            .is_some_and(|stairs| self.add_portal(stairs))
+    }
+
+    /// Adds `count` stairs between every two layers, at random cells that are free on
+    /// both
+    pub fn scatter_stairs(&mut self, count: usize, rng: &mut Rng) {
+        let layer_height = self.map.layer_height();
+
+        for layer in 0..self.map.layers().saturating_sub(1) {
+            let mut cells: Vec<_> = (0..self.width())
+                .flat_map(|x| (0..layer_height).map(move |y| (x, layer * layer_height + y)))
+                .filter(|&(x, y)| {
+                    !self.world().is_obstacle((x, y))
+                        && !self.world().is_obstacle((x, y + layer_height))
+                })
+                .collect();
+            rng.shuffle(&mut cells);
+
+            for cell in cells.into_iter().take(count) {
+                self.add_stairs(cell);
+            }
+        }
+    }
+
+    /// Connects two cells with a portal, restarting every search. Returns whether it was
+    /// added, not if either end is outside of the map, both are the same cell or it costs
+    /// nothing.
+    pub fn add_portal(&mut self, portal: Portal) -> bool {
+        if let Some(fog) = &mut self.fog {
+            fog.world_mut().add_portal(portal);
+        }
+
+        if !self.map.add_portal(portal) {
+            return false;
+        }
+
+        self.clear_search();
+        true
+    }
+
+    /// Lets every grid search step diagonally too, restarting them
+    pub fn set_diagonals(&mut self, diagonals: bool) -> &mut Grid {
+        for search in self.searches.iter_mut() {
+            search.set_diagonals(&self.map, diagonals);
+        }
+
+        self
+    }
+
+    /// Whether diagonal moves may squeeze between two obstacles touching at a corner.
+    /// Restarts every search, since that changes which cells are neighbors.
+    pub fn set_corner_cutting(&mut self, allow: bool) -> &mut Grid {
+        self.map.set_corner_cutting(allow);
+        if let Some(fog) = &mut self.fog {
+            fog.world_mut().set_corner_cutting(allow);
+        }
+        self.clear_search();
+        self
+    }
+
+    /// The map as the searches see it, with the fog only what the main agent knows of it
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    /// The real map, which only differs from [`Self::map`] with the fog
+    pub fn world(&self) -> &Map {
+        self.fog.as_ref().map_or(&self.map, Fog::world)
+    }
+
+    pub fn fog(&self) -> Option<&Fog> {
+        self.fog.as_ref()
+    }
+
+    /// Lets the main agent only sense obstacles within `radius` cells of itself, and walk
+    /// to the goal planning with what it sensed so far, or lifts the fog with `None`.
+    /// Restarts every search.
+    pub fn set_fog(&mut self, radius: Option<u32>) -> &mut Grid {
+        if let Some(fog) = self.fog.take() {
+            self.map = fog.into_world();
+        }
+
+        if let Some(radius) = radius {
+            let start = self.searches[0].start();
+            self.fog = Some(Fog::new(self.map.clone(), radius, start));
+        }
+
+        self.clear_search();
+        self
+    }
+
+    /// Puts the agent in the fog on `start` knowing nothing but what it senses there, and
+    /// restarts every search, the main one from there
+    fn restart_fog(&mut self, start: (u32, u32)) {
+        let Some(fog) = self.fog.take() else {
+            return;
+        };
+
+        let radius = fog.radius();
+        let mut fog = Fog::new(fog.into_world(), radius, start);
+        fog.sense();
+
+        self.map = fog.known_map();
+        self.fog = Some(fog);
+        self.changes.forget();
+        self.searches[0].set_start(&self.map, start);
+
+        // what they knew of the map is gone
+        for search in self.searches.iter_mut() {
+            search.clear(&self.map);
+        }
+    }
+
+    /// Moves the agent in the fog a cell along the main search's path once it found one,
+    /// sensing around it after every step. Walls it senses across the rest of the path
+    /// make it plan again from where it is.
+    fn walk(&mut self) {
+        let Some(fog) = &mut self.fog else {
+            return;
+        };
+        let search = &mut self.searches[0];
+
+        if search.status() != SearchStatus::Found {
+            // the map changed and the search started over, from where it last planned
+            if search.start() != fog.position() {
+                fog.replan();
+                search.set_start(&self.map, fog.position());
+            }
+            return;
+        }
+
+        if search.goal().contains(fog.position()) {
+            return;
+        }
+
+        if !fog.following() {
+            fog.follow(&self.map, search.path());
+        }
+
+        let from = fog.position();
+        if !fog.step() {
+            return;
+        }
+
+        self.changes.push(from);
+        self.changes.push(fog.position());
+        if search.goal().contains(fog.position()) {
+            return;
+        }
+
+        let sensed = fog.sense();
+        self.apply_material(&sensed, Material::Obstacle);
+
+        let Some(fog) = &mut self.fog else {
+            return;
+        };
+        let search = &mut self.searches[0];
+
+        if search.status() != SearchStatus::Found || fog.blocked(&self.map) {
+            fog.replan();
+            search.set_start(&self.map, fog.position());
+        }
+    }
+
+    /// Every agent's search, the main one first
+    pub fn searches(&self) -> &[Search] {
+        &self.searches
+    }
+
+    /// Cells whose material or whether the agent in the fog stands on them changed. What
+    /// the searches changed is in [`Search::changes`].
+    pub fn changes(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    /// String-pulls `path`: every waypoint that can be skipped by walking in a straight
+    /// line past it is dropped. Only obstacles are considered, terrain costs aren't.
+    pub fn smooth_path(&self, path: &[(u32, u32)]) -> Vec<(u32, u32)> {
+        let (Some(&first), Some(&last)) = (path.first(), path.last()) else {
+            return Vec::new();
+        };
+
+        let mut smoothed = vec![first];
+
+        for window in path.windows(2).skip(1) {
+            let (corner, next) = (window[0], window[1]);
+
+            if !self.map.line_of_sight(*smoothed.last().unwrap(), next) {
+                smoothed.push(corner);
+            }
+        }
+
+        if path.len() > 1 {
+            smoothed.push(last);
+        }
+
+        smoothed
+    }
+
+    pub fn set_width(&mut self, w: u32) -> &mut Grid {
+        self.map.set_width(w);
+        if let Some(fog) = &mut self.fog {
+            fog.world_mut().set_width(w);
+        }
+        self.clear_search();
+        self
+    }
+
+    pub fn width(&self) -> u32 {
+        self.map.width()
+    }
+
+    pub fn set_height(&mut self, h: u32) -> &mut Grid {
+        self.map.set_height(h);
+        if let Some(fog) = &mut self.fog {
+            fog.world_mut().set_height(h);
+        }
+        self.clear_search();
+        self
+    }
+
+    pub fn height(&self) -> u32 {
+        self.map.height()
+    }
+
+    /// Status of the main search
+    pub fn status(&self) -> SearchStatus {
+        let search = &self.searches[0];
+
+        match (search.status(), &self.fog) {
+            // the agent in the fog still has to walk there
+            (SearchStatus::Found, Some(fog)) if !search.goal().contains(fog.position()) => {
+                SearchStatus::Running
+            }
+            (status, _) => status,
+        }
+    }
+
+    /// Cost of the main search's path, once it found one
+    pub fn path_cost(&self) -> Option<i64> {
+        self.searches[0].path_cost()
+    }
+
+    /// Size of the main search's open set
+    pub fn open_set_size(&self) -> usize {
+        self.searches[0].open_set_size()
+    }
+
+    /// Summarizes the map and whether the main search's goal can be reached at all
+    pub fn summary(&self) -> MapSummary {
+        let mut summary = MapSummary {
+            width: self.width(),
+            height: self.height(),
+            obstacles: self.world().obstacle_count(),
+            components: None,
+            connected: None,
+        };
+
+        if self.width() as u64 * self.height() as u64 > MAX_SUMMARY_CELLS {
+            return summary;
+        }
+
+        let (components, labels) = self.world().components();
+        let search = &self.searches[0];
+        let component = |cell: (u32, u32)| labels[cell];
+
+        summary.components = Some(components);
+        summary.connected = Some(
+            component(search.start()).is_some()
+                && search
+                    .goal()
+                    .cells(&self.map)
+                    .into_iter()
+                    .any(|cell| component(cell) == component(search.start())),
+        );
+
+        summary
+    }
+
+    /// Cost of the shortest path the main search could have found, from a reference
+    /// dijkstra run from scratch on the same map, or Bellman-Ford if that is what the main
+    /// search runs, since only it sees negative terrain. JPS's reference doesn't cut corners
+    /// either, JPS never does. `None` if there is no path at all, or no shortest one because
+    /// of a negative cycle.
+    pub fn shortest_cost(&self) -> Option<i64> {
+        let search = &self.searches[0];
+
+        let algorithm = match search.algorithm() {
+            Algorithm::BellmanFord => Algorithm::BellmanFord,
+            _ => Algorithm::Dijkstra,
+        };
+
+        // JPS never cuts corners, whatever the map allows
+        let jps = search.algorithm() == Algorithm::Jps;
+        let uncut;
+        let map = if jps && self.map.corner_cutting() {
+            let mut map = self.map.clone();
+            map.set_corner_cutting(false);
+            uncut = map;
+            &uncut
+        } else {
+            &self.map
+        };
+
+        // a fresh search on the same map leaves the drawn ones alone
+        let mut reference = Search::new(map, search.start(), search.goal().clone(), algorithm);
+        reference.set_diagonals(map, search.diagonals() || jps);
+        if search.algorithm().uses_extra_starts() {
+            reference.set_extra_starts(map, search.extra_starts().to_vec());
+        }
+        reference.run_to_completion(map);
+
+        reference.path_cost()
+    }
+
+    /// Checks the main search's path: every step has to be possible, and its cost must
+    /// match that of a reference search run from scratch on the same map, see
+    /// [`Self::shortest_cost`]. Theta* paths only have to be possible, a straight line
+    /// weighted by its worst terrain can cost more than the shortest grid path.
+    pub fn verify(&self) -> Result<(), String> {
+        let search = &self.searches[0];
+
+        let (Some(cost), Some(&first), Some(&last)) = (
+            search.path_cost(),
+            search.path().first(),
+            search.path().last(),
+        ) else {
+            return Err("no path to verify".into());
+        };
+
+        if let Some((_, _, scale)) = search
+            .all_pairs_progress()
+            .filter(|&(_, _, scale)| scale > 1)
+        {
+            return Err(format!(
+                "Floyd-Warshall ran on blocks of {0}x{0} cells, its path has just one per block",
+                scale
+            ));
+        }
+
+        if !search.is_start(first) || !search.goal().contains(last) {
+            return Err(format!("path runs from {:?} to {:?}", first, last));
+        }
+
+        if let Some(&cell) = search
+            .path()
+            .iter()
+            .find(|&&cell| self.map.is_obstacle(cell))
+        {
+            return Err(format!("path crosses the obstacle {:?}", cell));
+        }
+
+        // JPS always moves diagonally
+        let diagonals = search.diagonals() || search.algorithm() == Algorithm::Jps;
+
+        for step in search.path().windows(2) {
+            // portals connect cells however far apart
+            if self.map.portal_cost(step[0], step[1]).is_some() {
+                continue;
+            }
+
+            if self.map.topology() == Topology::Triangle {
+                if !self.map.get_neighbors(step[0]).contains(&step[1]) {
+                    return Err(format!("path jumps from {:?} to {:?}", step[0], step[1]));
+                }
+                continue;
+            }
+
+            let valid = match search.algorithm() {
+                // only the waypoints are stored, they just have to see each other
+                Algorithm::Theta => self.map.line_of_sight(step[0], step[1]),
+                Algorithm::Dijkstra
+                | Algorithm::AStar
+                | Algorithm::Bfs
+                | Algorithm::Dfs
+                | Algorithm::Bidirectional
+                | Algorithm::BidirectionalAStar
+                | Algorithm::Jps
+                | Algorithm::DStarLite
+                | Algorithm::Lpa
+                | Algorithm::Ida
+                | Algorithm::BellmanFord
+                | Algorithm::FloydWarshall
+                    if diagonals =>
+                {
+                    step[0]
+                        .0
+                        .abs_diff(step[1].0)
+                        .max(step[0].1.abs_diff(step[1].1))
+                        == 1
+                        && self.map.can_step(step[0], step[1])
+                }
+                Algorithm::Dijkstra
+                | Algorithm::AStar
+                | Algorithm::Bfs
+                | Algorithm::Dfs
+                | Algorithm::Bidirectional
+                | Algorithm::BidirectionalAStar
+                | Algorithm::Jps
+                | Algorithm::DStarLite
+                | Algorithm::Lpa
+                | Algorithm::Ida
+                | Algorithm::BellmanFord
+                | Algorithm::FloydWarshall => {
+                    step[0].0.abs_diff(step[1].0) + step[0].1.abs_diff(step[1].1) == 1
+                }
+            };
+
+            if !valid {
+                return Err(format!("path jumps from {:?} to {:?}", step[0], step[1]));
+            }
+        }
+
+        if search.algorithm() == Algorithm::Theta {
+            return Ok(());
+        }
+
+        match self.shortest_cost() {
+            Some(optimal) if cost > optimal => Err(format!(
+                "path costs {}, {:.0}% more than the optimal {}",
+                format_dist(cost),
+                (cost - optimal) as f64 / optimal.abs().max(1) as f64 * 100.0,
+                format_dist(optimal)
+            )),
+            Some(_) => Ok(()),
+            None => Err("reference search found no path".into()),
+        }
+    }
+
+    /// Whether `cell` is any start or single goal cell of any agent, those are never
+    /// painted over. Goal regions can be, they just can't be reached through obstacles.
+    fn is_endpoint(&self, cell: (u32, u32)) -> bool {
+        self.endpoint_at(cell).is_some()
+            || self.searches.iter().any(|search| search.is_start(cell))
+            || self.fog.as_ref().is_some_and(|fog| fog.position() == cell)
+    }
+
+    /// The start or single goal cell at `cell`, starts first and earlier agents first
+    pub fn endpoint_at(&self, cell: (u32, u32)) -> Option<Endpoint> {
+        let start = self
+            .searches
+            .iter()
+            .position(|search| search.start() == cell);
+        let goal = self
+            .searches
+            .iter()
+            .position(|search| *search.goal() == GoalSpec::Cell(cell));
+
+        start.map(Endpoint::Start).or(goal.map(Endpoint::Goal))
+    }
+
+    /// Moves a start or goal to `cell`, restarting its search. Obstacles and other
+    /// endpoints can't be moved onto, returns whether it moved.
+    pub fn move_endpoint(&mut self, endpoint: Endpoint, cell: (u32, u32)) -> bool {
+        if !self.map.in_bounds(cell) || self.world().is_obstacle(cell) || self.is_endpoint(cell) {
+            return false;
+        }
+
+        match endpoint {
+            Endpoint::Start(0) if self.fog.is_some() => self.restart_fog(cell),
+            Endpoint::Start(agent) => self.searches[agent].set_start(&self.map, cell),
+            Endpoint::Goal(agent) => self.searches[agent].set_goal(&self.map, GoalSpec::Cell(cell)),
+        }
+
+        true
+    }
+
+    /// Moves the main search's goal onto one of its free neighbors picked at random, for a
+    /// goal that wanders off while it's being searched for. Only single goal cells move,
+    /// returns whether it moved.
+    pub fn wander_goal(&mut self, rng: &mut Rng) -> bool {
+        let &GoalSpec::Cell(goal) = self.searches[0].goal() else {
+            return false;
+        };
+
+        let mut neighbors = self.map.get_neighbors(goal);
+        rng.shuffle(&mut neighbors);
+
+        neighbors
+            .into_iter()
+            .any(|cell| self.move_endpoint(Endpoint::Goal(0), cell))
+    }
+
+    /// Moves the main search's goal a cell along the straight line towards `target`. Only
+    /// single goal cells move, and not into obstacles, returns whether it moved.
+    pub fn step_goal_towards(&mut self, target: (u32, u32)) -> bool {
+        let &GoalSpec::Cell(goal) = self.searches[0].goal() else {
+            return false;
+        };
+
+        match line(goal, target).get(1) {
+            Some(&cell) => self.move_endpoint(Endpoint::Goal(0), cell),
+            None => false,
+        }
+    }
+
+    /// Adds `cell` to the goal cells of the main search, or takes it out again, restarting
+    /// the search. A single goal cell becomes the first of several. Regions, obstacles,
+    /// starts and the last goal cell can't be toggled, returns whether the goal changed.
+    pub fn toggle_goal(&mut self, cell: (u32, u32)) -> bool {
+        let starts = self.searches.iter().any(|search| search.is_start(cell));
+        if !self.map.in_bounds(cell) || self.world().is_obstacle(cell) || starts {
+            return false;
+        }
+
+        let search = &mut self.searches[0];
+        let mut cells = match search.goal() {
+            GoalSpec::Cell(goal) => vec![*goal],
+            GoalSpec::Cells(cells) => cells.clone(),
+            GoalSpec::Rect { .. } | GoalSpec::Column(_) | GoalSpec::Row(_) => return false,
+        };
+
+        match cells.iter().position(|&goal| goal == cell) {
+            Some(_) if cells.len() == 1 => return false,
+            Some(i) => {
+                cells.remove(i);
+            }
+            None => cells.push(cell),
+        }
+
+        let goal = match cells[..] {
+            [goal] => GoalSpec::Cell(goal),
+            _ => GoalSpec::Cells(cells),
+        };
+        search.set_goal(&self.map, goal);
+
+        true
+    }
+
+    /// Changes the material of the given cells, restarting every search that had already
+    /// reached one of them, since the distances derived from it may no longer be correct.
+    /// Incremental searches repair those distances instead.
+    fn apply_material(&mut self, cells: &[(u32, u32)], material: Material) {
+        let mut changed = Vec::new();
+
+        for &cell in cells {
+            if self.is_endpoint(cell) {
+                continue;
+            }
+
+            // walls the agent in the fog hasn't sensed only go into the real map
+            let (material, world_changed) = match &mut self.fog {
+                Some(fog) => {
+                    let world_changed = fog.world_mut().set_material(cell, material);
+                    (fog.believed(cell, material), world_changed)
+                }
+                None => (material, false),
+            };
+
+            if self.map.set_material(cell, material) {
+                changed.push(cell);
+                self.changes.push(cell);
+            } else if world_changed {
+                self.changes.push(cell);
+            }
+        }
+
+        for search in self.searches.iter_mut() {
+            if search.algorithm().incremental() {
+                if !changed.is_empty() {
+                    search.repair(&self.map, &changed);
+                }
+            } else if changed.iter().any(|&cell| search.has_reached(cell)) {
+                search.clear(&self.map);
+            }
+        }
+    }
+
+    /// Paints a single cell, restarting the search if the cell has already been reached.
+    pub fn paint(&mut self, cell: (u32, u32), material: Material) {
+        self.apply_material(&[cell], material);
+    }
+
+    /// Paints all of `cells` at once, restarting the search if any of them had already
+    /// been reached.
+    pub fn paint_cells(&mut self, cells: &[(u32, u32)], material: Material) {
+        self.apply_material(cells, material);
+    }
+
+    /// Converts the whole 4-connected region of cells made of the same material as `cell`
+    /// to `material`, restarting the search if any of them had already been reached.
+    /// Nothing spills through portals or stairs, which would paint other layers.
+    pub fn fill(&mut self, cell: (u32, u32), material: Material) {
+        let Some(target) = self.map.material_at(cell) else {
+            return;
+        };
+
+        if target == material.normalized() {
+            return;
+        }
+
+        let region = self
+            .map
+            .flood_layer(cell, |n| self.map.material_at(n) == Some(target));
+
+        self.apply_material(&region, material);
+    }
+
+    /// Turns the whole map into walls, except for starts and goals, and returns the cells
+    /// to [`Self::carve`] a `kind` maze into it in the order they are carved. Starts and
+    /// goals off the maze's corridors get connected to them at the end.
+    pub fn begin_maze(&mut self, kind: MazeKind, rng: &mut Rng) -> Vec<(u32, u32)> {
+        let (w, h) = (self.width(), self.height());
+
+        let all: Vec<_> = (0..w).flat_map(|x| (0..h).map(move |y| (x, y))).collect();
+        self.apply_material(&all, Material::Obstacle);
+
+        let mut cells = maze::carve_order(kind, w, h, rng);
+
+        let mut carved = Grid2D::new(w, h, false);
+        for &cell in &cells {
+            carved[cell] = true;
+        }
+
+        let endpoints: Vec<_> = all
+            .into_iter()
+            .filter(|&cell| self.is_endpoint(cell) && !carved[cell])
+            .collect();
+
+        for endpoint in endpoints {
+            cells.extend(self.map.get_neighbors(endpoint));
+        }
+
+        cells
+    }
+
+    /// Turns each cell into an obstacle with probability `density`. Starts and goals,
+    /// goal regions included, are always left clear.
+    pub fn scatter_obstacles(&mut self, density: f64, rng: &mut Rng) {
+        let (w, h) = (self.width(), self.height());
+
+        let cells: Vec<_> = (0..w)
+            .flat_map(|x| (0..h).map(move |y| (x, y)))
+            .filter(|&cell| {
+                // always draw, so the same seed scatters the same way whatever the goals are
+                rng.unit() < density
+                    && !self
+                        .searches
+                        .iter()
+                        .any(|search| search.goal().contains(cell))
+            })
+            .collect();
+
+        self.apply_material(&cells, Material::Obstacle);
+    }
+
+    /// Gives every cell that isn't an obstacle a `kind` terrain cost
+    pub fn generate_terrain(&mut self, kind: TerrainKind, rng: &mut Rng) {
+        let costs = terrain::costs(kind, self.width(), self.height(), rng);
+
+        for cost in 1..=MAX_COST {
+            let cells: Vec<_> = (0..self.width())
+                .flat_map(|x| (0..self.height()).map(move |y| (x, y)))
+                .filter(|&cell| costs[cell] == cost)
+                .filter(|&cell| !self.map.is_obstacle(cell))
+                .collect();
+
+            self.apply_material(&cells, Material::Cost(cost));
+        }
+    }
+
+    /// Turns `cells` into normal terrain, restarting searches that had reached them
+    pub fn carve(&mut self, cells: &[(u32, u32)]) {
+        self.apply_material(cells, Material::Cost(1));
+    }
+
+    /// Throws away the progress of every search, keeping obstacles and terrain costs,
+    /// so all agents start over from their start cells on the next iteration.
+    ///
+    /// Calling this repeatedly is harmless.
+    pub fn clear_search(&mut self) {
+        self.changes.forget();
+
+        if let Some(start) = self.fog.as_ref().map(|fog| fog.trail()[0]) {
+            self.restart_fog(start);
+        }
+
+        for search in self.searches.iter_mut() {
+            search.clear(&self.map);
+        }
+    }
+
+    /// Turns every cell on the straight line from `start` to `end` into an obstacle, both
+    /// ends included. Cells outside of the map are skipped.
+    pub fn draw_obstacle(&mut self, start: (u32, u32), end: (u32, u32)) {
+        self.apply_material(&Shape::Line.cells(start, end), Material::Obstacle);
+    }
+
+    /// Logs everything known about a single cell, to figure out why the searches went
+    /// where they went
+    pub fn inspect(&self, cell: (u32, u32)) {
+        let Some(material) = self.map.material_at(cell) else {
+            return;
+        };
+
+        for (agent, search) in self.searches.iter().enumerate() {
+            tracing::info!(
+                "agent {} cell {:?}: {}, {:?}, parent {:?}, open set priority {:?}, heuristic {}, \
+                 settled {:?}",
+                agent,
+                cell,
+                material,
+                search.get_cell(cell),
+                search.parent(cell),
+                search.open_set_priority(cell),
+                search.heuristic(cell),
+                search.settle_index(cell),
+            );
+        }
+    }
+
+    /// Colors the whole path of every search that is still revealing it
+    pub fn skip_reveal(&mut self) {
+        for search in self.searches.iter_mut() {
+            search.skip_reveal();
+        }
+    }
+
+    /// Runs every search until it's done, found paths revealed, and the agent in the fog
+    /// until it arrived, and returns the status of the main search
+    pub fn run_to_completion(&mut self) -> SearchStatus {
+        loop {
+            for search in self.searches.iter_mut() {
+                search.run_to_completion(&self.map);
+            }
+
+            // the agent in the fog walks until it arrives or finds there's no way
+            if self.fog.is_none() || self.status() != SearchStatus::Running {
+                return self.status();
+            }
+
+            self.walk();
+        }
+    }
+
+    /// Advances every agent's search by one step, one agent after the other. The agent in
+    /// the fog takes a step too once its search found a path.
+    pub fn dijkstra_iteration(&mut self) {
+        self.walk();
+
+        for search in self.searches.iter_mut() {
+            search.iteration(&self.map);
+        }
+    }
+}
+
+/// What a [`Grid`] is saved as
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedGrid {
+    map: Map,
+    algorithm: Algorithm,
+    diagonals: bool,
+    /// Missing from grids saved before it could be changed
+    #[serde(default = "unweighted")]
+    heuristic_weight: f64,
+    /// Missing from grids saved before it could be picked
+    #[serde(default)]
+    heuristic: Option<Heuristic>,
+    /// The main agent first
+    agents: Vec<SavedAgent>,
+}
+
+fn unweighted() -> f64 {
+    1.0
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedAgent {
+    start: (u32, u32),
+    goal: GoalSpec,
+    #[serde(default)]
+    extra_starts: Vec<(u32, u32)>,
+}
+
+impl serde::Serialize for Grid {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let main = &self.searches[0];
+
+        SavedGrid {
+            map: self.world().clone(),
+            algorithm: main.algorithm(),
+            diagonals: main.diagonals(),
+            heuristic_weight: main.heuristic_weight(),
+            heuristic: main.heuristic_kind(),
+            agents: self
+                .searches
+                .iter()
+                .map(|search| SavedAgent {
+                    start: search.start(),
+                    goal: search.goal().clone(),
+                    extra_starts: search.extra_starts().to_vec(),
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Grid {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let saved = SavedGrid::deserialize(deserializer)?;
+        saved.map.validate().map_err(D::Error::custom)?;
+
+        if !(saved.heuristic_weight >= 0.0 && saved.heuristic_weight.is_finite()) {
+            return Err(D::Error::custom(
+                "heuristic weight has to be a positive number",
+            ));
+        }
+
+        if saved.agents.is_empty() {
+            return Err(D::Error::custom("grid has no agents"));
+        }
+
+        // searches panic on endpoints outside of the map
+        for agent in &saved.agents {
+            if !saved.map.in_bounds(agent.start)
+                || !agent
+                    .extra_starts
+                    .iter()
+                    .all(|&start| saved.map.in_bounds(start))
+                || agent.goal.cells(&saved.map).is_empty()
+            {
+                return Err(D::Error::custom(format!(
+                    "agent from {:?} to {:?} is outside of the map",
+                    agent.start, agent.goal
+                )));
+            }
+        }
+
+        let searches = saved
+            .agents
+            .iter()
+            .map(|agent| {
+                let mut search =
+                    Search::new(&saved.map, agent.start, agent.goal.clone(), saved.algorithm);
+                search.set_diagonals(&saved.map, saved.diagonals);
+                search.set_heuristic_weight(&saved.map, saved.heuristic_weight);
+                search.set_heuristic_kind(&saved.map, saved.heuristic);
+                if !agent.extra_starts.is_empty() {
+                    search.set_extra_starts(&saved.map, agent.extra_starts.clone());
+                }
+                search
+            })
+            .collect();
+
+        Ok(Self {
+            map: saved.map,
+            searches,
+            fog: None,
+            changes: ChangeLog::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::CellState;
+
+    /// A wall across most of the map, so the search has to go around it
+    fn walled(algorithm: Algorithm) -> Grid {
+        let mut grid = Grid::new(20, 15, (1, 1), GoalSpec::Cell((18, 13)), algorithm);
+        grid.draw_obstacle((10, 0), (10, 11));
+        grid
+    }
+
+    fn cells(grid: &Grid) -> Vec<Option<CellState>> {
+        (0..grid.width())
+            .flat_map(|x| (0..grid.height()).map(move |y| (x, y)))
+            .map(|cell| grid.searches()[0].get_cell(cell))
+            .collect()
+    }
+
+    fn assert_same_search(grid: &Grid, fresh: &Grid) {
+        let (search, fresh_search) = (&grid.searches()[0], &fresh.searches()[0]);
+
+        assert_eq!(grid.status(), fresh.status());
+        assert_eq!(grid.path_cost(), fresh.path_cost());
+        assert_eq!(search.path(), fresh_search.path());
+        assert_eq!(search.expanded(), fresh_search.expanded());
+        assert_eq!(cells(grid), cells(fresh));
+    }
+
+    #[test]
+    fn cleared_search_runs_like_a_fresh_one() {
+        for algorithm in [Algorithm::Dijkstra, Algorithm::AStar] {
+            let mut fresh = walled(algorithm);
+            fresh.run_to_completion();
+
+            let mut grid = walled(algorithm);
+            for _ in 0..40 {
+                grid.dijkstra_iteration();
+            }
+            grid.clear_search();
+            grid.run_to_completion();
+
+            assert_same_search(&grid, &fresh);
+        }
+    }
+
+    #[test]
+    fn clearing_while_revealing_the_path_starts_over() {
+        let mut fresh = walled(Algorithm::Dijkstra);
+        fresh.run_to_completion();
+
+        let mut grid = walled(Algorithm::Dijkstra);
+        while grid.status() != SearchStatus::Revealing {
+            grid.dijkstra_iteration();
+        }
+        grid.clear_search();
+        assert_eq!(grid.status(), SearchStatus::Running);
+
+        grid.run_to_completion();
+        assert_same_search(&grid, &fresh);
+    }
+
+    #[test]
+    fn clearing_twice_is_the_same_as_once() {
+        let mut once = walled(Algorithm::Dijkstra);
+        let mut twice = walled(Algorithm::Dijkstra);
+        for grid in [&mut once, &mut twice] {
+            for _ in 0..40 {
+                grid.dijkstra_iteration();
+            }
+            grid.clear_search();
+        }
+        twice.clear_search();
+
+        let fresh = walled(Algorithm::Dijkstra);
+        for grid in [&once, &twice] {
+            assert_eq!(cells(grid), cells(&fresh));
+            assert_eq!(grid.searches()[0].current(), (1, 1));
+            assert_eq!(
+                grid.searches()[0].expanded(),
+                fresh.searches()[0].expanded()
+            );
+        }
+    }
+
+    /// A hash of the text frame `--ascii` draws after each of `frames` frames of
+    /// `per_frame` iterations, the maze carved a cell per iteration first the way an
+    /// animated maze is
+    fn frame_hashes(seed: u64, frames: usize, per_frame: usize) -> Vec<u64> {
+        use std::hash::{Hash, Hasher};
+
+        let mut rng = Rng::new(seed);
+        let mut grid = Grid::new(31, 21, (1, 1), GoalSpec::Cell((29, 19)), Algorithm::AStar);
+        grid.generate_terrain(TerrainKind::Noise, &mut rng);
+        let mut carving = grid.begin_maze(MazeKind::Prims, &mut rng).into_iter();
+
+        (0..frames)
+            .map(|_| {
+                for _ in 0..per_frame {
+                    match carving.next() {
+                        Some(cell) => grid.carve(&[cell]),
+                        None => grid.dijkstra_iteration(),
+                    }
+                }
+
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                crate::shown::frame(&grid).hash(&mut hasher);
+                hasher.finish()
+            })
+            .collect()
+    }
+
+    /// What `--deterministic` relies on for identical recordings: the same seed and
+    /// iterations per frame draw the same frames
+    #[test]
+    fn same_seed_and_steps_give_the_same_frames() {
+        let frames = frame_hashes(7, 120, 5);
+
+        assert_eq!(frames, frame_hashes(7, 120, 5));
+        assert_ne!(frames, frame_hashes(8, 120, 5));
+        // the search moved on from frame to frame
+        assert!(frames.windows(2).filter(|pair| pair[0] != pair[1]).count() > 100);
+    }
+
+    #[test]
+    fn exported_settle_order_reads_back_the_same() {
+        let mut grid = walled(Algorithm::AStar);
+        grid.run_to_completion();
+
+        let path = std::env::temp_dir().join(format!(
+            "dijkstra_visual_settle_order_{}.json",
+            std::process::id()
+        ));
+        grid.export_path(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let exported: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let order: Vec<(u32, u32)> =
+            serde_json::from_value(exported["settle_order"].clone()).unwrap();
+        assert!(!order.is_empty());
+        assert_eq!(order, grid.searches()[0].settle_order());
+    }
+
+    #[test]
+    fn clearing_keeps_obstacles_and_terrain() {
+        let mut grid = walled(Algorithm::Dijkstra);
+        grid.paint((3, 3), Material::Cost(4));
+        for _ in 0..40 {
+            grid.dijkstra_iteration();
+        }
+        grid.clear_search();
+
+        assert!(grid.map().is_obstacle((10, 5)));
+        assert_eq!(grid.map().cost((3, 3)), 4);
+        assert_eq!(grid.map().cost((4, 3)), 1);
+    }
+
+    #[test]
+    fn filling_stops_at_portals_and_stairs() {
+        let mut grid = Grid::new(10, 10, (0, 0), GoalSpec::Cell((9, 4)), Algorithm::Dijkstra);
+        assert!(grid.set_layers(2));
+        // the left of the top layer is walled off, a portal leads out of it
+        grid.draw_obstacle((3, 0), (3, 4));
+        assert!(grid.add_portal(Portal {
+            a: (1, 1),
+            b: (6, 1),
+            cost: UNIT_COST,
+        }));
+        assert!(grid.add_stairs((1, 3)));
+
+        grid.fill((2, 2), Material::Cost(3));
+
+        assert_eq!(grid.map().cost((1, 1)), 3);
+        assert_eq!(grid.map().cost((1, 3)), 3);
+        // past the portal, and down the stairs on the other layer
+        assert_eq!(grid.map().cost((6, 1)), 1);
+        assert_eq!(grid.map().cost((1, 8)), 1);
+        assert_eq!(grid.map().cost((8, 8)), 1);
+    }
+
+    #[test]
+    fn painting_logs_only_cells_that_changed() {
+        let mut grid = walled(Algorithm::Dijkstra);
+        let mark = grid.changes().mark();
+
+        grid.paint((3, 3), Material::Cost(4));
+        grid.paint((3, 3), Material::Cost(4));
+        grid.paint((10, 5), Material::Obstacle);
+        assert_eq!(grid.changes().since(mark), Some(&[(3, 3)][..]));
+
+        grid.clear_search();
+        assert_eq!(grid.changes().since(mark), None);
+    }
+
+    #[test]
+    fn only_searches_growing_from_extra_starts_are_verified_against_them() {
+        let grid = |algorithm| {
+            let mut grid = Grid::new(12, 8, (0, 0), GoalSpec::Cell((11, 7)), algorithm);
+            grid.set_extra_starts(vec![(10, 7)]);
+            grid.run_to_completion();
+            grid
+        };
+
+        assert_eq!(
+            grid(Algorithm::Dijkstra).path_cost(),
+            Some(UNIT_COST as i64)
+        );
+
+        for algorithm in [Algorithm::Ida, Algorithm::FloydWarshall] {
+            let grid = grid(algorithm);
+
+            assert_eq!(
+                grid.path_cost(),
+                Some(18 * UNIT_COST as i64),
+                "{:?}",
+                algorithm
+            );
+            assert_eq!(grid.verify(), Ok(()), "{:?}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::ops::{Index, IndexMut};
+
+/// A value for every cell of a `width` x `height` map, stored row after row in a single
+/// vector, so looking up a cell is one index instead of two.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid2D<T> {
+    width: u32,
+    height: u32,
+    values: Vec<T>,
+}
+
+impl<T: Clone> Grid2D<T> {
+    /// Every cell set to `value`
+    pub fn new(width: u32, height: u32, value: T) -> Self {
+        Self {
+            width,
+            height,
+            values: vec![value; width as usize * height as usize],
+        }
+    }
+
+    /// Changes the size to `width` x `height`, keeping the value of every cell that's
+    /// still inside and setting new cells to `value`
+    pub fn resize(&mut self, width: u32, height: u32, value: T) {
+        let mut resized = Self::new(width, height, value);
+
+        for y in 0..self.height.min(height) {
+            for x in 0..self.width.min(width) {
+                resized[(x, y)] = self[(x, y)].clone();
+            }
+        }
+
+        *self = resized;
+    }
+
+    /// Every column from left to right, each from top to bottom
+    pub fn columns(&self) -> Vec<Vec<T>> {
+        (0..self.width)
+            .map(|x| (0..self.height).map(|y| self[(x, y)].clone()).collect())
+            .collect()
+    }
+
+    /// The grid made of `columns` like [`Self::columns`] returns, `None` if they aren't
+    /// all the same length
+    pub fn from_columns(columns: Vec<Vec<T>>) -> Option<Self> {
+        let width = columns.len();
+        let height = columns.first().map_or(0, Vec::len);
+        if columns.iter().any(|column| column.len() != height) {
+            return None;
+        }
+
+        let mut values = Vec::with_capacity(width * height);
+        for y in 0..height {
+            values.extend(columns.iter().map(|column| column[y].clone()));
+        }
+
+        Some(Self {
+            width: width as u32,
+            height: height as u32,
+            values,
+        })
+    }
+}
+
+impl<T> Grid2D<T> {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Whether this has a value for every cell of a `width` x `height` map and no more
+    pub fn fits(&self, width: u32, height: u32) -> bool {
+        (self.width, self.height) == (width, height)
+    }
+
+    /// Value of `cell`, `None` if it is out of bounds
+    pub fn get(&self, cell: (u32, u32)) -> Option<&T> {
+        self.index_of(cell).map(|i| &self.values[i])
+    }
+
+    pub fn get_mut(&mut self, cell: (u32, u32)) -> Option<&mut T> {
+        self.index_of(cell).map(|i| &mut self.values[i])
+    }
+
+    /// Every value, row after row
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.values.iter()
+    }
+
+    fn index_of(&self, (x, y): (u32, u32)) -> Option<usize> {
+        (x < self.width && y < self.height).then(|| y as usize * self.width as usize + x as usize)
+    }
+}
+
+impl<T> Default for Grid2D<T> {
+    /// A grid with no cells, for state that is only sized once a map is known
+    fn default() -> Self {
+        Self {
+            width: 0,
+            height: 0,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T> Index<(u32, u32)> for Grid2D<T> {
+    type Output = T;
+
+    fn index(&self, cell: (u32, u32)) -> &T {
+        self.get(cell)
+            .unwrap_or_else(|| panic!("{:?} is outside of {}x{}", cell, self.width, self.height))
+    }
+}
+
+impl<T> IndexMut<(u32, u32)> for Grid2D<T> {
+    fn index_mut(&mut self, cell: (u32, u32)) -> &mut T {
+        let (width, height) = (self.width, self.height);
+
+        self.get_mut(cell)
+            .unwrap_or_else(|| panic!("{:?} is outside of {}x{}", cell, width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_are_stored_row_after_row() {
+        let mut grid = Grid2D::new(3, 2, 0);
+        grid[(2, 0)] = 1;
+        grid[(0, 1)] = 2;
+
+        assert_eq!(
+            grid.values().copied().collect::<Vec<_>>(),
+            [0, 0, 1, 2, 0, 0]
+        );
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get((0, 2)), None);
+    }
+
+    #[test]
+    fn columns_round_trip() {
+        let columns = vec![vec![1, 2], vec![3, 4], vec![5, 6]];
+        let grid = Grid2D::from_columns(columns.clone()).unwrap();
+
+        assert!(grid.fits(3, 2));
+        assert_eq!(grid[(1, 0)], 3);
+        assert_eq!(grid[(2, 1)], 6);
+        assert_eq!(grid.columns(), columns);
+        assert_eq!(Grid2D::from_columns(vec![vec![1, 2], vec![3]]), None);
+    }
+
+    #[test]
+    fn resizing_keeps_the_cells_still_inside() {
+        let mut grid = Grid2D::from_columns(vec![vec![1, 2], vec![3, 4]]).unwrap();
+
+        grid.resize(3, 1, 0);
+        assert_eq!(grid.columns(), [[1], [3], [0]]);
+
+        grid.resize(2, 2, 9);
+        assert_eq!(grid.columns(), [[1, 9], [3, 9]]);
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::BTreeMap;
+
+use sdl2::keyboard::Keycode;
+
+/// Everything that can be triggered from the keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    ToggleValues,
+    ToggleHeuristicOverlay,
+    ToggleParents,
+    ToggleOpenSetChart,
+    Pause,
+    Step,
+    Reset,
+    SkipReveal,
+    DecreaseDelay,
+    IncreaseDelay,
+    HalveDelay,
+    DoubleDelay,
+    DecreaseWeight,
+    IncreaseWeight,
+    Obstacle,
+    Cost1,
+    Cost2,
+    Cost3,
+    Cost4,
+    Cost5,
+    Negative,
+    Eraser,
+    Brush,
+    Fill,
+    Inspect,
+    ShrinkBrush,
+    GrowBrush,
+    Line,
+    Rectangle,
+    Circle,
+    ToggleFilled,
+    Maze,
+    Screenshot,
+    LayerUp,
+    LayerDown,
+    ResetView,
+    Undo,
+    Redo,
+    Save,
+}
+
+impl Action {
+    pub const ALL: [Action; 39] = [
+        Action::ToggleValues,
+        Action::ToggleHeuristicOverlay,
+        Action::ToggleParents,
+        Action::ToggleOpenSetChart,
+        Action::Pause,
+        Action::Step,
+        Action::Reset,
+        Action::SkipReveal,
+        Action::DecreaseDelay,
+        Action::IncreaseDelay,
+        Action::HalveDelay,
+        Action::DoubleDelay,
+        Action::DecreaseWeight,
+        Action::IncreaseWeight,
+        Action::Obstacle,
+        Action::Cost1,
+        Action::Cost2,
+        Action::Cost3,
+        Action::Cost4,
+        Action::Cost5,
+        Action::Negative,
+        Action::Eraser,
+        Action::Brush,
+        Action::Fill,
+        Action::Inspect,
+        Action::ShrinkBrush,
+        Action::GrowBrush,
+        Action::Line,
+        Action::Rectangle,
+        Action::Circle,
+        Action::ToggleFilled,
+        Action::Maze,
+        Action::Screenshot,
+        Action::LayerUp,
+        Action::LayerDown,
+        Action::ResetView,
+        Action::Undo,
+        Action::Redo,
+        Action::Save,
+    ];
+
+    /// Name of the action in the `[keys]` section of the config
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ToggleValues => "toggle_values",
+            Action::ToggleHeuristicOverlay => "toggle_heuristic_overlay",
+            Action::ToggleParents => "toggle_parents",
+            Action::ToggleOpenSetChart => "toggle_open_set_chart",
+            Action::Pause => "pause",
+            Action::Step => "step",
+            Action::Reset => "reset",
+            Action::SkipReveal => "skip_reveal",
+            Action::DecreaseDelay => "decrease_delay",
+            Action::IncreaseDelay => "increase_delay",
+            Action::HalveDelay => "halve_delay",
+            Action::DoubleDelay => "double_delay",
+            Action::DecreaseWeight => "decrease_weight",
+            Action::IncreaseWeight => "increase_weight",
+            Action::Obstacle => "obstacle",
+            Action::Cost1 => "cost1",
+            Action::Cost2 => "cost2",
+            Action::Cost3 => "cost3",
+            Action::Cost4 => "cost4",
+            Action::Cost5 => "cost5",
+            Action::Negative => "negative",
+            Action::Eraser => "eraser",
+            Action::Brush => "brush",
+            Action::Fill => "fill",
+            Action::Inspect => "inspect",
+            Action::ShrinkBrush => "shrink_brush",
+            Action::GrowBrush => "grow_brush",
+            Action::Line => "line",
+            Action::Rectangle => "rectangle",
+            Action::Circle => "circle",
+            Action::ToggleFilled => "toggle_filled",
+            Action::Maze => "maze",
+            Action::Screenshot => "screenshot",
+            Action::LayerUp => "layer_up",
+            Action::LayerDown => "layer_down",
+            Action::ResetView => "reset_view",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Save => "save",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    fn default_key(self) -> Keycode {
+        match self {
+            Action::ToggleValues => Keycode::V,
+            Action::ToggleHeuristicOverlay => Keycode::O,
+            Action::ToggleParents => Keycode::P,
+            Action::ToggleOpenSetChart => Keycode::C,
+            Action::Pause => Keycode::Space,
+            Action::Step => Keycode::N,
+            Action::Reset => Keycode::R,
+            Action::SkipReveal => Keycode::Return,
+            Action::DecreaseDelay => Keycode::Minus,
+            Action::IncreaseDelay => Keycode::Equals,
+            Action::HalveDelay => Keycode::LeftBracket,
+            Action::DoubleDelay => Keycode::RightBracket,
+            Action::DecreaseWeight => Keycode::KpMinus,
+            Action::IncreaseWeight => Keycode::KpPlus,
+            Action::Obstacle => Keycode::Num0,
+            Action::Cost1 => Keycode::Num1,
+            Action::Cost2 => Keycode::Num2,
+            Action::Cost3 => Keycode::Num3,
+            Action::Cost4 => Keycode::Num4,
+            Action::Cost5 => Keycode::Num5,
+            Action::Negative => Keycode::Num9,
+            Action::Eraser => Keycode::E,
+            Action::Brush => Keycode::B,
+            Action::Fill => Keycode::F,
+            Action::Inspect => Keycode::I,
+            Action::ShrinkBrush => Keycode::Comma,
+            Action::GrowBrush => Keycode::Period,
+            Action::Line => Keycode::L,
+            Action::Rectangle => Keycode::Q,
+            Action::Circle => Keycode::W,
+            Action::ToggleFilled => Keycode::H,
+            Action::Maze => Keycode::M,
+            Action::Screenshot => Keycode::S,
+            Action::LayerUp => Keycode::PageUp,
+            Action::LayerDown => Keycode::PageDown,
+            Action::ResetView => Keycode::Home,
+            Action::Undo => Keycode::Z,
+            Action::Redo => Keycode::Y,
+            Action::Save => Keycode::S,
+        }
+    }
+
+    /// Whether the action's key is pressed with Ctrl, so it can share a key with one that
+    /// isn't
+    pub fn with_ctrl(self) -> bool {
+        matches!(self, Action::Undo | Action::Redo | Action::Save)
+    }
+}
+
+/// Keys listed when a key name isn't known, besides letters, digits and function keys
+const NAMED_KEYS: [Keycode; 31] = [
+    Keycode::Space,
+    Keycode::Return,
+    Keycode::Escape,
+    Keycode::Backspace,
+    Keycode::Tab,
+    Keycode::Insert,
+    Keycode::Delete,
+    Keycode::Home,
+    Keycode::End,
+    Keycode::PageUp,
+    Keycode::PageDown,
+    Keycode::Left,
+    Keycode::Right,
+    Keycode::Up,
+    Keycode::Down,
+    Keycode::Minus,
+    Keycode::Equals,
+    Keycode::LeftBracket,
+    Keycode::RightBracket,
+    Keycode::Backslash,
+    Keycode::Semicolon,
+    Keycode::Quote,
+    Keycode::Backquote,
+    Keycode::Comma,
+    Keycode::Period,
+    Keycode::Slash,
+    Keycode::KpPlus,
+    Keycode::KpMinus,
+    Keycode::KpMultiply,
+    Keycode::KpDivide,
+    Keycode::KpEnter,
+];
+
+/// SDL's names of the keys that can be bound, letters, digits, function keys and
+/// [`NAMED_KEYS`]
+fn key_names() -> Vec<String> {
+    let function_keys = [
+        Keycode::F1,
+        Keycode::F2,
+        Keycode::F3,
+        Keycode::F4,
+        Keycode::F5,
+        Keycode::F6,
+        Keycode::F7,
+        Keycode::F8,
+        Keycode::F9,
+        Keycode::F10,
+        Keycode::F11,
+        Keycode::F12,
+    ];
+
+    ('A'..='Z')
+        .chain('0'..='9')
+        .map(String::from)
+        .chain(
+            function_keys
+                .into_iter()
+                .chain(NAMED_KEYS)
+                .map(Keycode::name),
+        )
+        .collect()
+}
+
+/// Which key triggers which action
+#[derive(Clone, Debug)]
+pub struct Keybindings {
+    keys: BTreeMap<Action, Keycode>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            keys: Action::ALL
+                .into_iter()
+                .map(|action| (action, action.default_key()))
+                .collect(),
+        }
+    }
+}
+
+impl Keybindings {
+    /// The defaults, overridden by the `[keys]` section of the config. Key names are the
+    /// ones SDL uses, e.g. `"Space"` or `"R"`.
+    pub fn from_config(keys: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut bindings = Self::default();
+
+        for (name, key) in keys {
+            let Some(action) = Action::from_name(name) else {
+                let valid: Vec<_> = Action::ALL.iter().map(|action| action.name()).collect();
+
+                return Err(format!(
+                    "unknown action {:?} in [keys], valid actions are: {}",
+                    name,
+                    valid.join(", ")
+                ));
+            };
+
+            let Some(keycode) = Keycode::from_name(key) else {
+                return Err(format!(
+                    "unknown key {:?} for {} in [keys], valid keys are: {}",
+                    key,
+                    name,
+                    key_names().join(", ")
+                ));
+            };
+
+            bindings.keys.insert(action, keycode);
+        }
+
+        let keys: Vec<_> = bindings.keys.iter().collect();
+
+        for (i, (action, keycode)) in keys.iter().enumerate() {
+            let clashes = |(other, key): &&(&Action, &Keycode)| {
+                key == keycode && other.with_ctrl() == action.with_ctrl()
+            };
+
+            // only warn once, from the action that wins
+            if keys[..i].iter().any(|other| clashes(&other)) {
+                continue;
+            }
+
+            let shadowed: Vec<_> = keys[i + 1..]
+                .iter()
+                .filter(clashes)
+                .map(|(other, _)| other.name())
+                .collect();
+
+            if !shadowed.is_empty() {
+                tracing::warn!(
+                    "{} is bound to {} and {}, only {} will trigger",
+                    keycode.name(),
+                    action.name(),
+                    shadowed.join(", "),
+                    action.name()
+                );
+            }
+        }
+
+        Ok(bindings)
+    }
+
+    /// The action bound to `keycode`, pressed with Ctrl if `ctrl` is set. Keys that no
+    /// action is pressed with Ctrl for do what they do without it. If several actions are
+    /// bound, the first one in [`Action::ALL`].
+    pub fn action_for(&self, keycode: Keycode, ctrl: bool) -> Option<Action> {
+        let bound = |with_ctrl: bool| {
+            self.keys
+                .iter()
+                .find(|(action, key)| **key == keycode && action.with_ctrl() == with_ctrl)
+                .map(|(action, _)| *action)
+        };
+
+        ctrl.then(|| bound(true)).flatten().or_else(|| bound(false))
+    }
+}
+
+impl std::fmt::Display for Keybindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[keys]")?;
+
+        for (action, keycode) in &self.keys {
+            let ctrl = if action.with_ctrl() {
+                " # with Ctrl"
+            } else {
+                ""
+            };
+            writeln!(f, "{} = {:?}{}", action.name(), keycode.name(), ctrl)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|&(action, key)| (action.to_string(), key.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_errors() {
+        let unknown_action = Keybindings::from_config(&config(&[("jump", "J")])).unwrap_err();
+        assert!(
+            unknown_action.starts_with("unknown action \"jump\""),
+            "{}",
+            unknown_action
+        );
+
+        let unknown_key = Keybindings::from_config(&config(&[("pause", "Nope")])).unwrap_err();
+        assert!(
+            unknown_key.starts_with("unknown key \"Nope\""),
+            "{}",
+            unknown_key
+        );
+    }
+
+    #[test]
+    fn configured_keys_replace_the_defaults() {
+        let bindings = Keybindings::from_config(&config(&[("pause", "G")])).unwrap();
+
+        assert_eq!(bindings.action_for(Keycode::G, false), Some(Action::Pause));
+        assert_eq!(bindings.action_for(Keycode::Space, false), None);
+        assert_eq!(bindings.action_for(Keycode::R, false), Some(Action::Reset));
+    }
+
+    #[test]
+    fn ctrl_and_plain_bindings_share_a_key() {
+        let bindings = Keybindings::default();
+
+        assert_eq!(
+            bindings.action_for(Keycode::S, false),
+            Some(Action::Screenshot)
+        );
+        assert_eq!(bindings.action_for(Keycode::S, true), Some(Action::Save));
+        // nothing is bound to Ctrl+R, so it resets like R
+        assert_eq!(bindings.action_for(Keycode::R, true), Some(Action::Reset));
+        assert_eq!(bindings.action_for(Keycode::Z, false), None);
+
+        let moved = Keybindings::from_config(&config(&[("save", "X")])).unwrap();
+        assert_eq!(moved.action_for(Keycode::S, true), Some(Action::Screenshot));
+        assert_eq!(moved.action_for(Keycode::X, true), Some(Action::Save));
+    }
+}
@@ -0,0 +1,412 @@
+use std::ops::Range;
+
+use sdl2::rect::{Point, Rect};
+
+use dijkstra_visual::map::{points_up, Topology};
+
+/// Gap between cells in pixels
+pub const CELL_SPACING: u32 = 1;
+
+/// Most a [`Camera`] magnifies the grid
+const MAX_ZOOM: f64 = 64.0;
+
+/// Where the cells of a grid end up on a canvas of a given size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    columns: u32,
+    rows: u32,
+    /// Row of the map shown at the top, the first of a layer
+    first_row: u32,
+    /// Top left corner of the first cell
+    x: i32,
+    y: i32,
+    cell_width: u32,
+    cell_height: u32,
+    topology: Topology,
+    /// The canvas the cells are drawn on, zoomed in cells past it are clipped away
+    pane: Rect,
+    /// Part of the pane something else is drawn over
+    covered: Option<Rect>,
+}
+
+impl Layout {
+    /// Fits `columns` x `rows` cells into a `w` x `h` canvas, the rows from `first_row` on.
+    /// Cells stay square and the grid is centered, unless `stretch` is set, in which case
+    /// cells fill the whole canvas. Triangles are equilateral unless stretched, and every
+    /// one overlaps half of each of its neighbors' bounding boxes, so a row is only half a
+    /// triangle wider than the triangles are wide together.
+    pub fn new(
+        columns: u32,
+        rows: u32,
+        first_row: u32,
+        w: u32,
+        h: u32,
+        stretch: bool,
+        topology: Topology,
+    ) -> Self {
+        let avail_height = h.saturating_sub(rows.saturating_sub(1) * CELL_SPACING);
+        let mut cell_height = avail_height / rows.max(1);
+
+        let (cell_width, used_width) = match topology {
+            Topology::Square => {
+                let avail_width = w.saturating_sub(columns.saturating_sub(1) * CELL_SPACING);
+                let mut cell_width = avail_width / columns.max(1);
+
+                if !stretch {
+                    cell_width = cell_width.min(cell_height);
+                    cell_height = cell_width;
+                }
+
+                (
+                    cell_width,
+                    columns * cell_width + columns.saturating_sub(1) * CELL_SPACING,
+                )
+            }
+            Topology::Triangle => {
+                // half of a triangle's width
+                let mut half_width = (w / (columns + 1)).max(1);
+
+                if !stretch {
+                    half_width = half_width
+                        .min((cell_height as f64 / 3f64.sqrt()) as u32)
+                        .max(1);
+                    cell_height = (half_width as f64 * 3f64.sqrt()) as u32;
+                }
+
+                (half_width * 2, (columns + 1) * half_width)
+            }
+        };
+
+        let used_height = rows * cell_height + rows.saturating_sub(1) * CELL_SPACING;
+
+        Self {
+            columns,
+            rows,
+            first_row,
+            x: (w.saturating_sub(used_width) / 2) as i32,
+            y: (h.saturating_sub(used_height) / 2) as i32,
+            cell_width,
+            cell_height,
+            topology,
+            pane: Rect::new(0, 0, w, h),
+            covered: None,
+        }
+    }
+
+    /// The same layout with `rect` drawn over the cells, which then don't belong to any
+    /// position in it
+    pub fn covered_by(mut self, rect: Rect) -> Self {
+        self.covered = Some(rect);
+        self
+    }
+
+    /// The layout seen through `camera`: cells magnified by its zoom and moved by its pan.
+    /// Cells that end up outside the canvas are clipped away when drawn.
+    pub fn viewed(mut self, camera: &Camera) -> Self {
+        let scale = |size: u32| ((size as f64 * camera.zoom).round() as u32).max(1);
+
+        self.cell_width = match self.topology {
+            Topology::Square => scale(self.cell_width),
+            // kept even, so both halves of a triangle are as wide
+            Topology::Triangle => scale(self.cell_width / 2) * 2,
+        };
+        self.cell_height = scale(self.cell_height);
+        self.x += camera.offset.0;
+        self.y += camera.offset.1;
+
+        self
+    }
+
+    /// Pixels from one column, and one row, to the next
+    fn pitch(&self) -> (u32, u32) {
+        let column = match self.topology {
+            Topology::Square => self.cell_width + CELL_SPACING,
+            Topology::Triangle => self.cell_width / 2,
+        };
+
+        (column, self.cell_height + CELL_SPACING)
+    }
+
+    /// The same layout moved `dx` pixels to the right, to draw next to another one
+    pub fn shifted(mut self, dx: i32) -> Self {
+        self.x += dx;
+        self.pane.offset(dx, 0);
+        self.covered = self.covered.map(|mut rect| {
+            rect.offset(dx, 0);
+            rect
+        });
+        self
+    }
+
+    /// Part of the grid a `w` x `h` canvas shows, as fractions of its width and height:
+    /// left, top, right and bottom
+    pub fn visible(&self, w: u32, h: u32) -> (f64, f64, f64, f64) {
+        let (column_pitch, row_pitch) = self.pitch();
+        let width = (self.columns * column_pitch) as f64;
+        let height = (self.rows * row_pitch) as f64;
+
+        let fraction = |from: i32, to: u32, size: f64| {
+            (
+                (-from as f64 / size).clamp(0.0, 1.0),
+                ((to as i32 - from) as f64 / size).clamp(0.0, 1.0),
+            )
+        };
+        let (left, right) = fraction(self.x, w, width);
+        let (top, bottom) = fraction(self.y, h, height);
+
+        (left, top, right, bottom)
+    }
+
+    /// Area a picture with a pixel per cell of the rows shown is scaled up to, so every
+    /// pixel covers its cell and the gap after it. `None` for triangles, which overlap.
+    pub fn texel_rect(&self) -> Option<Rect> {
+        if self.topology != Topology::Square {
+            return None;
+        }
+
+        let (column_pitch, row_pitch) = self.pitch();
+
+        Some(Rect::new(
+            self.x,
+            self.y,
+            self.columns * column_pitch,
+            self.rows * row_pitch,
+        ))
+    }
+
+    /// The gaps after every column and row of squares, which a picture scaled up to
+    /// [`Layout::texel_rect`] covers with its cells
+    pub fn gaps(&self) -> Vec<Rect> {
+        let Some(area) = self.texel_rect() else {
+            return Vec::new();
+        };
+        let (column_pitch, row_pitch) = self.pitch();
+
+        // cells too small to be drawn have no gaps either, they are a pixel apart
+        let columns = (self.cell_width > 0).then_some(0..self.columns);
+        let rows = (self.cell_height > 0).then_some(0..self.rows);
+
+        let columns = columns.into_iter().flatten().map(|column| {
+            Rect::new(
+                area.x() + (column * column_pitch + self.cell_width) as i32,
+                area.y(),
+                CELL_SPACING,
+                area.height(),
+            )
+        });
+        let rows = rows.into_iter().flatten().map(|row| {
+            Rect::new(
+                area.x(),
+                area.y() + (row * row_pitch + self.cell_height) as i32,
+                area.width(),
+                CELL_SPACING,
+            )
+        });
+
+        columns.chain(rows).collect()
+    }
+
+    /// Size of a single cell in pixels
+    pub fn cell_size(&self) -> (u32, u32) {
+        (self.cell_width, self.cell_height)
+    }
+
+    /// Rows of the map that are shown
+    pub fn rows(&self) -> Range<u32> {
+        self.first_row..self.first_row + self.rows
+    }
+
+    pub fn shows(&self, cell: (u32, u32)) -> bool {
+        self.rows().contains(&cell.1)
+    }
+
+    /// Area `cell` is drawn to, the bounding box of triangles. Cells of rows that aren't
+    /// shown end up off the grid.
+    pub fn cell_rect(&self, cell: (u32, u32)) -> Rect {
+        let row = cell.1 as i32 - self.first_row as i32;
+
+        let (column_pitch, row_pitch) = self.pitch();
+
+        Rect::new(
+            self.x + (cell.0 * column_pitch) as i32,
+            self.y + row * row_pitch as i32,
+            self.cell_width,
+            self.cell_height,
+        )
+    }
+
+    /// Corners of the triangle `cell` is drawn as, apex first, `None` for squares
+    pub fn triangle(&self, cell: (u32, u32)) -> Option<[Point; 3]> {
+        if self.topology != Topology::Triangle {
+            return None;
+        }
+
+        let rect = self.cell_rect(cell);
+        let (left, right) = (rect.left(), rect.right() - 1);
+        let (top, bottom) = (rect.top(), rect.bottom() - 1);
+        let middle = rect.center().x();
+
+        Some(if points_up(cell) {
+            [
+                Point::new(middle, top),
+                Point::new(left, bottom),
+                Point::new(right, bottom),
+            ]
+        } else {
+            [
+                Point::new(middle, bottom),
+                Point::new(left, top),
+                Point::new(right, top),
+            ]
+        })
+    }
+
+    /// Middle of the square, or the centroid of the triangle
+    pub fn cell_center(&self, cell: (u32, u32)) -> Point {
+        let rect = self.cell_rect(cell);
+
+        match self.topology {
+            Topology::Square => rect.center(),
+            Topology::Triangle => {
+                let third = self.cell_height as i32 / 3;
+                let y = if points_up(cell) {
+                    rect.bottom() - third
+                } else {
+                    rect.top() + third
+                };
+
+                Point::new(rect.center().x(), y)
+            }
+        }
+    }
+
+    /// Maps a pixel position back to the cell drawn there. Positions in the letterbox
+    /// margins, the gaps past the last cell, outside the pane or under what covers it don't
+    /// belong to any cell.
+    pub fn cell_at(&self, x: i32, y: i32) -> Option<(u32, u32)> {
+        let point = Point::new(x, y);
+        if !self.pane.contains_point(point)
+            || self.covered.is_some_and(|rect| rect.contains_point(point))
+        {
+            return None;
+        }
+
+        let (x, y) = (x - self.x, y - self.y);
+        if x < 0 || y < 0 {
+            return None;
+        }
+
+        let row = y as u32 / (self.cell_height + CELL_SPACING);
+
+        let column = match self.topology {
+            Topology::Square => x as u32 / (self.cell_width + CELL_SPACING),
+            Topology::Triangle => {
+                // every half a triangle wide slot is shared by two triangles, split by
+                // the side between them
+                let half_width = self.cell_width / 2;
+                let slot = x as u32 / half_width;
+                let across = (x as u32 % half_width) as f64 / half_width as f64;
+                let down =
+                    (y as u32 % (self.cell_height + CELL_SPACING)) as f64 / self.cell_height as f64;
+
+                let own = if points_up((slot, self.first_row + row)) {
+                    down >= 1.0 - across
+                } else {
+                    down <= across
+                };
+
+                match (own, slot.checked_sub(1)) {
+                    (true, _) => slot,
+                    (false, Some(previous)) => previous,
+                    (false, None) => return None,
+                }
+            }
+        };
+
+        (column < self.columns && row < self.rows).then_some((column, self.first_row + row))
+    }
+}
+
+/// Zoom and pan the grid is seen with, on top of the layout fitting all of it into the
+/// canvas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// How many times larger than fitting into the canvas cells are, at least 1
+    zoom: f64,
+    /// Pixels the grid is moved by
+    offset: (i32, i32),
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            offset: (0, 0),
+        }
+    }
+}
+
+impl Camera {
+    /// Whether only part of the grid may be in view
+    pub fn zoomed(&self) -> bool {
+        self.zoom > 1.0
+    }
+
+    pub fn pan(&mut self, dx: i32, dy: i32) {
+        self.offset = (self.offset.0 + dx, self.offset.1 + dy);
+    }
+
+    /// Zooms by `factor` so whatever is at the pixel `at` stays there. `fitting` is the
+    /// layout without any camera. Zooming all the way out shows the whole grid again.
+    pub fn zoom_at(&mut self, fitting: &Layout, factor: f64, at: (i32, i32)) {
+        let before = fitting.viewed(self);
+        let (column_pitch, row_pitch) = before.pitch();
+        // where in the grid `at` is, in columns and rows
+        let column = (at.0 - before.x) as f64 / column_pitch as f64;
+        let row = (at.1 - before.y) as f64 / row_pitch as f64;
+
+        self.zoom = (self.zoom * factor).clamp(1.0, MAX_ZOOM);
+        if !self.zoomed() {
+            *self = Self::default();
+            return;
+        }
+
+        let after = fitting.viewed(self);
+        let (column_pitch, row_pitch) = after.pitch();
+
+        self.pan(
+            at.0 - (after.x + (column * column_pitch as f64).round() as i32),
+            at.1 - (after.y + (row * row_pitch as f64).round() as i32),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BAR_HEIGHT;
+
+    #[test]
+    fn zoomed_in_cells_past_the_pane_cant_be_clicked() {
+        let (w, h) = (800, 600);
+        let fitting = Layout::new(40, 30, 0, w, h - BAR_HEIGHT, false, Topology::Square);
+        let mut camera = Camera::default();
+        camera.zoom_at(&fitting, 4.0, (400, 300));
+        let layout = fitting.viewed(&camera);
+
+        // the zoomed in grid reaches down past the pane, under the settings bar
+        let bottom = (h - BAR_HEIGHT) as i32;
+        assert!(layout.cell_rect((20, 29)).bottom() > h as i32);
+        assert!(layout.cell_at(400, bottom - 1).is_some());
+        for y in bottom..h as i32 {
+            assert_eq!(layout.cell_at(400, y), None, "{}", y);
+        }
+        // nor in the pane of the compared search
+        assert_eq!(layout.cell_at(w as i32 + 10, 300), None);
+
+        let minimap = Rect::new(600, 10, 150, 100);
+        assert!(layout.cell_at(650, 50).is_some());
+        assert_eq!(layout.covered_by(minimap).cell_at(650, 50), None);
+        assert!(layout.covered_by(minimap).cell_at(400, 300).is_some());
+    }
+}
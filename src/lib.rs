@@ -0,0 +1,23 @@
+//! Grid path finding searches that can be driven one iteration at a time, so every step
+//! can be looked at. Nothing in here draws anything, the binary renders it with SDL or
+//! in the terminal, from what [`shown`] says every cell shows.
+//!
+//! A [`Grid`] holds the [`Map`](map::Map) and one [`Search`](search::Search) per agent,
+//! [`Grid::dijkstra_iteration`] advances all of them by one step.
+
+pub mod ascii;
+pub mod changes;
+pub mod fog;
+pub mod goal;
+pub mod grid;
+pub mod grid2d;
+pub mod map;
+pub mod maze;
+pub mod movingai;
+pub mod rng;
+pub mod search;
+pub mod shape;
+pub mod shown;
+pub mod terrain;
+
+pub use grid::{Endpoint, Grid, MapSummary};
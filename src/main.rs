@@ -1,21 +1,101 @@
 use std::{
-    collections::BinaryHeap,
+    collections::VecDeque,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use sdl2::{
-    pixels::Color,
+    event::{Event, WindowEvent},
+    keyboard::Mod,
+    mouse::MouseButton,
     rect::Rect,
-    render::{Canvas, RenderTarget, TextureCreator},
-    ttf::Font,
+    render::{Canvas, RenderTarget},
 };
 use tracing_subscriber::fmt::format::FmtSpan;
 
+use dijkstra_visual::{
+    ascii::AsciiMap,
+    fog::Fog,
+    goal::GoalSpec,
+    map::{Map, Material, Portal, Topology, UNIT_COST},
+    maze::MazeKind,
+    movingai::{MovingAiMap, Scenario},
+    rng::Rng,
+    search::{format_dist, Algorithm, Heuristic, Search, SearchStatus},
+    shape::Shape,
+    terrain::TerrainKind,
+    Grid,
+};
+
+use atlas::GlyphAtlas;
+use cells::{CellCanvas, Pane};
+use chart::Sparkline;
+use config::Config;
+use editor::{Editor, Tool};
+use font::FontSource;
+use keys::{Action, Keybindings};
+use layout::{Camera, Layout};
+use playback::Playback;
+use race::Race;
+use recorder::{save_screenshot, GifRecorder, Recorder, VideoRecorder};
+use renderer::Renderer;
+use terminal::{BlockRenderer, Session, TextRenderer};
+use theme::{Theme, ThemeKind};
+use ui::{render_colored_text, Ui};
+use view::{ColorBy, View};
+
+mod atlas;
+mod benchmark;
+mod cells;
+mod chart;
+mod config;
+mod editor;
+mod fade;
+mod font;
+mod keys;
+mod layout;
+mod playback;
+mod race;
+mod recorder;
+mod renderer;
+mod terminal;
+mod theme;
+mod ui;
+mod view;
+
+/// Window size without `--window-size`
 const W: u32 = 879;
 const H: u32 = 879;
 
-/// Visual dijkstra/A* demo
+/// Cells smaller than this (in pixels) are too small to fit their g/h/f values
+const MIN_LABELED_CELL_SIZE: u32 = 40;
+
+/// Number of iterations the open set chart covers
+const CHART_ITERATIONS: usize = 200;
+/// Size of the open set chart in pixels, it sits in the bottom right corner
+const CHART_WIDTH: u32 = 200;
+const CHART_HEIGHT: u32 = 50;
+
+/// Height of the settings bar along the bottom of the window, two rows of controls
+const BAR_HEIGHT: u32 = 60;
+/// How much a key press changes the heuristic weight
+const WEIGHT_STEP: f64 = 0.25;
+/// Stairs placed between every two layers when `--stairs` isn't given
+const RANDOM_STAIRS: usize = 4;
+
+/// Longer side of the minimap shown while zoomed in, in pixels
+const MINIMAP_SIZE: u32 = 160;
+
+/// How much a notch of the mouse wheel zooms in or out
+const ZOOM_STEP: f64 = 1.25;
+/// How far the mouse has to move with the middle button held to pan instead of clicking
+const PAN_THRESHOLD: i32 = 4;
+
+/// How long the startup summary stays on screen
+const SUMMARY_DURATION: Duration = Duration::from_secs(5);
+
+/// Visual dijkstra/A*/Theta* demo
 #[derive(clap::Parser)]
 #[command(about)]
 struct Args {
@@ -27,22 +107,905 @@ struct Args {
     #[arg(long, default_value_t = 60)]
     fps: u32,
 
-    /// Enable A* instead of dijkstra, using euclidean distance as heuristic
+    /// Search algorithm to visualize, dijkstra unless a `--load-map` grid says otherwise
+    #[arg(long, value_enum)]
+    algorithm: Option<Algorithm>,
+
+    /// Enable A* instead of dijkstra, using euclidean distance as heuristic. Same as
+    /// `--algorithm astar`
     #[arg(long)]
     enable_astar: bool,
+
+    /// Multiply the heuristic of A* and Theta* by this, above 1 finds paths faster that
+    /// may not be the shortest, 0 searches like Dijkstra. 1 unless a `--load-map` grid says
+    /// otherwise. Keypad plus and minus change it while running.
+    #[arg(long, value_name = "WEIGHT", value_parser = parse_weight)]
+    astar_weight: Option<f64>,
+
+    /// How the heuristic measures the distance to the goal. Without it, octile for
+    /// searches stepping diagonally and the straight line rounded down otherwise, both of
+    /// which never overestimate. Manhattan does once steps may be diagonal and octile for
+    /// Theta*, which then may not find the shortest path. Loaded grids keep theirs
+    /// without it.
+    #[arg(long, value_enum)]
+    heuristic: Option<Heuristic>,
+
+    /// What the main search has to reach: `X,Y`, `rect:X,Y,W,H`, `column:X`, `row:Y` or
+    /// several cells, whichever is closest, as `cells:X,Y:X,Y...`. Middle clicks add and
+    /// remove goal cells.
+    #[arg(long, value_name = "GOAL", default_value = "74,40", value_parser = parse_goal)]
+    goal: GoalSpec,
+
+    /// Cell the main search starts from on the built-in map
+    #[arg(long, value_name = "X,Y", default_value = "64,4", value_parser = parse_cell, conflicts_with_all = ["map", "movingai", "load_map"])]
+    start: (u32, u32),
+
+    /// Number of columns of the built-in map
+    #[arg(long, default_value_t = 80, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["map", "movingai", "load_map"])]
+    width: u32,
+
+    /// Number of rows of the built-in map, 80 for every layer if not given
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..), conflicts_with_all = ["map", "movingai", "load_map"])]
+    height: Option<u32>,
+
+    /// Another cell the main search grows from at the same time as from its start, so every
+    /// cell is reached from the closest one. Can be repeated.
+    #[arg(long = "extra-start", value_name = "X,Y", value_parser = parse_cell)]
+    extra_starts: Vec<(u32, u32)>,
+
+    /// Additional agent searching on the same map, can be repeated
+    #[arg(long = "agent", value_name = "START_X,START_Y:GOAL_X,GOAL_Y", value_parser = parse_agent)]
+    agents: Vec<Agent>,
+
+    /// Pair of cells stepping between which costs COST normal steps, more than 0 and 1 if not
+    /// given, however far apart they are. Can be repeated. JPS doesn't take portals, other
+    /// than stairs between layers where it searches like A*.
+    #[arg(long = "portal", value_name = "X1,Y1:X2,Y2[:COST]", value_parser = parse_portal)]
+    portals: Vec<Portal>,
+
+    /// Split the rows into this many layers stacked on top of each other, like floors of
+    /// a building, only connected by stairs. Rows are counted across all layers, the
+    /// default map gets 80 for every one. PageUp and PageDown switch the layer shown.
+    #[arg(long, value_name = "LAYERS", value_parser = clap::value_parser!(u32).range(1..))]
+    layers: Option<u32>,
+
+    /// Stairs from this cell to the same one on the layer below, as cheap as a normal step.
+    /// Can be repeated, without it a few are placed at random between every two layers.
+    #[arg(long = "stairs", value_name = "X,Y", value_parser = parse_cell)]
+    stairs: Vec<(u32, u32)>,
+
+    /// Obstacle wall appearing after the given number of iterations, can be repeated
+    #[arg(long = "obstacle-at", value_name = "ITERATION:X1,Y1:X2,Y2", value_parser = parse_timed_obstacle)]
+    timed_obstacles: Vec<TimedObstacle>,
+
+    /// Let the main agent only sense walls within this many cells of itself. It walks the
+    /// path planned on what it knows, taking unknown cells to be free, and plans again
+    /// from where it is once it senses a wall across it. Walls it hasn't sensed are drawn
+    /// gray.
+    #[arg(long, value_name = "RADIUS", value_parser = clap::value_parser!(u32).range(1..))]
+    fog: Option<u32>,
+
+    /// Run this algorithm on a copy of the map in a second pane to the right, stepping in
+    /// lockstep with the main search. Editing the map, moving endpoints or changing the
+    /// weight starts it over on the new setup.
+    #[arg(long, value_name = "ALGORITHM", value_enum, conflicts_with = "fog")]
+    compare: Option<Algorithm>,
+
+    /// Race these algorithms, separated by commas, on copies of the map behind the scenes,
+    /// a step each whenever the main search takes one. Once all of them are done, a table
+    /// ranks them by path cost, cells expanded and time taken.
+    #[arg(long, value_name = "ALGORITHMS", value_enum, value_delimiter = ',')]
+    race: Vec<Algorithm>,
+
+    /// Move the goal a cell every this many iterations, the search has to keep replanning
+    /// to catch it. It wanders at random unless `--goal-waypoint`s are given. LPA* keeps
+    /// what it knows when the goal moves, every other search starts over.
+    #[arg(long, value_name = "ITERATIONS", value_parser = clap::value_parser!(u64).range(1..))]
+    goal_drift: Option<u64>,
+
+    /// Cell the drifting goal walks to, then on to the next one, looping back to the first
+    /// after the last. Can be repeated.
+    #[arg(long = "goal-waypoint", value_name = "X,Y", value_parser = parse_cell, requires = "goal_drift")]
+    goal_waypoints: Vec<(u32, u32)>,
+
+    /// Load the map, start and goal from a text file: `#` obstacle, `.` free, `S` start,
+    /// `G` goal and `1` to `5` terrain costs, one row per line
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["goal", "maze", "random_obstacles"])]
+    map: Option<PathBuf>,
+
+    /// Load a grid saved with `--save-map`: obstacles, terrain costs, every agent's start
+    /// and goal, algorithm and diagonals
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["map", "movingai", "goal", "maze", "random_obstacles", "terrain"]
+    )]
+    load_map: Option<PathBuf>,
+
+    /// Save the grid as JSON when the window closes, to be loaded again with `--load-map`,
+    /// or as a text map for `--map` if the path ends in `.txt`. Ctrl+S saves it right away.
+    #[arg(long, value_name = "PATH")]
+    save_map: Option<PathBuf>,
+
+    /// Edit the map instead of searching it: the search never runs, only the map with its
+    /// starts and goals and the editing tools are there. Saves to `--save-map`.
+    #[arg(
+        long,
+        requires = "save_map",
+        conflicts_with_all = ["headless", "benchmark", "animate_maze", "compare", "race"]
+    )]
+    edit: bool,
+
+    /// MovingAI benchmark map (`.map`) to search between the ends of a `--scen` scenario.
+    /// Octile maps turn on `--diagonals`.
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "scen",
+        conflicts_with_all = ["map", "goal", "maze", "random_obstacles"]
+    )]
+    movingai: Option<PathBuf>,
+
+    /// MovingAI scenario file (`.scen`) with the starts and goals for `--movingai`
+    #[arg(long, value_name = "PATH", requires = "movingai")]
+    scen: Option<PathBuf>,
+
+    /// Which scenario of `--scen` to show, counting from 0
+    #[arg(long, value_name = "INDEX", default_value_t = 0)]
+    scenario: usize,
+
+    /// Run every scenario of `--scen` without a window and print how long the searches
+    /// took. Exits with 1 if any path isn't optimal.
+    #[arg(long, requires = "scen")]
+    benchmark: bool,
+
+    /// Run the search to completion without a window, then print how many cells it
+    /// expanded, the path it found and how long it took. SDL isn't started at all, so
+    /// this works without a display. Exits like `--exit-on-complete`. Animated mazes are
+    /// carved at once, timed obstacles and a drifting goal never come.
+    #[arg(long)]
+    headless: bool,
+
+    /// Play the search back as text on stdout instead of in a window, a frame every
+    /// `--fps` with the screen cleared in between, for terminals, `asciinema` and logs.
+    /// SDL isn't started at all. Animated mazes are carved at once, timed obstacles and a
+    /// drifting goal never come. Verifies and writes the path and metrics, and exits, like
+    /// `--headless`.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "headless",
+            "benchmark",
+            "edit",
+            "compare",
+            "race",
+            "exit_on_complete",
+        ]
+    )]
+    ascii: bool,
+
+    /// Like `--ascii`, but the cells are colored blocks in the colors of the window, two
+    /// rows to a line. Needs a terminal with 24-bit colors.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "headless",
+            "benchmark",
+            "edit",
+            "compare",
+            "race",
+            "ascii",
+            "exit_on_complete",
+        ]
+    )]
+    tui: bool,
+
+    /// Generate a maze instead of the built-in walls, `M` generates a new one. Without
+    /// this, `--random-obstacles` or `--terrain`, `M` generates a maze anyway.
+    #[arg(long, value_enum)]
+    maze: Option<MazeKind>,
+
+    /// Carve the maze a cell per iteration before the search starts
+    #[arg(long)]
+    animate_maze: bool,
+
+    /// Scatter obstacles over this fraction of the cells instead of the built-in walls
+    #[arg(long, value_name = "DENSITY", value_parser = parse_density, conflicts_with = "maze")]
+    random_obstacles: Option<f64>,
+
+    /// Generate terrain costs, on top of which the walls or random obstacles are placed
+    #[arg(long, value_enum, conflicts_with = "maze")]
+    terrain: Option<TerrainKind>,
+
+    /// Seed for everything random, picked from the clock if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Stretch the cells to fill the whole window instead of keeping them square
+    #[arg(long)]
+    stretch: bool,
+
+    /// Size the window opens with, the cells are sized to fit into it and follow it when it
+    /// is resized
+    #[arg(long, value_name = "WxH", value_parser = parse_window_size)]
+    window_size: Option<(u32, u32)>,
+
+    /// Open the window fullscreen, at the resolution of the desktop
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// Present frames in sync with the display's refresh, so they never tear. Frames
+    /// still come at most `--fps` times a second.
+    #[arg(long)]
+    vsync: bool,
+
+    /// Also draw found paths smoothed by cutting corners wherever there is line of sight
+    #[arg(long)]
+    smooth: bool,
+
+    /// TOML config file, see `--print-keys` for the `[keys]` section. A `[colors]` section
+    /// overrides colors of the theme, e.g. `visited = "#ffcc00"` or `text = "255, 255, 255"`,
+    /// see `--print-colors` for their names.
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Print the effective keybindings and exit
+    #[arg(long)]
+    print_keys: bool,
+
+    /// Print the colors of the theme, with those of the config applied, and exit
+    #[arg(long)]
+    print_colors: bool,
+
+    /// Advance a virtual clock by exactly one frame per rendered frame instead of following
+    /// the wall clock, so the same arguments always render the same frames. Each frame runs
+    /// as many iterations as delays fit into the virtual time since the last one, and what
+    /// is left over carries on to the next frame rather than being dropped, so iterations
+    /// keep pace with `--delay`. `--record` then gets the same frames every run, encoded
+    /// into a video by `ffmpeg` rather than saved as identical PNGs.
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Let dijkstra and A* step diagonally too, at about 1.41 times the cost
+    #[arg(long)]
+    diagonals: bool,
+
+    /// Shape of the cells. Triangles alternate pointing up and down and only have the
+    /// three neighbors they share a side with, so `--diagonals` does nothing on them.
+    /// Loaded grids keep theirs without it.
+    #[arg(long, value_enum)]
+    topology: Option<Topology>,
+
+    /// Keep searching past the goal until every reachable cell has a distance from the
+    /// start, and color cells by it. The parent pointers (`toggle_parents`) then point
+    /// downhill. Only dijkstra, A*, Theta*, BFS and DFS build one.
+    #[arg(long)]
+    flow_field: bool,
+
+    /// Let diagonal moves slip between two obstacles touching at a corner. JPS never does.
+    #[arg(long, overrides_with = "no_corner_cutting")]
+    allow_corner_cutting: bool,
+
+    /// Keep diagonal moves from slipping between two obstacles touching at a corner,
+    /// the default
+    #[arg(long, overrides_with = "allow_corner_cutting")]
+    no_corner_cutting: bool,
+
+    /// Blend cells into their new color when their state changes, instead of snapping
+    #[arg(long)]
+    fade: bool,
+
+    /// Exit once the search is done, after giving the final frame this many seconds on
+    /// screen. Exits with 0 if a path was found and 2 if there is none, or no shortest one
+    /// because of a negative cycle. Pressing any key in the meantime keeps the window open.
+    #[arg(long, value_name = "SECS", num_args = 0..=1, default_missing_value = "2")]
+    exit_on_complete: Option<f64>,
+
+    /// Check every found path against a reference dijkstra, complaining if it isn't
+    /// optimal or not a valid path at all. Theta* paths are only checked for being valid,
+    /// any-angle paths don't compare to grid paths.
+    #[arg(long)]
+    verify: bool,
+
+    /// Write the main search's path to this file whenever it finds one, with the cost of
+    /// every cell on it. CSV if the name ends in `.csv`, JSON otherwise, which also has
+    /// every cell the search settled in the order it did.
+    #[arg(long, value_name = "PATH")]
+    path_out: Option<PathBuf>,
+
+    /// Append a CSV row to this file whenever the main search finishes: its algorithm,
+    /// the map and seed, cells expanded, path cost, time spent searching and the most
+    /// entries its open set had at once. The header is written when the file is new.
+    #[arg(long, value_name = "PATH")]
+    metrics_out: Option<PathBuf>,
+
+    /// Record the window into an animated GIF, which is finished once the search is
+    #[arg(long, value_name = "PATH")]
+    record_gif: Option<PathBuf>,
+
+    /// Record the window into a video by piping it to `ffmpeg`, a frame per rendered frame
+    /// at `--fps`. The container and codec follow from the file name, e.g. `out.mp4`.
+    /// Finished once the search is.
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Directory the screenshot key saves PNGs into
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    screenshot_dir: PathBuf,
+
+    /// Only record every this many frames with `--record-gif`, for smaller GIFs of long runs
+    #[arg(
+        long,
+        value_name = "FRAMES",
+        default_value_t = 1,
+        requires = "record_gif"
+    )]
+    record_every: u32,
+
+    /// What visited cells are shaded by
+    #[arg(long, value_enum, default_value_t = ColorBy::State)]
+    color_by: ColorBy,
+
+    /// Colors everything is drawn in
+    #[arg(long, value_enum, default_value_t = ThemeKind::Default)]
+    theme: ThemeKind,
+
+    /// Font text is drawn in, as the path to a .ttf or .otf file or the name of an
+    /// installed font. Monospaced fonts line up best. Uses the built-in font if not given.
+    #[arg(long, value_name = "PATH|NAME")]
+    font: Option<String>,
+
+    /// Distance, in steps, that `--color-by distance` shades reddest, instead of the
+    /// farthest cell visited so far. Keeps colors comparable between runs and maps.
+    #[arg(long, value_name = "STEPS")]
+    distance_scale: Option<f64>,
 }
 
-fn main() {
-    let env_filter =
-        tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into());
+/// Start and goal of an additional agent
+#[derive(Clone, Copy, Debug)]
+struct Agent {
+    start: (u32, u32),
+    goal: (u32, u32),
+}
+
+/// Obstacle wall from `start` to `end`, drawn once `iteration` iterations have run
+#[derive(Clone, Copy, Debug)]
+struct TimedObstacle {
+    iteration: u64,
+    start: (u32, u32),
+    end: (u32, u32),
+}
+
+fn parse_window_size(s: &str) -> Result<(u32, u32), String> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected WxH, got {:?}", s))?;
 
+    let parse = |n: &str| match n.trim().parse() {
+        Ok(0) => Err(format!("window size {:?} has to be positive", s)),
+        Ok(n) => Ok(n),
+        Err(e) => Err(format!("invalid window size {:?}: {}", s, e)),
+    };
+
+    Ok((parse(w)?, parse(h)?))
+}
+
+fn parse_cell(s: &str) -> Result<(u32, u32), String> {
+    let (x, y) = s
+        .split_once(',')
+        .ok_or_else(|| format!("expected X,Y, got {:?}", s))?;
+
+    let x = x
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid x {:?}: {}", x, e))?;
+    let y = y
+        .trim()
+        .parse()
+        .map_err(|e| format!("invalid y {:?}: {}", y, e))?;
+
+    Ok((x, y))
+}
+
+fn parse_goal(s: &str) -> Result<GoalSpec, String> {
+    let Some((kind, value)) = s.split_once(':') else {
+        return Ok(GoalSpec::Cell(parse_cell(s)?));
+    };
+
+    let parse_number = |n: &str| {
+        n.trim()
+            .parse()
+            .map_err(|e| format!("invalid number {:?}: {}", n, e))
+    };
+
+    match kind {
+        "cell" => Ok(GoalSpec::Cell(parse_cell(value)?)),
+        "rect" => {
+            let numbers = value
+                .split(',')
+                .map(parse_number)
+                .collect::<Result<Vec<u32>, _>>()?;
+
+            let [x, y, w, h] = numbers[..] else {
+                return Err(format!("expected rect:X,Y,W,H, got {:?}", s));
+            };
+
+            Ok(GoalSpec::Rect { x, y, w, h })
+        }
+        "column" => Ok(GoalSpec::Column(parse_number(value)?)),
+        "row" => Ok(GoalSpec::Row(parse_number(value)?)),
+        "cells" => Ok(GoalSpec::Cells(
+            value.split(':').map(parse_cell).collect::<Result<_, _>>()?,
+        )),
+        _ => Err(format!(
+            "unknown goal kind {:?}, expected cell, rect, column, row or cells",
+            kind
+        )),
+    }
+}
+
+fn parse_density(s: &str) -> Result<f64, String> {
+    let density: f64 = s
+        .parse()
+        .map_err(|e| format!("invalid density {:?}: {}", s, e))?;
+
+    if !(0.0..=1.0).contains(&density) {
+        return Err(format!("density must be between 0 and 1, got {}", density));
+    }
+
+    Ok(density)
+}
+
+fn parse_weight(s: &str) -> Result<f64, String> {
+    let weight: f64 = s
+        .parse()
+        .map_err(|e| format!("invalid weight {:?}: {}", s, e))?;
+
+    if !(weight >= 0.0 && weight.is_finite()) {
+        return Err(format!("weight must be a positive number, got {}", weight));
+    }
+
+    Ok(weight)
+}
+
+fn parse_agent(s: &str) -> Result<Agent, String> {
+    let (start, goal) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected START_X,START_Y:GOAL_X,GOAL_Y, got {:?}", s))?;
+
+    Ok(Agent {
+        start: parse_cell(start)?,
+        goal: parse_cell(goal)?,
+    })
+}
+
+fn parse_portal(s: &str) -> Result<Portal, String> {
+    let mut parts = s.split(':');
+
+    let (Some(a), Some(b), cost, None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("expected X1,Y1:X2,Y2[:COST], got {:?}", s));
+    };
+
+    let cost: f64 = match cost {
+        Some(cost) => cost
+            .parse()
+            .map_err(|e| format!("invalid portal cost {:?}: {}", cost, e))?,
+        None => 1.0,
+    };
+
+    // free portals would make both ends equally far, incremental searches can't follow
+    // their paths across them
+    let fixed_point = (cost * UNIT_COST as f64).round();
+    if !(fixed_point > 0.0 && cost.is_finite()) {
+        return Err(format!(
+            "portal cost has to be a positive number of at least {}, got {}",
+            0.5 / UNIT_COST as f64,
+            cost
+        ));
+    }
+
+    Ok(Portal {
+        a: parse_cell(a)?,
+        b: parse_cell(b)?,
+        cost: fixed_point as u32,
+    })
+}
+
+fn parse_timed_obstacle(s: &str) -> Result<TimedObstacle, String> {
+    let mut parts = s.split(':');
+
+    let (Some(iteration), Some(start), Some(end), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(format!("expected ITERATION:X1,Y1:X2,Y2, got {:?}", s));
+    };
+
+    Ok(TimedObstacle {
+        iteration: iteration
+            .parse()
+            .map_err(|e| format!("invalid iteration {:?}: {}", iteration, e))?,
+        start: parse_cell(start)?,
+        end: parse_cell(end)?,
+    })
+}
+
+fn main() {
     let args = Args::parse();
 
+    // every search iteration is a span, logging them would swamp the benchmark. The text
+    // frames still get what is said about them, like a verified path, on stderr.
+    let default_filter = if args.benchmark || args.headless {
+        "warn"
+    } else if args.ascii || args.tui {
+        "warn,dijkstra_visual=info,dijkstra_visual::search=warn"
+    } else {
+        "info"
+    };
+    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| default_filter.into());
+
+    // stdout is for reports and --ascii and --tui frames
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_env_filter(env_filter)
         .with_span_events(FmtSpan::CLOSE)
         .init();
 
+    let config = args
+        .config
+        .as_deref()
+        .map(Config::load)
+        .transpose()
+        .unwrap_or_else(|e| exit_with_error(&e))
+        .unwrap_or_default();
+
+    let keybindings =
+        Keybindings::from_config(&config.keys).unwrap_or_else(|e| exit_with_error(&e));
+
+    if args.print_keys {
+        print!("{}", keybindings);
+        return;
+    }
+
+    let theme = Theme::new(args.theme)
+        .with_overrides(&config.colors)
+        .unwrap_or_else(|e| exit_with_error(&e));
+
+    if args.print_colors {
+        print!("{}", theme);
+        return;
+    }
+
+    // a loaded grid keeps its own algorithm unless one is asked for
+    let requested_algorithm = if args.enable_astar {
+        Some(Algorithm::AStar)
+    } else {
+        args.algorithm
+    };
+    let algorithm = requested_algorithm.unwrap_or(Algorithm::Dijkstra);
+
+    let movingai = match (&args.movingai, &args.scen) {
+        (Some(map), Some(scen)) => Some(
+            MovingAiMap::load(map)
+                .and_then(|map| Ok((map, Scenario::load_all(scen)?)))
+                .unwrap_or_else(|e| exit_with_error(&e)),
+        ),
+        _ => None,
+    };
+
+    if args.benchmark {
+        let (map, scenarios) = movingai
+            .as_ref()
+            .expect("clap makes --benchmark require --scen and --movingai");
+
+        let optimal = benchmark::run(
+            map,
+            scenarios,
+            algorithm,
+            args.astar_weight.unwrap_or(1.0),
+            args.heuristic,
+        )
+        .unwrap_or_else(|e| exit_with_error(&e));
+        std::process::exit(if optimal { 0 } else { 1 });
+    }
+
+    // for --metrics-out
+    let map_name = map_name(&args);
+
+    let ascii_map = args
+        .map
+        .as_deref()
+        .map(AsciiMap::load)
+        .transpose()
+        .unwrap_or_else(|e| exit_with_error(&e));
+
+    let loaded = args
+        .load_map
+        .as_deref()
+        .map(Grid::load)
+        .transpose()
+        .unwrap_or_else(|e| exit_with_error(&e));
+
+    let mut grid = match (loaded, &ascii_map, &movingai) {
+        (Some(mut grid), _, _) => {
+            if let Some(algorithm) = requested_algorithm {
+                grid.set_algorithm(algorithm);
+            }
+
+            grid
+        }
+        (None, Some(map), _) => Grid::new(
+            map.width,
+            map.height,
+            map.start,
+            GoalSpec::Cell(map.goal),
+            algorithm,
+        ),
+        (None, None, Some((map, scenarios))) => {
+            let scenario = scenarios.get(args.scenario).unwrap_or_else(|| {
+                exit_with_error(&format!(
+                    "no scenario {}, there are {}",
+                    args.scenario,
+                    scenarios.len()
+                ))
+            });
+
+            benchmark::scenario_grid(map, scenario, algorithm)
+                .unwrap_or_else(|e| exit_with_error(&e))
+        }
+        (None, None, None) => {
+            let height = args.height.unwrap_or(80 * args.layers.unwrap_or(1));
+            let map = Map::new(args.width, height);
+
+            if !map.in_bounds(args.start) {
+                exit_with_error(&format!(
+                    "start {:?} is outside of the {}x{} map, pick another with --start",
+                    args.start, args.width, height
+                ));
+            }
+
+            if args.goal.cells(&map).is_empty() {
+                exit_with_error(&format!(
+                    "the goal is outside of the {}x{} map, pick another with --goal",
+                    args.width, height
+                ));
+            }
+
+            Grid::new(args.width, height, args.start, args.goal.clone(), algorithm)
+        }
+    };
+
+    if let Some(layers) = args.layers {
+        if !grid.set_layers(layers) {
+            exit_with_error(&format!(
+                "{} rows can't be split into {} layers",
+                grid.height(),
+                layers
+            ));
+        }
+    }
+
+    // without either flag loaded grids keep what they were saved with
+    if args.allow_corner_cutting || args.no_corner_cutting {
+        grid.set_corner_cutting(args.allow_corner_cutting);
+    }
+
+    if let Some(weight) = args.astar_weight {
+        grid.set_heuristic_weight(weight);
+    }
+
+    if args.heuristic.is_some() {
+        grid.set_heuristic_kind(args.heuristic);
+    }
+
+    if let Some(topology) = args.topology {
+        grid.set_topology(topology);
+    }
+
+    if args.diagonals || movingai.as_ref().is_some_and(|(map, _)| map.octile) {
+        grid.set_diagonals(true);
+    }
+
+    if args.flow_field {
+        grid.set_flow_field(true);
+    }
+
+    if !args.extra_starts.is_empty() {
+        if let Some(start) = args
+            .extra_starts
+            .iter()
+            .find(|&&start| !grid.map().in_bounds(start))
+        {
+            exit_with_error(&format!("extra start {:?} is outside of the map", start));
+        }
+
+        grid.set_extra_starts(args.extra_starts.clone());
+    }
+
+    if args.goal_drift.is_some() {
+        if !matches!(grid.searches()[0].goal(), GoalSpec::Cell(_)) {
+            exit_with_error("only a single goal cell can drift");
+        }
+
+        if let Some(cell) = args
+            .goal_waypoints
+            .iter()
+            .find(|&&cell| !grid.map().in_bounds(cell))
+        {
+            exit_with_error(&format!("goal waypoint {:?} is outside of the map", cell));
+        }
+    }
+
+    let distance_scale = args
+        .distance_scale
+        .map(|steps| (steps.max(0.0) * UNIT_COST as f64).round() as u32);
+
+    let mut view = View::default();
+
+    view.set_smooth_paths(args.smooth)
+        .set_color_by(args.color_by)
+        .set_distance_scale(distance_scale)
+        .set_theme(theme)
+        .set_fade(args.fade);
+
+    let mut rival_view = View::default();
+
+    rival_view
+        .set_smooth_paths(args.smooth)
+        .set_color_by(args.color_by)
+        .set_distance_scale(distance_scale)
+        .set_theme(theme)
+        .set_fade(args.fade);
+
+    for agent in &args.agents {
+        if let Some(cell) = [agent.start, agent.goal]
+            .into_iter()
+            .find(|&cell| !grid.map().in_bounds(cell))
+        {
+            exit_with_error(&format!("agent cell {:?} is outside of the map", cell));
+        }
+
+        grid.add_agent(agent.start, agent.goal);
+    }
+
+    for &portal in &args.portals {
+        if !grid.add_portal(portal) {
+            exit_with_error(&format!(
+                "portal from {:?} to {:?} has to connect two cells of the map",
+                portal.a, portal.b
+            ));
+        }
+    }
+
+    let seed = args.seed.unwrap_or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64)
+    });
+    tracing::info!("seed {}", seed);
+
+    let mut rng = Rng::new(seed);
+
+    // cells still to be carved out of the maze, a cell per iteration when animated
+    let mut carving = VecDeque::new();
+
+    if let Some(kind) = args.terrain {
+        grid.generate_terrain(kind, &mut rng);
+    }
+
+    match (args.maze, args.random_obstacles, &ascii_map) {
+        (Some(kind), _, _) => carving = generate_maze(&mut grid, kind, &mut rng, args.animate_maze),
+        (None, Some(density), _) => grid.scatter_obstacles(density, &mut rng),
+        (None, None, Some(map)) => {
+            grid.paint_cells(&map.obstacles, Material::Obstacle);
+            for &(cell, cost) in &map.costs {
+                grid.paint(cell, Material::Cost(cost));
+            }
+        }
+        // benchmark and saved maps come with their obstacles
+        (None, None, None) if movingai.is_some() || args.load_map.is_some() => {}
+        (None, None, None) => {
+            grid.draw_obstacle((4, 16), (18, 4));
+            grid.draw_obstacle((24, 40), (80, 0));
+            grid.draw_obstacle((15, 8), (80, 8));
+            grid.draw_obstacle((0, 30), (30, 30));
+            grid.draw_obstacle((4, 70), (70, 20));
+        }
+    }
+
+    for &cell in &args.stairs {
+        if !grid.add_stairs(cell) {
+            exit_with_error(&format!(
+                "stairs at {:?} need a layer of the map below them",
+                cell
+            ));
+        }
+    }
+
+    if args.stairs.is_empty() && args.layers.is_some() {
+        grid.scatter_stairs(RANDOM_STAIRS, &mut rng);
+    }
+
+    if args.fog.is_some() {
+        grid.set_fog(args.fog);
+    }
+
+    // the same map searched with the --compare algorithm, drawn next to the main grid
+    let mut rival = args.compare.map(|algorithm| grid.with_algorithm(algorithm));
+
+    let mut race = (!args.race.is_empty()).then(|| Race::new(&grid, &args.race));
+    // whether the standings of the finished race were logged
+    let mut race_logged = false;
+
+    let metrics = args
+        .metrics_out
+        .as_deref()
+        .map(|path| (path, map_name.as_str(), seed));
+
+    if args.headless {
+        run_headless(
+            grid,
+            carving,
+            rival,
+            race,
+            args.verify,
+            args.path_out.as_deref(),
+            metrics,
+        );
+    }
+
+    if args.ascii || args.tui {
+        grid.carve(carving.make_contiguous());
+
+        let delay = Duration::from_millis(args.delay);
+        let frame_interval = Duration::from_secs_f64(1.0 / args.fps.max(1) as f64);
+        // the terminal is put back before exiting
+        let finished = {
+            let session = Session::start(args.tui);
+            let finished = if args.tui {
+                let mut renderer = BlockRenderer::new(pane_label);
+                terminal::animate(
+                    &mut grid,
+                    &mut renderer,
+                    &mut view,
+                    delay,
+                    frame_interval,
+                    &session,
+                )
+            } else {
+                let mut renderer = TextRenderer { footer: pane_label };
+                terminal::animate(
+                    &mut grid,
+                    &mut renderer,
+                    &mut view,
+                    delay,
+                    frame_interval,
+                    &session,
+                )
+            };
+
+            if finished {
+                session.linger();
+            }
+            finished
+        };
+
+        if !finished {
+            std::process::exit(130);
+        }
+
+        // stdout carries the frames
+        if finish_outputs(&grid, args.verify, args.path_out.as_deref(), metrics) {
+            tracing::info!("path verified");
+        }
+
+        std::process::exit(match grid.status() {
+            SearchStatus::Found => 0,
+            _ => 2,
+        })
+    }
+
     let sdl_context = sdl2::init().unwrap();
 
     let mut histogram =
@@ -50,401 +1013,1344 @@ fn main() {
 
     let video = sdl_context.video().unwrap();
 
-    let window = video
-        .window("dijkstra", W, H)
-        .position_centered()
+    let (window_width, window_height) = args.window_size.unwrap_or((W, H));
+    let mut window = video.window("dijkstra", window_width, window_height);
+    window.position_centered().resizable();
+
+    if args.fullscreen {
+        window.fullscreen_desktop();
+    }
+
+    let window = window
         .build()
-        .unwrap();
+        .unwrap_or_else(|e| exit_with_error(&format!("couldn't open the window: {}", e)));
 
-    let mut canvas = window.into_canvas().build().unwrap();
+    let mut canvas = window.into_canvas();
+    if args.vsync {
+        canvas = canvas.present_vsync();
+    }
+    let mut canvas = canvas.build().unwrap();
 
     let mut pump = sdl_context.event_pump().unwrap();
 
-    let mut grid = Grid::new(80, 80, (64, 4), (74, 40), args.enable_astar);
+    let mut recorders: Vec<Box<dyn Recorder>> = Vec::new();
+    // whether the next frame gets saved as a screenshot
+    let mut screenshot = false;
+    let size = canvas.output_size().unwrap();
+
+    if let Some(path) = &args.record_gif {
+        let gif = GifRecorder::create(path, size, args.record_every);
+        recorders.push(Box::new(gif.unwrap_or_else(|e| exit_with_error(&e))));
+    }
+
+    if let Some(path) = &args.record {
+        let video = VideoRecorder::create(path, size, args.fps.max(1));
+        recorders.push(Box::new(video.unwrap_or_else(|e| exit_with_error(&e))));
+    }
 
-    grid.draw_obstacle((4, 16), (18, 4));
-    grid.draw_obstacle((24, 40), (80, 0));
-    grid.draw_obstacle((15, 8), (80, 8));
-    grid.draw_obstacle((0, 30), (30, 30));
-    grid.draw_obstacle((4, 70), (70, 20));
+    // the map summary and the frame it was made in, made once the map is complete
+    let mut summary = None;
 
     let texture_creator = canvas.texture_creator();
 
     let ttf = sdl2::ttf::init().unwrap();
 
-    let font = ttf
-        .load_font("/usr/share/fonts/liberation/LiberationMono-Regular.ttf", 20)
-        .unwrap();
+    let font_source =
+        FontSource::find(args.font.as_deref()).unwrap_or_else(|e| exit_with_error(&e));
+    let font = font_source
+        .load(&ttf, 20)
+        .unwrap_or_else(|e| exit_with_error(&e));
 
-    let dijkstra_interval = Duration::from_millis(args.delay);
-    let frame_interval = Duration::from_secs_f64(1.0 / args.fps as f64);
+    let mut atlas: Option<GlyphAtlas> = None;
 
-    let mut begin_last_dijkstra = Instant::now();
-    let mut begin_last_frame = Instant::now();
+    let mut ui = Ui::new(&texture_creator, &font, theme);
+
+    // the cells of the main pane and of the compared search
+    let mut cell_canvases = [
+        CellCanvas::new(&texture_creator),
+        CellCanvas::new(&texture_creator),
+    ];
 
-    let mut finished_last_frame = Instant::now();
+    let mut playback = Playback::new(Duration::from_millis(args.delay), args.fps);
+
+    let mut timed_obstacles = args.timed_obstacles.clone();
+    timed_obstacles.sort_by_key(|obstacle| std::cmp::Reverse(obstacle.iteration));
+    let mut iterations: u64 = 0;
+    // index of the --goal-waypoint the drifting goal walks to
+    let mut waypoint = 0;
+    let mut frames: u64 = 0;
+    let mut begin_last_dijkstra = Instant::now();
+    // when the next frame is due, a frame interval after the last one was due rather
+    // than after it was done, so the time it took doesn't add up over frames
+    let mut next_frame = Instant::now();
+    // when the last frame was presented, the histogram records the time between presents
+    let mut last_present: Option<Instant> = None;
+
+    let started = Instant::now();
+    // time the current frame is drawn at, virtual when deterministic
+    let mut clock = Duration::ZERO;
+
+    let mut exit_after = args
+        .exit_on_complete
+        .map(|secs| Duration::from_secs_f64(secs.max(0.0)));
+    if let Some(grace) = exit_after {
+        tracing::info!("exiting {:.1?} after the search is done", grace);
+    }
+    // when the search was first seen done, to count down to exiting
+    let mut completed_at: Option<Duration> = None;
+    let mut exit_code = None;
+
+    // outcome of checking the main search's path, once it is checked
+    let mut verification: Option<Result<(), String>> = None;
+    // cost of the shortest path, worked out once a search that may miss it found one
+    let mut shortest: Option<Option<i64>> = None;
+    // whether the current path was written to --path-out, a new path overwrites it
+    let mut path_exported = false;
+    // whether the finished search has its row in --metrics-out, every run gets one
+    let mut metrics_written = false;
+
+    let mut editor = Editor::default();
+    let mut camera = Camera::default();
+    // where the mouse was last seen, for the wheel to zoom around
+    let mut mouse = (0, 0);
+    // the middle button going down, and whether the mouse moved far enough since to pan
+    // rather than add or remove a goal cell once it is released
+    let mut panning: Option<(Event, bool)> = None;
+    let mut show_values = false;
+    let mut show_chart = false;
+    // layer of the map that is drawn
+    let mut layer = 0;
+    let mut open_set_chart = Sparkline::new(CHART_ITERATIONS);
 
     'main: loop {
-        if begin_last_dijkstra.elapsed() >= dijkstra_interval {
+        let (w, h) = canvas.output_size().unwrap();
+        // the map can lose layers when it's loaded
+        layer = layer.min(grid.map().layers() - 1);
+
+        // side by side with the compared search
+        let pane_width = if rival.is_some() { w / 2 } else { w };
+
+        let fitting = Layout::new(
+            grid.width(),
+            grid.map().layer_height(),
+            layer * grid.map().layer_height(),
+            pane_width,
+            h.saturating_sub(BAR_HEIGHT),
+            args.stretch,
+            grid.map().topology(),
+        );
+        // clicks on the minimap mustn't paint the cells under it
+        let minimap = minimap_rect(&grid, pane_width);
+        let layout = if camera.zoomed() {
+            fitting.viewed(&camera).covered_by(minimap)
+        } else {
+            fitting.viewed(&camera)
+        };
+        let rival_layout = layout.shifted(pane_width as i32);
+
+        let dijkstra_interval = playback.delay;
+        let frame_interval = playback.frame_interval();
+
+        let due = if args.edit {
+            0
+        } else if playback.paused {
+            std::mem::take(&mut playback.steps)
+        } else if args.deterministic {
+            playback.deterministic_due()
+        } else if begin_last_dijkstra.elapsed() >= dijkstra_interval {
             begin_last_dijkstra = Instant::now();
+            1
+        } else {
+            0
+        };
+
+        for _ in 0..due {
+            // the search waits for the maze to be done
+            if let Some(cell) = carving.pop_front() {
+                grid.carve(&[cell]);
+                continue;
+            }
+
+            // sorted latest first, so the next one due is always at the end
+            while let Some(obstacle) = timed_obstacles.last() {
+                if obstacle.iteration > iterations {
+                    break;
+                }
+
+                grid.draw_obstacle(obstacle.start, obstacle.end);
+                timed_obstacles.pop();
+            }
+
+            if args
+                .goal_drift
+                .is_some_and(|every| iterations > 0 && iterations.is_multiple_of(every))
+            {
+                match args.goal_waypoints.get(waypoint) {
+                    Some(&target) => {
+                        // reached or walled off waypoints are left for the next one
+                        let moved = grid.step_goal_towards(target);
+                        if !moved || *grid.searches()[0].goal() == GoalSpec::Cell(target) {
+                            waypoint = (waypoint + 1) % args.goal_waypoints.len();
+                        }
+                    }
+                    None => {
+                        grid.wander_goal(&mut rng);
+                    }
+                }
+            }
+
+            // only running searches are charted, so the final shape stays on screen
+            let running = grid.status() == SearchStatus::Running;
 
             grid.dijkstra_iteration();
+            iterations += 1;
+
+            if let Some(rival) = &mut rival {
+                sync_rival(&grid, rival);
+                rival.dijkstra_iteration();
+            }
+
+            if let Some(race) = &mut race {
+                race.step(&grid);
+            }
+
+            // a new run of the search gets a new chart
+            open_set_chart.follow(grid.searches()[0].restarts());
+            if running {
+                open_set_chart.push(grid.open_set_size());
+            }
         }
 
-        if begin_last_frame.elapsed() >= dijkstra_interval {
-            begin_last_frame = Instant::now();
+        if args.verify {
+            match grid.status() {
+                SearchStatus::Found if verification.is_none() => {
+                    let result = grid.verify();
 
-            canvas.set_draw_color(Color::GRAY);
+                    match &result {
+                        Ok(()) => tracing::info!("path verified"),
+                        Err(e) => tracing::error!("path verification failed: {}", e),
+                    }
+
+                    verification = Some(result);
+                }
+                SearchStatus::Running => verification = None,
+                _ => (),
+            }
+        }
+
+        let main_search = &grid.searches()[0];
+        match grid.status() {
+            SearchStatus::Found if shortest.is_none() && !main_search.finds_shortest() => {
+                shortest = Some(grid.shortest_cost());
+            }
+            SearchStatus::Running => shortest = None,
+            _ => (),
+        }
+
+        if let Some(path) = &args.path_out {
+            match grid.status() {
+                SearchStatus::Found if !path_exported => {
+                    match grid.export_path(path) {
+                        Ok(()) => tracing::info!("wrote path to {}", path.display()),
+                        Err(e) => tracing::error!("{}", e),
+                    }
+
+                    path_exported = true;
+                }
+                SearchStatus::Running => path_exported = false,
+                _ => (),
+            }
+        }
+
+        if let Some(path) = &args.metrics_out {
+            match grid.status() {
+                SearchStatus::Running | SearchStatus::Revealing => metrics_written = false,
+                _ if !metrics_written => {
+                    if let Err(e) = append_metrics(path, &grid, &map_name, seed) {
+                        tracing::error!("{}", e);
+                    }
+
+                    metrics_written = true;
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(race) = &race {
+            match race.finished() {
+                true if !race_logged => {
+                    for line in race.standings() {
+                        tracing::info!("{}", line);
+                    }
+                    race_logged = true;
+                }
+                true => (),
+                false => race_logged = false,
+            }
+        }
+
+        if args.deterministic || Instant::now() >= next_frame {
+            next_frame += frame_interval;
+            // more than a whole frame late, catching up would only rush the next frames
+            if next_frame < Instant::now() {
+                next_frame = Instant::now() + frame_interval;
+            }
+            frames += 1;
+
+            playback.frame_passed();
+
+            clock = if args.deterministic {
+                clock + frame_interval
+            } else {
+                started.elapsed()
+            };
+
+            canvas.set_draw_color(theme.background);
             canvas.clear();
 
-            grid.draw_to_canvas(&mut canvas, W, H);
+            // zoomed in cells mustn't spill into the other pane or the settings bar
+            let pane = |x| Rect::new(x, 0, pane_width, h.saturating_sub(BAR_HEIGHT));
+
+            Pane {
+                cells: &mut cell_canvases[0],
+                canvas: &mut canvas,
+                layout: &layout,
+                rect: pane(0),
+            }
+            .draw_cells(&mut view, &grid, clock)
+            .unwrap_or_else(|e| exit_with_error(&e));
+            canvas.set_clip_rect(pane(0));
+            view.draw_overlays(&mut canvas, &grid, &layout);
+            canvas.set_clip_rect(None);
+
+            if let Some(rival) = &mut rival {
+                // edits while paused show up right away
+                sync_rival(&grid, rival);
+
+                rival_view.show_parents(view.parents_shown());
+                rival_view.show_heuristic_overlay(view.heuristic_overlay_shown());
+                Pane {
+                    cells: &mut cell_canvases[1],
+                    canvas: &mut canvas,
+                    layout: &layout,
+                    rect: pane(pane_width as i32),
+                }
+                .draw_cells(&mut rival_view, rival, clock)
+                .unwrap_or_else(|e| exit_with_error(&e));
+                canvas.set_clip_rect(pane(pane_width as i32));
+                rival_view.draw_overlays(&mut canvas, rival, &rival_layout);
+                canvas.set_clip_rect(None);
+
+                for (pane, x) in [(&grid, 0), (&*rival, pane_width as i32)] {
+                    render_colored_text(
+                        &mut canvas,
+                        &texture_creator,
+                        &font,
+                        &pane_label(pane),
+                        x,
+                        h.saturating_sub(BAR_HEIGHT + 20) as i32,
+                        theme.text,
+                    );
+                }
+            }
+
+            if camera.zoomed() {
+                let visible = layout.visible(pane_width, h.saturating_sub(BAR_HEIGHT));
+
+                view.draw_minimap(&mut canvas, &grid, &layout, minimap, visible);
+            }
+
+            if let Some(cell) = editor.hover() {
+                // every cell the brush or the shape being dragged out would paint
+                let cells = editor.footprint(&grid, cell);
+
+                canvas.set_draw_color(theme.lines);
+                canvas.set_clip_rect(pane(0));
+                for cell in cells {
+                    view::outline_cell(&mut canvas, &layout, cell);
+                }
+                canvas.set_clip_rect(None);
+            }
+
+            let (cell_width, cell_height) = layout.cell_size();
+            if show_values && cell_width.min(cell_height) >= MIN_LABELED_CELL_SIZE {
+                // three lines of text have to fit into a cell
+                let point_size = (cell_height / 4) as u16;
+
+                if atlas.as_ref().map(GlyphAtlas::point_size) != Some(point_size) {
+                    atlas = Some(GlyphAtlas::new(
+                        &font_source
+                            .load(&ttf, point_size)
+                            .unwrap_or_else(|e| exit_with_error(&e)),
+                        point_size,
+                        &texture_creator,
+                    ));
+                }
+
+                if let Some(atlas) = &atlas {
+                    canvas.set_clip_rect(pane(0));
+                    view.draw_values(&mut canvas, &grid, atlas, &layout);
+                    canvas.set_clip_rect(None);
+                }
+            }
+
+            // frame times differ from run to run
+            if args.deterministic {
+                render_colored_text(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &format!("FRAME {} ITERATION {}", frames, iterations),
+                    0,
+                    0,
+                    theme.text,
+                );
+            } else {
+                render_colored_text(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &format!("AVG Frame Time: {:.5}", histogram.mean()),
+                    0,
+                    0,
+                    theme.text,
+                );
 
-            render_text(
+                render_colored_text(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &format!("95th Frame Time: {}", histogram.value_at_quantile(0.95)),
+                    0,
+                    20,
+                    theme.text,
+                );
+            }
+
+            render_colored_text(
                 &mut canvas,
                 &texture_creator,
                 &font,
-                &format!("AVG Frame Time: {:.5}", histogram.mean()),
-                0,
+                &match &args.save_map {
+                    Some(path) if args.edit => format!("EDITING {}", path.display()),
+                    _ => running_label(&grid.searches()[0]),
+                },
                 0,
+                40,
+                theme.text,
             );
 
-            render_text(
+            render_colored_text(
                 &mut canvas,
                 &texture_creator,
                 &font,
-                &format!("95th Frame Time: {}", histogram.value_at_quantile(0.95)),
+                &tool_label(&editor, &grid, layer),
                 0,
-                20,
+                60,
+                theme.text,
             );
 
-            render_text(
+            // search times differ from run to run too
+            render_colored_text(
                 &mut canvas,
                 &texture_creator,
                 &font,
-                if args.enable_astar {
-                    "RUNNING A*"
-                } else {
-                    "RUNNING PURE DIJKSTRA"
-                },
+                &stats_label(&grid.searches()[0], !args.deterministic),
                 0,
-                40,
+                80,
+                theme.text,
+            );
+
+            if summary.is_none() && carving.is_empty() {
+                let map_summary = grid.summary();
+
+                match map_summary.connected {
+                    Some(false) => tracing::warn!("{}", map_summary),
+                    _ => tracing::info!("{}", map_summary),
+                }
+
+                summary = Some((map_summary, frames));
+            }
+
+            // counted in frames, so deterministic runs render it the same way
+            if let Some((summary, since)) = &summary {
+                if ((frames - since) as f64) < SUMMARY_DURATION.as_secs_f64() * playback.fps as f64
+                {
+                    render_colored_text(
+                        &mut canvas,
+                        &texture_creator,
+                        &font,
+                        &summary.to_string(),
+                        0,
+                        120,
+                        theme.text,
+                    );
+                }
+            }
+
+            if let Some(Err(e)) = &verification {
+                render_colored_text(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &format!("NOT VERIFIED: {}", e),
+                    0,
+                    140,
+                    theme.error_text,
+                );
+            }
+
+            if let Some(race) = race.as_ref().filter(|race| race.finished()) {
+                for (i, line) in race.standings().iter().enumerate() {
+                    render_colored_text(
+                        &mut canvas,
+                        &texture_creator,
+                        &font,
+                        line,
+                        0,
+                        160 + 20 * i as i32,
+                        theme.text,
+                    );
+                }
+            }
+
+            match grid.status() {
+                SearchStatus::Running | SearchStatus::Revealing => {
+                    if let Some(fog) = grid.fog() {
+                        render_colored_text(
+                            &mut canvas,
+                            &texture_creator,
+                            &font,
+                            &format!("WALKING: {}", walked_label(fog)),
+                            0,
+                            100,
+                            theme.text,
+                        );
+                    }
+                }
+                SearchStatus::Found => {
+                    let cost = grid.path_cost().unwrap_or_default();
+
+                    let text = match shortest {
+                        Some(Some(shortest)) if cost > shortest => format!(
+                            "PATH COST: {}, NOT SHORTEST, that costs {}",
+                            format_dist(cost),
+                            format_dist(shortest)
+                        ),
+                        Some(Some(_)) => format!("PATH COST: {}, the shortest", format_dist(cost)),
+                        _ => format!("PATH COST: {}", format_dist(cost)),
+                    };
+
+                    let search = &grid.searches()[0];
+                    let mut text = format!("{}, {} cells expanded", text, search.expanded());
+
+                    // which of several goal cells won
+                    if let (GoalSpec::Cells(cells), Some(&reached)) =
+                        (search.goal(), search.path().last())
+                    {
+                        if let Some(i) = cells.iter().position(|&cell| cell == reached) {
+                            text = format!(
+                                "{}, reached goal {} of {} at {:?}",
+                                text,
+                                i + 1,
+                                cells.len(),
+                                reached
+                            );
+                        }
+                    }
+
+                    if let Some(fog) = grid.fog() {
+                        text = format!("{}, {}", text, walked_label(fog));
+                    }
+
+                    render_colored_text(
+                        &mut canvas,
+                        &texture_creator,
+                        &font,
+                        &text,
+                        0,
+                        100,
+                        theme.text,
+                    )
+                }
+                SearchStatus::NoPath { trapped } => render_colored_text(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &format!("NO PATH, {}", trapped_label(&grid.searches()[0], trapped)),
+                    0,
+                    100,
+                    theme.text,
+                ),
+                SearchStatus::NegativeCycle { length } => render_colored_text(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &format!(
+                        "NEGATIVE CYCLE of {} cells, going around it makes paths cheaper forever",
+                        length
+                    ),
+                    0,
+                    100,
+                    theme.text,
+                ),
+            }
+
+            if show_chart {
+                let rect = Rect::new(
+                    w.saturating_sub(CHART_WIDTH + 10) as i32,
+                    h.saturating_sub(BAR_HEIGHT + CHART_HEIGHT + 10) as i32,
+                    CHART_WIDTH,
+                    CHART_HEIGHT,
+                );
+
+                open_set_chart.follow(grid.searches()[0].restarts());
+                open_set_chart.draw(&mut canvas, rect);
+
+                if let Some((min, max)) = open_set_chart.range() {
+                    render_colored_text(
+                        &mut canvas,
+                        &texture_creator,
+                        &font,
+                        &format!("OPEN SET {}..{}", min, max),
+                        rect.x(),
+                        rect.y() - 20,
+                        theme.text,
+                    );
+                }
+            }
+
+            let new_map = draw_settings_bar(
+                &mut canvas,
+                &mut ui,
+                Rect::new(0, h.saturating_sub(BAR_HEIGHT) as i32, w, BAR_HEIGHT),
+                &mut playback,
+                &mut grid,
+            );
+            ui.end_frame();
+
+            if new_map {
+                carving = regenerate_map(&mut grid, &args, &mut rng);
+                summary = None;
+                editor.clear_history();
+            }
+
+            if std::mem::take(&mut screenshot) {
+                match save_screenshot(&canvas, &args.screenshot_dir) {
+                    Ok(path) => tracing::info!("saved screenshot {}", path.display()),
+                    Err(e) => tracing::error!("{}", e),
+                }
+            }
+
+            recorders.retain_mut(|recorder| match recorder.capture(&canvas, frame_interval) {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!("recording failed, stopping: {}", e);
+                    false
+                }
+            });
+
+            let done = matches!(
+                grid.status(),
+                SearchStatus::Found
+                    | SearchStatus::NoPath { .. }
+                    | SearchStatus::NegativeCycle { .. }
             );
+            if done && carving.is_empty() {
+                finish_recordings(&mut recorders);
+            }
 
             canvas.present();
 
-            histogram
-                .record(finished_last_frame.elapsed().as_micros() as u64)
-                .unwrap();
+            if let Some(last_present) = last_present {
+                histogram
+                    .record(last_present.elapsed().as_micros() as u64)
+                    .unwrap();
+            }
 
-            finished_last_frame = Instant::now();
+            last_present = Some(Instant::now());
+        }
+
+        if let Some(delay) = exit_after {
+            let code = match grid.status() {
+                SearchStatus::Running | SearchStatus::Revealing => None,
+                SearchStatus::Found => Some(0),
+                SearchStatus::NoPath { .. } | SearchStatus::NegativeCycle { .. } => Some(2),
+            };
+
+            // editing the map restarts the search, and with it the countdown
+            if code.is_none() {
+                completed_at = None;
+            }
+
+            if let Some(code) = code {
+                if completed_at.is_none() {
+                    tracing::info!(
+                        "search done, exiting in {:.1?} unless a key is pressed",
+                        delay
+                    );
+                }
+
+                if clock.saturating_sub(*completed_at.get_or_insert(clock)) >= delay {
+                    exit_code = Some(code);
+                    break 'main;
+                }
+            }
         }
 
         for e in pump.poll_iter() {
             match e {
-                sdl2::event::Event::Quit { .. } => break 'main,
+                Event::Quit { .. } => break 'main,
+                Event::KeyDown { .. } if completed_at.is_some() && exit_after.is_some() => {
+                    tracing::info!("key pressed, not exiting on completion");
+                    exit_after = None;
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } => match keybindings
+                    .action_for(keycode, keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD))
+                {
+                    // Ctrl+Shift+Z redoes too, like it does elsewhere
+                    Some(Action::Undo) if !keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+                        editor.undo(&mut grid)
+                    }
+                    Some(Action::Undo | Action::Redo) => editor.redo(&mut grid),
+                    Some(Action::Save) => match &args.save_map {
+                        Some(path) => save_grid(&grid, path),
+                        None => tracing::warn!("nowhere to save to, see --save-map"),
+                    },
+                    Some(Action::ToggleValues) => show_values = !show_values,
+                    Some(Action::ToggleHeuristicOverlay) => {
+                        view.show_heuristic_overlay(!view.heuristic_overlay_shown())
+                    }
+                    Some(Action::ToggleParents) => view.show_parents(!view.parents_shown()),
+                    Some(Action::ToggleOpenSetChart) => show_chart = !show_chart,
+                    Some(Action::Pause) => playback.toggle_pause(),
+                    Some(Action::Maze) => {
+                        carving = regenerate_map(&mut grid, &args, &mut rng);
+                        summary = None;
+                        editor.clear_history();
+                    }
+                    Some(Action::Step) => playback.step(),
+                    // the map, endpoints and settings stay, the searches start over
+                    Some(Action::Reset) => {
+                        grid.clear_search();
+
+                        if let Some(rival) = &mut rival {
+                            rival.clear_search();
+                        }
+                    }
+                    Some(Action::SkipReveal) => grid.skip_reveal(),
+                    Some(Action::DecreaseDelay) => playback.decrease_delay(),
+                    Some(Action::IncreaseDelay) => playback.increase_delay(),
+                    Some(Action::HalveDelay) => playback.halve_delay(),
+                    Some(Action::DoubleDelay) => playback.double_delay(),
+                    Some(Action::DecreaseWeight) => {
+                        let weight = grid.searches()[0].heuristic_weight() - WEIGHT_STEP;
+                        grid.set_heuristic_weight(weight.max(0.0));
+                    }
+                    Some(Action::IncreaseWeight) => {
+                        let weight = grid.searches()[0].heuristic_weight() + WEIGHT_STEP;
+                        grid.set_heuristic_weight(weight);
+                    }
+                    Some(Action::Obstacle) => editor.set_material(Material::Obstacle),
+                    Some(Action::Cost1) => editor.set_material(Material::Cost(1)),
+                    Some(Action::Cost2) => editor.set_material(Material::Cost(2)),
+                    Some(Action::Cost3) => editor.set_material(Material::Cost(3)),
+                    Some(Action::Cost4) => editor.set_material(Material::Cost(4)),
+                    Some(Action::Cost5) => editor.set_material(Material::Cost(5)),
+                    Some(Action::Negative) => editor.set_material(Material::Negative),
+                    Some(Action::Eraser) => editor.set_material(Material::Eraser),
+                    Some(Action::Brush) => editor.set_tool(Tool::Brush),
+                    Some(Action::Fill) => editor.set_tool(Tool::Fill),
+                    Some(Action::Inspect) => editor.set_tool(Tool::Inspect),
+                    Some(Action::ShrinkBrush) => editor.shrink_brush(),
+                    Some(Action::GrowBrush) => editor.grow_brush(),
+                    Some(Action::Line) => editor.set_tool(Tool::Shape(Shape::Line)),
+                    Some(Action::Rectangle) => {
+                        editor.set_tool(Tool::Shape(Shape::Rectangle { filled: true }))
+                    }
+                    Some(Action::Circle) => {
+                        editor.set_tool(Tool::Shape(Shape::Circle { filled: true }))
+                    }
+                    Some(Action::ToggleFilled) => editor.toggle_filled(),
+                    Some(Action::Screenshot) => screenshot = true,
+                    Some(Action::LayerUp) => layer = layer.saturating_sub(1),
+                    Some(Action::LayerDown) => layer = (layer + 1).min(grid.map().layers() - 1),
+                    Some(Action::ResetView) => camera = Camera::default(),
+                    None => continue,
+                },
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } => {
+                    // the layout has no cells under the settings bar, only it reacts there
+                    ui.click(x, y);
+                    editor.handle_mouse(&e, &layout, &mut grid);
+                }
+                Event::MouseButtonDown {
+                    mouse_btn: MouseButton::Middle,
+                    ..
+                } => panning = Some((e, false)),
+                Event::MouseButtonUp {
+                    mouse_btn: MouseButton::Middle,
+                    ..
+                } => {
+                    // a click without panning goes to the editor after all
+                    if let Some((down, false)) = panning.take() {
+                        editor.handle_mouse(&down, &layout, &mut grid);
+                    }
+                }
+                Event::MouseMotion {
+                    x, y, xrel, yrel, ..
+                } => {
+                    mouse = (x, y);
+
+                    if let Some((
+                        Event::MouseButtonDown {
+                            x: from_x,
+                            y: from_y,
+                            ..
+                        },
+                        moved,
+                    )) = &mut panning
+                    {
+                        *moved |= (x - *from_x).abs().max((y - *from_y).abs()) > PAN_THRESHOLD;
+                        camera.pan(xrel, yrel);
+                    }
+
+                    editor.handle_mouse(&e, &layout, &mut grid);
+                }
+                Event::MouseWheel { y, .. } if y != 0 => {
+                    camera.zoom_at(&fitting, ZOOM_STEP.powi(y), mouse);
+                    editor.forget_hover();
+                }
+                Event::MouseButtonDown { .. } | Event::MouseButtonUp { .. } => {
+                    editor.handle_mouse(&e, &layout, &mut grid)
+                }
+                // the textures lost their cells, or are gone altogether
+                Event::RenderTargetsReset { .. } => {
+                    view.invalidate();
+                    rival_view.invalidate();
+                }
+                Event::RenderDeviceReset { .. } => {
+                    cell_canvases = [
+                        CellCanvas::new(&texture_creator),
+                        CellCanvas::new(&texture_creator),
+                    ];
+                }
+                Event::Window {
+                    win_event: WindowEvent::SizeChanged(width, height),
+                    ..
+                } => {
+                    // the next frame lays the cells out anew, the mouse is over another one
+                    editor.forget_hover();
+
+                    if !recorders.is_empty() {
+                        tracing::warn!(
+                            "window resized to {}x{}, recordings stay {}x{} and crop or pad it",
+                            width,
+                            height,
+                            size.0,
+                            size.1
+                        );
+                    }
+                }
                 _ => continue,
             }
         }
 
+        let time_till_frame = next_frame.saturating_duration_since(Instant::now());
+
+        // iterations are tied to frames, waiting only keeps the playback speed watchable
+        if args.deterministic {
+            std::thread::sleep(time_till_frame);
+            continue;
+        }
+
         let time_till_dijkstra = dijkstra_interval.saturating_sub(begin_last_dijkstra.elapsed());
-        let time_till_frame = frame_interval.saturating_sub(begin_last_frame.elapsed());
 
         std::thread::sleep(std::cmp::min(time_till_dijkstra, time_till_frame));
     }
-}
 
-fn render_text<T: RenderTarget, C>(
-    canvas: &mut Canvas<T>,
-    texture_creater: &TextureCreator<C>,
-    font: &Font,
-    text: &str,
-    x: i32,
-    y: i32,
-) {
-    let surface = font.render(text).solid(Color::BLACK).unwrap();
-    let mut rect = surface.rect();
-    rect.offset(x, y);
-
-    canvas
-        .copy(&surface.as_texture(texture_creater).unwrap(), None, rect)
-        .unwrap();
-}
+    finish_recordings(&mut recorders);
 
-#[derive(Clone, Copy, Debug)]
-enum CellState {
-    Unknown,
-    Unvisited,
-    Visited { dist: u32 },
-    Obstacle,
-    OnPath,
-}
+    if let Some(path) = &args.save_map {
+        save_grid(&grid, path);
+    }
 
-#[derive(Clone, Copy, Eq, PartialEq, Debug)]
-struct UnvisitedState {
-    /// This optionally includes euclidean distance when using A*
-    pub dist: u32,
-    /// This never includes euclidean distance
-    pub actual_dist: u32,
-    pub cell: (u32, u32),
+    if let Some(code) = exit_code {
+        std::process::exit(code);
+    }
 }
 
-impl Ord for UnvisitedState {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other
-            .dist
-            .cmp(&self.dist)
-            .then(other.actual_dist.cmp(&self.actual_dist))
-            .then_with(|| self.cell.cmp(&other.cell))
+/// Replaces the map with a maze. Unless animated it is carved right away, otherwise the
+/// cells still to be carved are returned.
+fn generate_maze(
+    grid: &mut Grid,
+    kind: MazeKind,
+    rng: &mut Rng,
+    animate: bool,
+) -> VecDeque<(u32, u32)> {
+    let cells = grid.begin_maze(kind, rng);
+
+    if animate {
+        return cells.into();
     }
+
+    grid.carve(&cells);
+    VecDeque::new()
 }
 
-impl PartialOrd for UnvisitedState {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+/// Saves `grid` to `path`, logging whether that worked
+fn save_grid(grid: &Grid, path: &Path) {
+    match grid.save(path) {
+        Ok(()) => tracing::info!("saved grid to {}", path.display()),
+        Err(e) => tracing::error!("{}", e),
     }
 }
 
-#[derive(Debug)]
-pub struct Grid {
-    enable_astar: bool,
-
-    cells: Vec<Vec<CellState>>,
-    unvisited: BinaryHeap<UnvisitedState>,
+/// A new map like the one the flags asked for, drawn from the same random numbers so a
+/// seed always gives the same sequence of maps: a new maze, new random obstacles, or
+/// new terrain around the same walls. Maps that weren't random get a maze. Returns the
+/// cells still to be carved like [`generate_maze`].
+fn regenerate_map(grid: &mut Grid, args: &Args, rng: &mut Rng) -> VecDeque<(u32, u32)> {
+    match (args.maze, args.random_obstacles, args.terrain) {
+        (Some(kind), _, _) => return generate_maze(grid, kind, rng, args.animate_maze),
+        (None, Some(density), terrain) => {
+            let all: Vec<_> = (0..grid.width())
+                .flat_map(|x| (0..grid.height()).map(move |y| (x, y)))
+                .collect();
+            grid.carve(&all);
+
+            if let Some(kind) = terrain {
+                grid.generate_terrain(kind, rng);
+            }
+            grid.scatter_obstacles(density, rng);
+        }
+        (None, None, Some(kind)) => grid.generate_terrain(kind, rng),
+        (None, None, None) => {
+            return generate_maze(grid, MazeKind::Backtracker, rng, args.animate_maze)
+        }
+    }
 
-    start: (u32, u32),
-    current: (u32, u32),
-    current_dist: u32,
-    goal: (u32, u32),
+    VecDeque::new()
 }
 
-impl Grid {
-    pub fn new(w: u32, h: u32, start: (u32, u32), goal: (u32, u32), enable_astar: bool) -> Self {
-        assert!(start.0 < w && start.1 < h, "start isn't in bounds");
-        assert!(goal.0 < w && goal.1 < h, "goal isn't in bounds");
-
-        let mut grid = Self {
-            enable_astar,
-            cells: vec![vec![CellState::Unknown; h as usize]; w as usize],
-            unvisited: BinaryHeap::new(),
-            start,
-            current: start,
-            current_dist: 0,
-            goal,
-        };
-
-        grid.set_cell(grid.current, CellState::Unvisited);
+/// The editor's tool and material, and on maps with several layers which one is shown
+/// and which one the main search is at
+fn tool_label(editor: &Editor, grid: &Grid, layer: u32) -> String {
+    let mut label = format!("Tool: {} Material: {}", editor.tool(), editor.material());
+    if editor.tool() == Tool::Brush {
+        label = format!("{} Size: {}", label, editor.brush_size());
+    }
 
-        grid
+    let map = grid.map();
+    if map.layers() == 1 {
+        return label;
     }
 
-    pub fn set_width(&mut self, w: u32) -> &mut Grid {
-        let height = self.height();
+    format!(
+        "{} Layer: {}/{} Current node on: {}",
+        label,
+        layer + 1,
+        map.layers(),
+        map.layer_of(grid.searches()[0].current()) + 1
+    )
+}
 
-        self.cells
-            .resize_with(w as usize, || vec![CellState::Unknown; height as usize]);
-        self
+/// Verifies, exports and logs the metrics of the finished main search, for the runs
+/// without a window that exit right after. Returns whether the path was verified, for
+/// the caller to say so where it doesn't get in the way of its output.
+fn finish_outputs(
+    grid: &Grid,
+    verify: bool,
+    path_out: Option<&Path>,
+    metrics: Option<(&Path, &str, u64)>,
+) -> bool {
+    let status = grid.status();
+    let verified = verify && status == SearchStatus::Found;
+
+    if verified {
+        if let Err(e) = grid.verify() {
+            exit_with_error(&format!("path verification failed: {}", e));
+        }
     }
 
-    pub fn width(&self) -> u32 {
-        self.cells.len() as u32
+    if let (Some(path), SearchStatus::Found) = (path_out, status) {
+        grid.export_path(path)
+            .unwrap_or_else(|e| exit_with_error(&e));
     }
 
-    pub fn set_height(&mut self, h: u32) -> &mut Grid {
-        self.cells
-            .iter_mut()
-            .for_each(|v| v.resize_with(h as usize, || CellState::Unknown));
-        self
+    if let Some((path, map, seed)) = metrics {
+        append_metrics(path, grid, map, seed).unwrap_or_else(|e| exit_with_error(&e));
     }
 
-    pub fn height(&self) -> u32 {
-        self.cells.get(0).map(Vec::len).unwrap_or(0) as u32
-    }
+    verified
+}
 
-    fn get_cell(&self, cell: (u32, u32)) -> Option<CellState> {
-        self.cells
-            .get(cell.0 as usize)
-            .and_then(|col| col.get(cell.1 as usize))
-            .copied()
-    }
+/// Runs the main search, and the compared and racing ones, to completion without drawing
+/// anything, prints how they did and exits with 0 if the main search found a path and 2
+/// if it didn't
+fn run_headless(
+    mut grid: Grid,
+    mut carving: VecDeque<(u32, u32)>,
+    rival: Option<Grid>,
+    race: Option<Race>,
+    verify: bool,
+    path_out: Option<&Path>,
+    metrics: Option<(&Path, &str, u64)>,
+) -> ! {
+    grid.carve(carving.make_contiguous());
+
+    let started = Instant::now();
+    let status = grid.run_to_completion();
+    let elapsed = started.elapsed();
+
+    let search = &grid.searches()[0];
+    let outcome = match status {
+        SearchStatus::Found => {
+            let cost = grid.path_cost().unwrap_or_default();
+            format!(
+                "path of {} cells costing {}",
+                search.path().len(),
+                format_dist(cost)
+            )
+        }
+        SearchStatus::NoPath { trapped } => format!("no path, {}", trapped_label(search, trapped)),
+        SearchStatus::NegativeCycle { length } => format!("negative cycle of {} cells", length),
+        SearchStatus::Running | SearchStatus::Revealing => {
+            unreachable!("searches run to completion")
+        }
+    };
 
-    fn set_cell(&mut self, cell: (u32, u32), state: CellState) {
-        let _ = self
-            .cells
-            .get_mut(cell.0 as usize)
-            .and_then(|col| col.get_mut(cell.1 as usize))
-            .map(|cell| {
-                *cell = state;
-            });
+    println!("{}: {}", search.algorithm(), outcome);
+    println!("expanded {} cells in {:.2?}", search.expanded(), elapsed);
+
+    if let Some(fog) = grid.fog() {
+        println!("{}", walked_label(fog));
     }
 
-    pub fn draw_obstacle(&mut self, start: (u32, u32), end: (u32, u32)) {
-        let m = (start.1 as f64 - end.1 as f64) / (start.0 as f64 - end.0 as f64);
+    if finish_outputs(&grid, verify, path_out, metrics) {
+        println!("path verified");
+    }
 
-        for x in start.0..end.0 {
-            let y = (m * (x as f64 - start.0 as f64)) + start.1 as f64;
+    if let Some(mut rival) = rival {
+        sync_rival(&grid, &mut rival);
+        rival.run_to_completion();
+        println!("{}", pane_label(&rival));
+    }
 
-            let y = y.round() as u32;
+    if let Some(mut race) = race {
+        while !race.finished() {
+            race.step(&grid);
+        }
 
-            self.set_cell((x, y), CellState::Obstacle);
+        for line in race.standings() {
+            println!("{}", line);
         }
     }
 
-    fn get_neighbors(&self, cell: (u32, u32)) -> Vec<(u32, u32)> {
-        let mut neighbors = Vec::with_capacity(4);
+    std::process::exit(match status {
+        SearchStatus::Found => 0,
+        _ => 2,
+    })
+}
 
-        // up
-        if cell.1 > 0 {
-            neighbors.push((cell.0, cell.1 - 1));
-        }
-        // down
-        if cell.1 < self.height() - 1 {
-            neighbors.push((cell.0, cell.1 + 1));
-        }
-        // left
-        if cell.0 > 0 {
-            neighbors.push((cell.0 - 1, cell.1));
-        }
-        // right
-        if cell.0 < self.width() - 1 {
-            neighbors.push((cell.0 + 1, cell.1));
-        }
+/// What the map was made from, for the metrics: the file it was loaded from, or how it
+/// was generated
+fn map_name(args: &Args) -> String {
+    let file = [&args.load_map, &args.map, &args.movingai]
+        .into_iter()
+        .find_map(|path| path.as_ref());
+
+    match (file, args.maze, args.random_obstacles) {
+        (Some(path), _, _) => path.display().to_string(),
+        (None, Some(kind), _) => format!("{:?} maze", kind).to_lowercase(),
+        (None, None, Some(_)) => "random obstacles".to_string(),
+        (None, None, None) => "built-in".to_string(),
+    }
+}
 
-        neighbors
+/// Appends a row for the main search of `grid`, which is done, to the CSV file at `path`,
+/// writing the header first if the file is new
+fn append_metrics(path: &Path, grid: &Grid, map: &str, seed: u64) -> Result<(), String> {
+    use std::io::Write;
+
+    let error = |e: std::io::Error| format!("couldn't write metrics {}: {}", path.display(), e);
+
+    let new = std::fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(error)?;
+
+    let search = &grid.searches()[0];
+    // left empty without a path
+    let cost = grid
+        .path_cost()
+        .filter(|_| grid.status() == SearchStatus::Found)
+        .map_or(String::new(), |cost| {
+            (cost as f64 / UNIT_COST as f64).to_string()
+        });
+
+    let mut text = String::new();
+    if new {
+        text.push_str("algorithm,map,seed,expanded,path_cost,wall_time_ms,peak_open_set\n");
     }
+    text.push_str(&format!(
+        "{},{},{},{},{},{:.3},{}\n",
+        search.algorithm(),
+        map,
+        seed,
+        search.expanded(),
+        cost,
+        search.search_time().as_secs_f64() * 1000.0,
+        search.peak_open_set()
+    ));
+
+    file.write_all(text.as_bytes()).map_err(error)
+}
 
-    fn get_dist(&self, cell: (u32, u32), dist: u32) -> u32 {
-        if self.enable_astar {
-            let euclid_dist = (((cell.0 as i32 - self.goal.0 as i32).pow(2)
-                + (cell.1 as i32 - self.goal.1 as i32).pow(2))
-                as f64)
-                .sqrt() as u32;
+/// Starts the compared search over on a copy of the main grid once its map or agents
+/// changed
+fn sync_rival(grid: &Grid, rival: &mut Grid) {
+    if !rival.same_setup(grid) {
+        *rival = grid.with_algorithm(rival.searches()[0].algorithm());
+    }
+}
 
-            dist + euclid_dist
-        } else {
-            dist
+/// The algorithm of a pane of the comparison, how many cells it expanded and what its
+/// path costs once it found one
+fn pane_label(grid: &Grid) -> String {
+    let search = &grid.searches()[0];
+    let label = format!("{}: {} expanded", search.algorithm(), search.expanded());
+
+    match grid.path_cost() {
+        Some(cost) if grid.status() == SearchStatus::Found => {
+            format!("{}, path cost {}", label, format_dist(cost))
         }
+        _ => label,
     }
+}
 
-    #[tracing::instrument(skip(self))]
-    fn dijkstra_iteration(&mut self) {
-        if self.current == self.goal {
-            return;
-        }
+/// Where the minimap goes while zoomed in, in the top right corner of the main pane
+fn minimap_rect(grid: &Grid, pane_width: u32) -> Rect {
+    let (columns, rows) = (grid.width(), grid.map().layer_height());
+    let (width, height) = if columns >= rows {
+        (MINIMAP_SIZE, (MINIMAP_SIZE * rows / columns).max(1))
+    } else {
+        ((MINIMAP_SIZE * columns / rows).max(1), MINIMAP_SIZE)
+    };
+
+    Rect::new(
+        pane_width.saturating_sub(width + 10) as i32,
+        10,
+        width,
+        height,
+    )
+}
 
-        for n in self.get_neighbors(self.current) {
-            let state = self.get_cell(n).unwrap();
+/// How far the agent in the fog walked, and how often it had to plan again on the way
+fn walked_label(fog: &Fog) -> String {
+    format!(
+        "walked {} cells, planned again {} times",
+        fog.trail().len() - 1,
+        fog.replans()
+    )
+}
 
-            match state {
-                CellState::Unknown => {
-                    let dist = self.current_dist + 1;
+/// Where `search` found no way through: the goal's region, or the start's for searches
+/// going back from the goal
+fn trapped_label(search: &Search, trapped: usize) -> String {
+    let end = if search.searches_from_goal() {
+        "start"
+    } else {
+        "goal"
+    };
 
-                    self.set_cell(n, CellState::Unvisited);
+    format!("{} trapped in {} cells", end, trapped)
+}
 
-                    self.unvisited.push(UnvisitedState {
-                        dist: self.get_dist(n, dist),
-                        actual_dist: dist,
-                        cell: n,
-                    })
-                }
-                CellState::Unvisited => continue,
-                CellState::Visited { dist } => {
-                    assert!(dist <= self.current_dist + 1);
-                }
-                CellState::Obstacle => continue,
-                CellState::OnPath => unreachable!(
-                    "we shouldn't get here, because cells are only set to onpath on completion"
-                ),
-            }
-        }
+/// How far `search` got: cells expanded, entries in the open set, the g-value of the cell
+/// it expanded last and the length of its path once it found one, with the time spent
+/// searching if `timed`
+fn stats_label(search: &Search, timed: bool) -> String {
+    let mut label = format!(
+        "Expanded: {} Open: {} g: {}",
+        search.expanded(),
+        search.open_set_size(),
+        format_dist(search.current_dist())
+    );
+
+    if search.status() == SearchStatus::Found {
+        // Theta* keeps just the corners of its path
+        let unit = match search.algorithm() {
+            Algorithm::Theta => "corners",
+            _ => "cells",
+        };
+        label = format!("{} Path: {} {}", label, search.path().len(), unit);
+    }
 
-        self.set_cell(
-            self.current,
-            CellState::Visited {
-                dist: self.current_dist,
-            },
-        );
+    if timed {
+        label = format!("{} Time: {:.2?}", label, search.search_time());
+    }
 
-        if let Some(cell) = self.unvisited.pop() {
-            self.current = cell.cell;
-            self.current_dist = cell.actual_dist;
-        } else {
-            println!("no possible path");
-            return;
-        }
+    label
+}
 
-        if self.current == self.goal {
-            println!("we are done");
-            self.color_path();
+/// The algorithm `search` runs, with the heuristic it was given and its weight if it
+/// has one and whether it builds a flow field, how many cells an incremental search
+/// expanded again since the last map change, and how far IDA*, Bellman-Ford or
+/// Floyd-Warshall got
+fn running_label(search: &Search) -> String {
+    let weight = search.heuristic_weight();
+
+    let mut details = Vec::new();
+    if search.algorithm().uses_heuristic() {
+        if let Some(kind) = search.heuristic_kind() {
+            details.push(format!("{} heuristic", kind));
         }
+        details.push(format!("weight {}", weight));
     }
 
-    fn color_path(&mut self) {
-        if self.current != self.goal {
-            return;
-        }
-
-        let mut cursor = self.goal;
+    let label = if details.is_empty() {
+        format!("RUNNING {}", search.algorithm())
+    } else {
+        format!("RUNNING {} ({})", search.algorithm(), details.join(", "))
+    };
+    let label = if search.flow_field() {
+        format!("{} FLOW FIELD", label)
+    } else {
+        label
+    };
+
+    if let Some((k, nodes, scale)) = search.all_pairs_progress() {
+        let label = format!("{}, {} of {} nodes let through", label, k, nodes);
+
+        return match scale {
+            1 => label,
+            _ => format!("{}, nodes are blocks of {}x{} cells", label, scale, scale),
+        };
+    }
 
-        while cursor != self.start {
-            self.set_cell(cursor, CellState::OnPath);
+    if let Some((sweeps, lowered)) = search.sweeps() {
+        return format!(
+            "{}, sweep {}, {} distances lowered in the last one",
+            label, sweeps, lowered
+        );
+    }
 
-            cursor = self
-                .get_neighbors(cursor)
-                .into_iter()
-                .filter_map(|cell| match self.get_cell(cell).unwrap() {
-                    CellState::Visited { dist } => Some((cell, dist)),
-                    _ => None,
-                })
-                .min_by_key(|(_, dist)| *dist)
-                .unwrap()
-                .0
-        }
+    if let Some((pass, bound)) = search.deepening_bound() {
+        return format!(
+            "{}, pass {} up to {}, {} cells remembered",
+            label,
+            pass + 1,
+            format_dist(bound),
+            search.open_set_size()
+        );
     }
 
-    pub fn draw_to_canvas<T: RenderTarget>(&self, canvas: &mut Canvas<T>, w: u32, h: u32) {
-        let x_spacing = 1;
-        let y_spacing = 1;
+    match search.repairs() {
+        Some(repairs) => format!(
+            "{}, {} cells re-expanded after the last change",
+            label, repairs
+        ),
+        None => label,
+    }
+}
 
-        let avail_width = w - ((self.width() - 1) * x_spacing);
-        let avail_height = h - ((self.height() - 1) * y_spacing);
+/// Ends every recording still going
+fn finish_recordings(recorders: &mut Vec<Box<dyn Recorder>>) {
+    for recorder in recorders.drain(..) {
+        match recorder.finish() {
+            Ok(()) => tracing::info!("recording finished"),
+            Err(e) => tracing::error!("recording failed: {}", e),
+        }
+    }
+}
 
-        let wide = avail_width / self.width();
-        let high = avail_height / self.width();
+/// Config and keybinding problems are the user's to fix, so no backtrace
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1)
+}
 
-        for (x, col) in self.cells.iter().enumerate() {
-            for (y, cell) in col.iter().enumerate() {
-                let x = x as u32;
-                let y = y as u32;
+/// Draws the delay and fps controls, the pause button and the new map button into the
+/// top row of `bar`, and the heuristic weight and algorithm of the search into the
+/// bottom one, applying the ones clicked since the last frame. Returns whether a new map
+/// was asked for.
+fn draw_settings_bar<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    ui: &mut Ui<C>,
+    bar: Rect,
+    playback: &mut Playback,
+    grid: &mut Grid,
+) -> bool {
+    ui.panel(canvas, bar);
+
+    let y = bar.y() + 3;
+    let button = |x| Rect::new(x, y, 24, 24);
+
+    ui.label(canvas, "DELAY", 5, y);
+    if ui.button(canvas, button(75), "-") {
+        playback.decrease_delay();
+    }
+    ui.label(canvas, &format!("{}ms", playback.delay.as_millis()), 105, y);
+    if ui.button(canvas, button(185), "+") {
+        playback.increase_delay();
+    }
 
-                let rect = Rect::new(
-                    (x * (wide + x_spacing)) as i32,
-                    (y * (high + y_spacing)) as i32,
-                    wide,
-                    high,
-                );
+    ui.label(canvas, "FPS", 230, y);
+    if ui.button(canvas, button(275), "-") {
+        playback.decrease_fps();
+    }
+    ui.label(canvas, &format!("{}", playback.fps), 305, y);
+    if ui.button(canvas, button(360), "+") {
+        playback.increase_fps();
+    }
 
-                let color = {
-                    if (x, y) == self.start {
-                        Color::BLUE
-                    } else if (x, y) == self.goal {
-                        Color::GREEN
-                    } else if (x, y) == self.current {
-                        Color::CYAN
-                    } else {
-                        match cell {
-                            CellState::Unknown => Color::GREY,
-                            CellState::Unvisited { .. } => Color::RED,
-                            CellState::Visited { .. } => Color::YELLOW,
-                            CellState::Obstacle => Color::WHITE,
-                            CellState::OnPath => Color::MAGENTA,
-                        }
-                    }
-                };
+    let label = if playback.paused { "PLAY" } else { "PAUSE" };
+    if ui.button(canvas, Rect::new(405, y, 80, 24), label) {
+        playback.toggle_pause();
+    }
+    if ui.button(canvas, Rect::new(490, y, 70, 24), "STEP") {
+        playback.step();
+    }
+    let new_map = ui.button(canvas, Rect::new(565, y, 100, 24), "NEW MAP");
+
+    let y = y + 30;
+    let button = |x| Rect::new(x, y, 24, 24);
+    ui.label(canvas, "WEIGHT", 5, y);
+    let weight = grid.searches()[0].heuristic_weight();
+    if ui.button(canvas, button(85), "-") {
+        grid.set_heuristic_weight((weight - WEIGHT_STEP).max(0.0));
+    }
+    ui.label(canvas, &format!("{:.2}", weight), 115, y);
+    if ui.button(canvas, button(185), "+") {
+        grid.set_heuristic_weight(weight + WEIGHT_STEP);
+    }
 
-                canvas.set_draw_color(color);
+    // every algorithm in turn, wrapping around at either end
+    let algorithms = Algorithm::value_variants();
+    let index = algorithms
+        .iter()
+        .position(|&algorithm| algorithm == grid.searches()[0].algorithm())
+        .unwrap_or(0);
 
-                canvas.fill_rect(rect).unwrap();
-            }
-        }
+    ui.label(canvas, "ALGO", 230, y);
+    if ui.button(canvas, button(285), "<") {
+        grid.set_algorithm(algorithms[(index + algorithms.len() - 1) % algorithms.len()]);
+    }
+    if ui.button(canvas, button(315), ">") {
+        grid.set_algorithm(algorithms[(index + 1) % algorithms.len()]);
     }
+    ui.label(canvas, &grid.searches()[0].algorithm().to_string(), 350, y);
+
+    new_map
 }
@@ -0,0 +1,841 @@
+use crate::grid2d::Grid2D;
+
+/// Most expensive terrain cost that can be painted
+pub const MAX_COST: u32 = 5;
+
+/// Cost of negative terrain, which gains a step instead of costing one. Only Bellman-Ford
+/// takes it into account, every other search sees normal terrain.
+pub const NEGATIVE_COST: i32 = -1;
+
+/// Distances are fixed point, this is the cost of one orthogonal step over normal terrain
+pub const UNIT_COST: u32 = 100;
+
+/// Cost of one diagonal step over normal terrain, `UNIT_COST` times the square root of 2
+pub const DIAGONAL_COST: u32 = 141;
+
+/// What painting a cell turns it into
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Material {
+    /// Free cell with the given traversal cost
+    Cost(u32),
+    Obstacle,
+    /// Free cell with the normal traversal cost
+    Eraser,
+    /// Free cell with [`NEGATIVE_COST`]
+    Negative,
+}
+
+impl Material {
+    /// The eraser is just normal terrain, and costs are kept in the paintable range
+    pub fn normalized(self) -> Self {
+        match self {
+            Material::Cost(cost) => Material::Cost(cost.clamp(1, MAX_COST)),
+            Material::Obstacle => Material::Obstacle,
+            Material::Eraser => Material::Cost(1),
+            Material::Negative => Material::Negative,
+        }
+    }
+}
+
+impl std::fmt::Display for Material {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Material::Cost(cost) => write!(f, "Cost {}", cost),
+            Material::Obstacle => write!(f, "Obstacle"),
+            Material::Eraser => write!(f, "Eraser"),
+            Material::Negative => write!(f, "Negative"),
+        }
+    }
+}
+
+/// Shape of the cells, which decides which ones are neighbors
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    serde::Serialize,
+    serde::Deserialize,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Topology {
+    /// Squares with 4 neighbors, or 8 stepping diagonally
+    #[default]
+    Square,
+    /// Triangles pointing up and down in turn along every row, with 3 neighbors each: the
+    /// ones left and right, and the one across their flat side. Nothing is diagonal.
+    Triangle,
+}
+
+/// Two cells that are neighbors however far apart they are, stepping from either one to
+/// the other costs `cost`, never nothing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Portal {
+    pub a: (u32, u32),
+    pub b: (u32, u32),
+    /// Fixed point like every distance, [`UNIT_COST`] costs as much as a normal step
+    pub cost: u32,
+}
+
+impl Portal {
+    /// The other end of the portal if `cell` is one of its ends
+    pub fn exit(&self, cell: (u32, u32)) -> Option<(u32, u32)> {
+        if cell == self.a {
+            Some(self.b)
+        } else if cell == self.b {
+            Some(self.a)
+        } else {
+            None
+        }
+    }
+}
+
+/// The terrain every search runs on: which cells are obstacles, what entering the others
+/// costs and which are connected by portals.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Map {
+    #[serde(with = "columns")]
+    obstacles: Grid2D<bool>,
+    /// Cost of entering a cell, at least 1 or [`NEGATIVE_COST`]
+    #[serde(with = "columns")]
+    costs: Grid2D<i32>,
+    /// Whether diagonal moves may squeeze between two orthogonally adjacent obstacles
+    corner_cutting: bool,
+    /// Missing from maps saved before there were portals
+    #[serde(default)]
+    portals: Vec<Portal>,
+    /// Missing from maps saved before there were other shapes than squares
+    #[serde(default)]
+    topology: Topology,
+    /// Number of layers the rows are split into, stacked from the top. Missing from maps
+    /// saved before there could be more than one.
+    #[serde(default = "single_layer")]
+    layers: u32,
+}
+
+fn single_layer() -> u32 {
+    1
+}
+
+/// Saves the cells of a map the way they were before they were stored flat, a list of
+/// columns, so saved grids stay readable
+mod columns {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::grid2d::Grid2D;
+
+    pub fn serialize<T, S>(grid: &Grid2D<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Clone + Serialize,
+        S: Serializer,
+    {
+        grid.columns().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Grid2D<T>, D::Error>
+    where
+        T: Clone + Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Grid2D::from_columns(Vec::deserialize(deserializer)?)
+            .ok_or_else(|| D::Error::custom("map isn't rectangular"))
+    }
+}
+
+impl Map {
+    pub fn new(w: u32, h: u32) -> Self {
+        Self {
+            obstacles: Grid2D::new(w, h, false),
+            costs: Grid2D::new(w, h, 1),
+            corner_cutting: false,
+            portals: Vec::new(),
+            topology: Topology::Square,
+            layers: 1,
+        }
+    }
+
+    /// Checks a map that didn't come from [`Self::new`], like a deserialized one, for
+    /// being rectangular and having costs that can be painted
+    pub fn validate(&self) -> Result<(), String> {
+        let (w, h) = (self.width(), self.height());
+
+        if w == 0 || h == 0 {
+            return Err("map is empty".to_string());
+        }
+        if !self.costs.fits(w, h) {
+            return Err("map isn't rectangular".to_string());
+        }
+
+        if let Some(cost) = self
+            .costs
+            .values()
+            .find(|&&cost| cost != NEGATIVE_COST && !(1..=MAX_COST as i32).contains(&cost))
+        {
+            return Err(format!(
+                "cost {} isn't {} or between 1 and {}",
+                cost, NEGATIVE_COST, MAX_COST
+            ));
+        }
+
+        if self.layers == 0 || !h.is_multiple_of(self.layers) {
+            return Err(format!(
+                "{} rows can't be split into {} layers",
+                h, self.layers
+            ));
+        }
+
+        if let Some(portal) = self
+            .portals
+            .iter()
+            .find(|portal| !self.in_bounds(portal.a) || !self.in_bounds(portal.b))
+        {
+            return Err(format!(
+                "portal from {:?} to {:?} is outside of the map",
+                portal.a, portal.b
+            ));
+        }
+
+        if let Some(portal) = self.portals.iter().find(|portal| portal.cost == 0) {
+            return Err(format!(
+                "portal from {:?} to {:?} costs nothing",
+                portal.a, portal.b
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn corner_cutting(&self) -> bool {
+        self.corner_cutting
+    }
+
+    pub fn set_corner_cutting(&mut self, allow: bool) {
+        self.corner_cutting = allow;
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    /// Rows every layer has
+    pub fn layer_height(&self) -> u32 {
+        self.height() / self.layers
+    }
+
+    /// Layer `cell` is on, 0 for the top rows
+    pub fn layer_of(&self, cell: (u32, u32)) -> u32 {
+        cell.1 / self.layer_height().max(1)
+    }
+
+    /// Splits the rows into `layers` equally high layers, unless there are none or the
+    /// rows can't be split evenly. Returns whether they were.
+    pub fn set_layers(&mut self, layers: u32) -> bool {
+        if layers == 0 || !self.height().is_multiple_of(layers) {
+            return false;
+        }
+
+        self.layers = layers;
+        true
+    }
+
+    /// Stairs from `cell` to the same cell of the layer below it: a portal that costs as
+    /// much as a normal step. `None` on the last layer.
+    pub fn stairs(&self, cell: (u32, u32)) -> Option<Portal> {
+        let below = (cell.0, cell.1 + self.layer_height());
+
+        (self.in_bounds(cell) && self.in_bounds(below)).then_some(Portal {
+            a: cell,
+            b: below,
+            cost: UNIT_COST,
+        })
+    }
+
+    /// Whether `portal` leads straight down or up a layer, like [`Self::stairs`] do
+    pub fn is_stairs(&self, portal: &Portal) -> bool {
+        portal.a.0 == portal.b.0 && portal.a.1.abs_diff(portal.b.1) == self.layer_height()
+    }
+
+    pub fn portals(&self) -> &[Portal] {
+        &self.portals
+    }
+
+    /// Connects two cells with a portal, unless one of them is outside of the map,
+    /// they're the same cell or it costs nothing. Returns whether it was added.
+    pub fn add_portal(&mut self, portal: Portal) -> bool {
+        if !self.in_bounds(portal.a)
+            || !self.in_bounds(portal.b)
+            || portal.a == portal.b
+            || portal.cost == 0
+        {
+            return false;
+        }
+
+        self.portals.push(portal);
+        true
+    }
+
+    /// Cost of stepping from `from` to `to` through a portal, the cheapest one if there
+    /// are several, `None` if no portal connects them
+    pub fn portal_cost(&self, from: (u32, u32), to: (u32, u32)) -> Option<u32> {
+        self.portals
+            .iter()
+            .filter(|portal| portal.exit(from) == Some(to))
+            .map(|portal| portal.cost)
+            .min()
+    }
+
+    /// Whether a single step from `from` to the adjacent `to`, or through a portal, is
+    /// allowed. Diagonal steps between two obstacles touching at a corner only are if
+    /// corner cutting is, and only portals lead to other layers.
+    pub fn can_step(&self, from: (u32, u32), to: (u32, u32)) -> bool {
+        if self.portal_cost(from, to).is_some() {
+            return true;
+        }
+
+        self.layer_of(from) == self.layer_of(to)
+            && (from.0 == to.0
+                || from.1 == to.1
+                || self.corner_cutting
+                || !self.is_obstacle((from.0, to.1)) && !self.is_obstacle((to.0, from.1)))
+    }
+
+    pub fn set_width(&mut self, w: u32) {
+        let height = self.height();
+
+        self.obstacles.resize(w, height, false);
+        self.costs.resize(w, height, 1);
+        self.portals
+            .retain(|portal| portal.a.0 < w && portal.b.0 < w);
+    }
+
+    pub fn width(&self) -> u32 {
+        self.obstacles.width()
+    }
+
+    pub fn set_height(&mut self, h: u32) {
+        let width = self.width();
+
+        self.obstacles.resize(width, h, false);
+        self.costs.resize(width, h, 1);
+        self.portals
+            .retain(|portal| portal.a.1 < h && portal.b.1 < h);
+    }
+
+    pub fn height(&self) -> u32 {
+        self.obstacles.height()
+    }
+
+    pub fn in_bounds(&self, cell: (u32, u32)) -> bool {
+        cell.0 < self.width() && cell.1 < self.height()
+    }
+
+    /// Cells outside of the map count as obstacles
+    pub fn is_obstacle(&self, cell: (u32, u32)) -> bool {
+        self.obstacles.get(cell).copied().unwrap_or(true)
+    }
+
+    /// Cost of entering a cell, negative terrain counting as normal terrain
+    pub fn cost(&self, cell: (u32, u32)) -> u32 {
+        self.signed_cost(cell).max(1) as u32
+    }
+
+    /// Cost of entering a cell, [`NEGATIVE_COST`] for negative terrain
+    pub fn signed_cost(&self, cell: (u32, u32)) -> i32 {
+        self.costs[cell]
+    }
+
+    /// Material a cell currently consists of, `None` if out of bounds
+    pub fn material_at(&self, cell: (u32, u32)) -> Option<Material> {
+        if !self.in_bounds(cell) {
+            None
+        } else if self.is_obstacle(cell) {
+            Some(Material::Obstacle)
+        } else if self.signed_cost(cell) < 0 {
+            Some(Material::Negative)
+        } else {
+            Some(Material::Cost(self.cost(cell)))
+        }
+    }
+
+    /// Changes the material of a single cell, returning whether anything changed
+    pub fn set_material(&mut self, cell: (u32, u32), material: Material) -> bool {
+        let material = material.normalized();
+        if !self.in_bounds(cell) || self.material_at(cell) == Some(material) {
+            return false;
+        }
+
+        let (obstacle, cost) = match material {
+            Material::Obstacle => (true, 1),
+            Material::Cost(cost) => (false, cost as i32),
+            Material::Negative => (false, NEGATIVE_COST),
+            Material::Eraser => unreachable!("normalized materials are never the eraser"),
+        };
+
+        self.obstacles[cell] = obstacle;
+        self.costs[cell] = cost;
+
+        true
+    }
+
+    /// The 4-connected neighbors of `cell` on its layer, obstacles included, and the other
+    /// ends of the portals at it. Triangles only have 3, see [`Topology`].
+    pub fn get_neighbors(&self, cell: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut neighbors = self.layer_neighbors(cell);
+
+        for exit in self.portals.iter().filter_map(|portal| portal.exit(cell)) {
+            if !neighbors.contains(&exit) {
+                neighbors.push(exit);
+            }
+        }
+
+        neighbors
+    }
+
+    /// Like [`Self::get_neighbors`], without the portals
+    pub fn layer_neighbors(&self, cell: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut neighbors = Vec::with_capacity(4);
+        let triangle = self.topology == Topology::Triangle;
+        let row = cell.1 % self.layer_height().max(1);
+
+        // up
+        if row > 0 && (!triangle || !points_up(cell)) {
+            neighbors.push((cell.0, cell.1 - 1));
+        }
+        // down
+        if row < self.layer_height() - 1 && (!triangle || points_up(cell)) {
+            neighbors.push((cell.0, cell.1 + 1));
+        }
+        // left
+        if cell.0 > 0 {
+            neighbors.push((cell.0 - 1, cell.1));
+        }
+        // right
+        if cell.0 < self.width() - 1 {
+            neighbors.push((cell.0 + 1, cell.1));
+        }
+
+        neighbors
+    }
+
+    /// All 8 surrounding cells of `cell` on its layer, obstacles included.
+    /// Without corner cutting, diagonal cells are left out if either orthogonal cell
+    /// next to both of them is an obstacle. Triangles have no diagonal neighbors.
+    pub fn get_neighbors8(&self, cell: (u32, u32)) -> Vec<(u32, u32)> {
+        let mut neighbors = self.get_neighbors(cell);
+        if self.topology == Topology::Triangle {
+            return neighbors;
+        }
+
+        for (dx, dy) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let n = (
+                cell.0.wrapping_add_signed(dx),
+                cell.1.wrapping_add_signed(dy),
+            );
+
+            if self.in_bounds(n) && self.can_step(cell, n) && !neighbors.contains(&n) {
+                neighbors.push(n);
+            }
+        }
+
+        neighbors
+    }
+
+    /// Whether the straight line between two cells only crosses free cells. Without
+    /// corner cutting, diagonal steps along the line need both cells they squeeze
+    /// between to be free as well. Triangles only see their neighbors, and no cell sees
+    /// another layer.
+    pub fn line_of_sight(&self, from: (u32, u32), to: (u32, u32)) -> bool {
+        if self.layer_of(from) != self.layer_of(to) {
+            return false;
+        }
+
+        if self.topology == Topology::Triangle {
+            return !self.is_obstacle(from)
+                && !self.is_obstacle(to)
+                && (from == to || self.get_neighbors(from).contains(&to));
+        }
+
+        let cells = line(from, to);
+
+        cells.iter().all(|&cell| !self.is_obstacle(cell))
+            && cells.windows(2).all(|step| self.can_step(step[0], step[1]))
+    }
+
+    /// Cost of a single orthogonal or diagonal step from `from` into the adjacent `to`,
+    /// negative terrain counting as normal terrain, or through a portal if that's cheaper
+    pub fn step_cost(&self, from: (u32, u32), to: (u32, u32)) -> u32 {
+        let step = self.cost(to) * step_unit(from, to);
+
+        match self.portal_cost(from, to) {
+            Some(portal) if adjacent(from, to) => portal.min(step),
+            Some(portal) => portal,
+            None => step,
+        }
+    }
+
+    /// Like [`Self::step_cost`], but stepping onto negative terrain gains a step
+    pub fn signed_step_cost(&self, from: (u32, u32), to: (u32, u32)) -> i64 {
+        let step = self.signed_cost(to) as i64 * step_unit(from, to) as i64;
+
+        match self.portal_cost(from, to) {
+            Some(portal) if adjacent(from, to) => (portal as i64).min(step),
+            Some(portal) => portal as i64,
+            None => step,
+        }
+    }
+
+    /// Cost of moving in a straight line from `from` to `to`: its length, weighted by
+    /// the most expensive terrain entered along the way. The length is rounded down like
+    /// [`DIAGONAL_COST`] before it's weighted, so single steps cost what
+    /// [`Self::step_cost`] says.
+    pub fn segment_cost(&self, from: (u32, u32), to: (u32, u32)) -> u32 {
+        let terrain = line(from, to)
+            .into_iter()
+            .skip(1)
+            .map(|cell| self.cost(cell))
+            .max()
+            .unwrap_or(1);
+
+        let length =
+            ((from.0 as f64 - to.0 as f64).powi(2) + (from.1 as f64 - to.1 as f64).powi(2)).sqrt();
+
+        (length * UNIT_COST as f64) as u32 * terrain
+    }
+
+    /// Collects `from` and every cell 4-connected to it, or connected through portals,
+    /// through cells `include` accepts.
+    pub fn flood(&self, from: (u32, u32), include: impl Fn((u32, u32)) -> bool) -> Vec<(u32, u32)> {
+        let mut seen = Grid2D::new(self.width(), self.height(), false);
+
+        self.flood_unseen(from, &mut seen, |cell| self.get_neighbors(cell), include)
+    }
+
+    /// Like [`Self::flood`], but only 4-connected, never through portals or to another
+    /// layer
+    pub fn flood_layer(
+        &self,
+        from: (u32, u32),
+        include: impl Fn((u32, u32)) -> bool,
+    ) -> Vec<(u32, u32)> {
+        let mut seen = Grid2D::new(self.width(), self.height(), false);
+
+        self.flood_unseen(from, &mut seen, |cell| self.layer_neighbors(cell), include)
+    }
+
+    /// Like [`Self::flood`], but skips and marks cells in `seen`, so several floods can
+    /// share the work, and steps to the cells `neighbors` gives
+    fn flood_unseen(
+        &self,
+        from: (u32, u32),
+        seen: &mut Grid2D<bool>,
+        neighbors: impl Fn((u32, u32)) -> Vec<(u32, u32)>,
+        include: impl Fn((u32, u32)) -> bool,
+    ) -> Vec<(u32, u32)> {
+        seen[from] = true;
+
+        // explicit stack, since regions can be far too big for recursion
+        let mut stack = vec![from];
+        let mut region = Vec::new();
+
+        while let Some(cell) = stack.pop() {
+            for n in neighbors(cell) {
+                if !seen[n] && include(n) {
+                    seen[n] = true;
+                    stack.push(n);
+                }
+            }
+
+            region.push(cell);
+        }
+
+        region
+    }
+
+    pub fn obstacle_count(&self) -> usize {
+        self.obstacles
+            .values()
+            .filter(|&&obstacle| obstacle)
+            .count()
+    }
+
+    /// Splits the free cells into components 4-connected or connected through portals.
+    /// Returns how many there are and the component of every cell, `None` for
+    /// obstacles.
+    pub fn components(&self) -> (usize, Grid2D<Option<usize>>) {
+        let mut seen = Grid2D::new(self.width(), self.height(), false);
+        let mut labels = Grid2D::new(self.width(), self.height(), None);
+        let mut count = 0;
+
+        for x in 0..self.width() {
+            for y in 0..self.height() {
+                if seen[(x, y)] || self.is_obstacle((x, y)) {
+                    continue;
+                }
+
+                let neighbors = |cell| self.get_neighbors(cell);
+                for cell in
+                    self.flood_unseen((x, y), &mut seen, neighbors, |n| !self.is_obstacle(n))
+                {
+                    labels[cell] = Some(count);
+                }
+
+                count += 1;
+            }
+        }
+
+        (count, labels)
+    }
+}
+
+/// Whether the triangle at `cell` points up, with its flat side at the bottom. Triangles
+/// take turns along rows and columns, the top left one points up.
+pub fn points_up(cell: (u32, u32)) -> bool {
+    (cell.0 + cell.1).is_multiple_of(2)
+}
+
+/// Number of steps between two triangles on an empty map. Every step crosses one of the
+/// lines of the three directions the triangles' sides run in, and there is always a way
+/// that only crosses the lines between the two.
+pub fn triangle_distance(a: (u32, u32), b: (u32, u32)) -> u32 {
+    // strips between the lines of each direction the triangles are in
+    let strips = |(x, y): (u32, u32)| {
+        let (x, y) = (x as i64, y as i64);
+        (y, (x + y).div_euclid(2), (x - y + 1).div_euclid(2))
+    };
+
+    let (a, b) = (strips(a), strips(b));
+
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1) + a.2.abs_diff(b.2)) as u32
+}
+
+/// Whether two cells are next to each other, diagonally or not
+fn adjacent(a: (u32, u32), b: (u32, u32)) -> bool {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1)) <= 1
+}
+
+/// Cost of an orthogonal or diagonal step over normal terrain
+fn step_unit(from: (u32, u32), to: (u32, u32)) -> u32 {
+    if from.0 == to.0 || from.1 == to.1 {
+        UNIT_COST
+    } else {
+        DIAGONAL_COST
+    }
+}
+
+/// Every cell on the straight line from `from` to `to`, both included, in order
+pub fn line(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+    let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+    let (x1, y1) = (to.0 as i64, to.1 as i64);
+
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+
+    let mut err = dx + dy;
+    let mut cells = Vec::with_capacity(dx.max(-dy) as usize + 1);
+
+    loop {
+        cells.push((x as u32, y as u32));
+
+        if x == x1 && y == y1 {
+            return cells;
+        }
+
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        goal::GoalSpec,
+        search::{Algorithm, SearchStatus},
+        Grid,
+    };
+
+    /// The classic gap: a wall from the bottom left to the top right of a 4x4 map whose
+    /// cells only touch at their corners, so the top left and bottom right halves are
+    /// only connected by diagonal steps between two obstacles
+    const GAP_WALL: [(u32, u32); 4] = [(0, 3), (1, 2), (2, 1), (3, 0)];
+
+    fn gap_map(corner_cutting: bool) -> Map {
+        let mut map = Map::new(4, 4);
+        for cell in GAP_WALL {
+            map.set_material(cell, Material::Obstacle);
+        }
+        map.set_corner_cutting(corner_cutting);
+        map
+    }
+
+    #[test]
+    fn cells_are_saved_as_columns() {
+        let mut map = Map::new(3, 2);
+        map.set_material((2, 0), Material::Obstacle);
+        map.set_material((0, 1), Material::Cost(4));
+
+        let json = serde_json::to_value(&map).unwrap();
+        assert_eq!(
+            json["obstacles"],
+            serde_json::json!([[false, false], [false, false], [true, false]])
+        );
+        assert_eq!(json["costs"], serde_json::json!([[1, 4], [1, 1], [1, 1]]));
+
+        let loaded: Map = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, map);
+        assert!(loaded.validate().is_ok());
+    }
+
+    #[test]
+    fn ragged_saved_maps_are_refused() {
+        let json = serde_json::json!({
+            "obstacles": [[false, false], [false]],
+            "costs": [[1, 1], [1, 1]],
+            "corner_cutting": false,
+        });
+
+        assert!(serde_json::from_value::<Map>(json).is_err());
+    }
+
+    #[test]
+    fn corner_cutting_is_off_by_default() {
+        let mut map = Map::new(4, 4);
+        for cell in GAP_WALL {
+            map.set_material(cell, Material::Obstacle);
+        }
+
+        assert!(!map.can_step((1, 1), (2, 2)));
+    }
+
+    #[test]
+    fn diagonal_steps_between_two_obstacles() {
+        let cutting = gap_map(true);
+        assert!(cutting.can_step((1, 1), (2, 2)));
+        assert!(cutting.get_neighbors8((1, 1)).contains(&(2, 2)));
+        assert!(cutting.line_of_sight((0, 0), (3, 3)));
+
+        let blocked = gap_map(false);
+        assert!(!blocked.can_step((1, 1), (2, 2)));
+        assert!(!blocked.can_step((2, 2), (1, 1)));
+        assert!(!blocked.get_neighbors8((1, 1)).contains(&(2, 2)));
+        assert!(!blocked.line_of_sight((0, 0), (3, 3)));
+    }
+
+    #[test]
+    fn diagonal_steps_past_a_single_obstacle_need_corner_cutting() {
+        let mut map = Map::new(3, 3);
+        map.set_material((1, 0), Material::Obstacle);
+
+        assert!(!map.can_step((0, 0), (1, 1)));
+        assert!(map.can_step((0, 1), (1, 2)));
+
+        map.set_corner_cutting(true);
+        assert!(map.can_step((0, 0), (1, 1)));
+    }
+
+    #[test]
+    fn orthogonal_steps_ignore_corner_cutting() {
+        let map = gap_map(false);
+
+        assert!(map.can_step((1, 1), (1, 0)));
+        assert!(map.get_neighbors8((1, 1)).contains(&(0, 1)));
+    }
+
+    /// Searches from the top left to the bottom right of the gap map with diagonals
+    fn search_gap(algorithm: Algorithm, corner_cutting: bool) -> Grid {
+        let mut grid = Grid::new(4, 4, (0, 0), GoalSpec::Cell((3, 3)), algorithm);
+        grid.paint_cells(&GAP_WALL, Material::Obstacle);
+        grid.set_diagonals(true).set_corner_cutting(corner_cutting);
+        grid.run_to_completion();
+        grid
+    }
+
+    #[test]
+    fn paths_only_squeeze_through_the_gap_with_corner_cutting() {
+        for algorithm in [Algorithm::Dijkstra, Algorithm::AStar, Algorithm::Theta] {
+            let cutting = search_gap(algorithm, true);
+            assert_eq!(cutting.status(), SearchStatus::Found, "{}", algorithm);
+
+            let path = cutting.searches()[0].path();
+            assert_eq!(path.first(), Some(&(0, 0)), "{}", algorithm);
+            assert_eq!(path.last(), Some(&(3, 3)), "{}", algorithm);
+            assert!(
+                path.windows(2)
+                    .all(|step| cutting.map().can_step(step[0], step[1])),
+                "{} path {:?} isn't made of single steps",
+                algorithm,
+                path
+            );
+        }
+
+        // JPS prunes as if corners can't be cut, so it never squeezes through
+        for algorithm in [
+            Algorithm::Dijkstra,
+            Algorithm::AStar,
+            Algorithm::Theta,
+            Algorithm::Jps,
+        ] {
+            let blocked = search_gap(algorithm, false);
+            assert!(
+                matches!(blocked.status(), SearchStatus::NoPath { .. }),
+                "{} found {:?}",
+                algorithm,
+                blocked.searches()[0].path()
+            );
+        }
+    }
+
+    #[test]
+    fn single_segments_cost_a_step() {
+        let mut map = Map::new(3, 3);
+        map.set_material((2, 2), Material::Cost(3));
+
+        for to in [(1, 0), (0, 1), (1, 1), (2, 1), (1, 2), (2, 2)] {
+            let from = (1, 1);
+            if from != to {
+                assert_eq!(
+                    map.segment_cost(from, to),
+                    map.step_cost(from, to),
+                    "{:?}",
+                    to
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn free_portals_are_refused() {
+        let free = Portal {
+            a: (0, 0),
+            b: (3, 3),
+            cost: 0,
+        };
+
+        let mut map = Map::new(4, 4);
+        assert!(!map.add_portal(free));
+        assert!(map.add_portal(Portal { cost: 1, ..free }));
+
+        map.portals.push(free);
+        assert!(map.validate().is_err());
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{grid2d::Grid2D, rng::Rng};
+
+/// How mazes are generated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum MazeKind {
+    /// Recursive backtracker, long winding corridors with few dead ends
+    Backtracker,
+    /// Randomized Prim's, lots of short dead ends branching off everywhere
+    Prims,
+    /// Randomized Kruskal's, separate bits of corridor growing together all over the map
+    Kruskal,
+}
+
+/// Rooms of a `w` x `h` maze sit on odd coordinates, with walls or passages between them,
+/// and the outermost cells are always walls
+fn rooms(w: u32, h: u32) -> (u32, u32) {
+    (w.saturating_sub(1) / 2, h.saturating_sub(1) / 2)
+}
+
+fn room_cell(room: (u32, u32)) -> (u32, u32) {
+    (room.0 * 2 + 1, room.1 * 2 + 1)
+}
+
+/// Cell between two neighboring rooms
+fn passage(a: (u32, u32), b: (u32, u32)) -> (u32, u32) {
+    let (a, b) = (room_cell(a), room_cell(b));
+
+    ((a.0 + b.0) / 2, (a.1 + b.1) / 2)
+}
+
+/// The 4-connected rooms next to `room`
+fn neighbor_rooms(room: (u32, u32), rooms: (u32, u32)) -> Vec<(u32, u32)> {
+    let mut neighbors = Vec::with_capacity(4);
+
+    if room.1 > 0 {
+        neighbors.push((room.0, room.1 - 1));
+    }
+    if room.1 + 1 < rooms.1 {
+        neighbors.push((room.0, room.1 + 1));
+    }
+    if room.0 > 0 {
+        neighbors.push((room.0 - 1, room.1));
+    }
+    if room.0 + 1 < rooms.0 {
+        neighbors.push((room.0 + 1, room.1));
+    }
+
+    neighbors
+}
+
+/// Generates a perfect maze on a `w` x `h` grid that starts out all walls. Returns the
+/// cells to carve out, in the order the generator carves them, so it can be animated.
+pub fn carve_order(kind: MazeKind, w: u32, h: u32, rng: &mut Rng) -> Vec<(u32, u32)> {
+    let rooms = rooms(w, h);
+    if rooms.0 == 0 || rooms.1 == 0 {
+        return Vec::new();
+    }
+
+    match kind {
+        MazeKind::Backtracker => backtracker(rooms, rng),
+        MazeKind::Prims => prims(rooms, rng),
+        MazeKind::Kruskal => kruskal(rooms, rng),
+    }
+}
+
+fn first_room(rooms: (u32, u32), rng: &mut Rng) -> (u32, u32) {
+    (
+        rng.below(rooms.0 as usize) as u32,
+        rng.below(rooms.1 as usize) as u32,
+    )
+}
+
+fn backtracker(rooms: (u32, u32), rng: &mut Rng) -> Vec<(u32, u32)> {
+    let mut visited = Grid2D::new(rooms.0, rooms.1, false);
+
+    let first = first_room(rooms, rng);
+    visited[first] = true;
+
+    let mut carved = vec![room_cell(first)];
+    // explicit stack, since the corridors can be far too long for recursion
+    let mut stack = vec![first];
+
+    while let Some(&room) = stack.last() {
+        let unvisited: Vec<_> = neighbor_rooms(room, rooms)
+            .into_iter()
+            .filter(|&n| !visited[n])
+            .collect();
+
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let next = unvisited[rng.below(unvisited.len())];
+        visited[next] = true;
+
+        carved.push(passage(room, next));
+        carved.push(room_cell(next));
+        stack.push(next);
+    }
+
+    carved
+}
+
+fn prims(rooms: (u32, u32), rng: &mut Rng) -> Vec<(u32, u32)> {
+    let mut visited = Grid2D::new(rooms.0, rooms.1, false);
+
+    let first = first_room(rooms, rng);
+    visited[first] = true;
+
+    let mut carved = vec![room_cell(first)];
+    // walls between a visited room and a neighbor that may not be visited yet
+    let mut frontier: Vec<_> = neighbor_rooms(first, rooms)
+        .into_iter()
+        .map(|n| (first, n))
+        .collect();
+
+    while !frontier.is_empty() {
+        let (from, to) = frontier.swap_remove(rng.below(frontier.len()));
+
+        if visited[to] {
+            continue;
+        }
+
+        visited[to] = true;
+
+        carved.push(passage(from, to));
+        carved.push(room_cell(to));
+
+        frontier.extend(
+            neighbor_rooms(to, rooms)
+                .into_iter()
+                .filter(|&n| !visited[n])
+                .map(|n| (to, n)),
+        );
+    }
+
+    carved
+}
+
+/// Disjoint sets of rooms, indexed by `x * height + y`
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            // path halving keeps the trees flat
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+
+        i
+    }
+
+    /// Merges the sets of `a` and `b`, returns false if they already were one
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+
+        a != b
+    }
+}
+
+fn kruskal(rooms: (u32, u32), rng: &mut Rng) -> Vec<(u32, u32)> {
+    let index = |room: (u32, u32)| (room.0 * rooms.1 + room.1) as usize;
+
+    // every wall between two rooms, each one only once
+    let mut walls = Vec::new();
+    for x in 0..rooms.0 {
+        for y in 0..rooms.1 {
+            if x + 1 < rooms.0 {
+                walls.push(((x, y), (x + 1, y)));
+            }
+            if y + 1 < rooms.1 {
+                walls.push(((x, y), (x, y + 1)));
+            }
+        }
+    }
+    rng.shuffle(&mut walls);
+
+    let mut sets = UnionFind::new((rooms.0 * rooms.1) as usize);
+    let mut room_carved = vec![false; (rooms.0 * rooms.1) as usize];
+    let mut carved = Vec::new();
+
+    for (a, b) in walls {
+        if !sets.union(index(a), index(b)) {
+            continue;
+        }
+
+        // rooms only show up once their first wall falls, so the merging sets stand out
+        for room in [a, b] {
+            if !std::mem::replace(&mut room_carved[index(room)], true) {
+                carved.push(room_cell(room));
+            }
+        }
+
+        carved.push(passage(a, b));
+    }
+
+    // a single room has no walls to remove
+    if carved.is_empty() {
+        carved.push(room_cell((0, 0)));
+    }
+
+    carved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the maze `kind` carves on a `w` x `h` grid is perfect: every room carved
+    /// once, one passage less than there are rooms, and all of them connected
+    fn assert_perfect(kind: MazeKind, w: u32, h: u32, seed: u64) {
+        let carved = carve_order(kind, w, h, &mut Rng::new(seed));
+        let rooms = rooms(w, h);
+
+        let mut open = Grid2D::new(w, h, false);
+        for &cell in &carved {
+            assert!(
+                cell.0 > 0 && cell.1 > 0 && cell.0 + 1 < w && cell.1 + 1 < h,
+                "{:?} carved {:?} on the outer wall",
+                kind,
+                cell
+            );
+            assert!(!open[cell], "{:?} carved {:?} twice", kind, cell);
+            open[cell] = true;
+        }
+
+        for x in 0..rooms.0 {
+            for y in 0..rooms.1 {
+                assert!(
+                    open[room_cell((x, y))],
+                    "{:?} left {:?} a wall",
+                    kind,
+                    (x, y)
+                );
+            }
+        }
+
+        let room_count = (rooms.0 * rooms.1) as usize;
+        assert_eq!(carved.len() - room_count, room_count - 1, "{:?}", kind);
+
+        // flood the carved cells from the first room
+        let mut reached = Grid2D::new(w, h, false);
+        let mut stack = vec![room_cell((0, 0))];
+        reached[room_cell((0, 0))] = true;
+        let mut count = 1;
+        while let Some((x, y)) = stack.pop() {
+            for next in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)] {
+                if open[next] && !reached[next] {
+                    reached[next] = true;
+                    count += 1;
+                    stack.push(next);
+                }
+            }
+        }
+        assert_eq!(count, carved.len(), "{:?} isn't connected", kind);
+    }
+
+    #[test]
+    fn backtracker_mazes_are_perfect() {
+        for seed in 0..5 {
+            assert_perfect(MazeKind::Backtracker, 41, 31, seed);
+            assert_perfect(MazeKind::Backtracker, 40, 30, seed);
+        }
+    }
+
+    #[test]
+    fn prims_mazes_are_perfect() {
+        for seed in 0..5 {
+            assert_perfect(MazeKind::Prims, 41, 31, seed);
+            assert_perfect(MazeKind::Prims, 40, 30, seed);
+        }
+    }
+
+    #[test]
+    fn kruskal_mazes_are_perfect() {
+        for seed in 0..5 {
+            assert_perfect(MazeKind::Kruskal, 41, 31, seed);
+            assert_perfect(MazeKind::Kruskal, 40, 30, seed);
+        }
+    }
+
+    #[test]
+    fn tiny_mazes_are_perfect_too() {
+        for kind in [MazeKind::Backtracker, MazeKind::Prims, MazeKind::Kruskal] {
+            // a single room has no walls to remove
+            assert_eq!(
+                carve_order(kind, 3, 3, &mut Rng::new(1)),
+                [(1, 1)],
+                "{:?}",
+                kind
+            );
+            assert_perfect(kind, 3, 3, 1);
+            assert_perfect(kind, 5, 5, 1);
+            assert_perfect(kind, 5, 3, 1);
+            // no room fits at all
+            assert!(
+                carve_order(kind, 2, 9, &mut Rng::new(1)).is_empty(),
+                "{:?}",
+                kind
+            );
+        }
+    }
+}
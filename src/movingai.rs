@@ -0,0 +1,229 @@
+//! The `.map` and `.scen` formats of the MovingAI grid benchmarks,
+//! <https://movingai.com/benchmarks/formats.html>
+
+use std::path::Path;
+
+/// Obstacles of a benchmark map. Out of bounds (`@`, `O`), trees (`T`) and water (`W`)
+/// are obstacles, ground (`.`, `G`) and swamp (`S`) are free.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MovingAiMap {
+    pub width: u32,
+    pub height: u32,
+    /// Whether the benchmarks on this map move diagonally, `type octile`
+    pub octile: bool,
+    pub obstacles: Vec<(u32, u32)>,
+}
+
+impl MovingAiMap {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read map {}: {}", path.display(), e))?;
+
+        Self::parse(&text).map_err(|e| format!("invalid map {}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().map(str::trim_end).enumerate();
+
+        let mut kind = None;
+        let mut width = None;
+        let mut height = None;
+
+        for (i, line) in lines.by_ref() {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let parse_size = |value: &str| {
+                value
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|e| format!("invalid {} on line {}: {}", key, i + 1, e))
+            };
+
+            match key {
+                "type" => kind = Some(value.trim().to_string()),
+                "width" => width = Some(parse_size(value)?),
+                "height" => height = Some(parse_size(value)?),
+                "map" => break,
+                _ => return Err(format!("unexpected header {:?} on line {}", line, i + 1)),
+            }
+        }
+
+        let (Some(kind), Some(width), Some(height)) = (kind, width, height) else {
+            return Err("header needs type, width and height before map".to_string());
+        };
+
+        let mut obstacles = Vec::new();
+        let mut rows = 0;
+
+        for (i, line) in lines.filter(|(_, line)| !line.is_empty()) {
+            if rows == height {
+                return Err(format!("more than {} rows, line {}", height, i + 1));
+            }
+            if line.chars().count() != width as usize {
+                return Err(format!(
+                    "line {} is {} cells wide, expected {}",
+                    i + 1,
+                    line.chars().count(),
+                    width
+                ));
+            }
+
+            for (x, c) in line.chars().enumerate() {
+                match c {
+                    '.' | 'G' | 'S' => {}
+                    '@' | 'O' | 'T' | 'W' => obstacles.push((x as u32, rows)),
+                    _ => {
+                        return Err(format!(
+                            "unexpected {:?} on line {} column {}",
+                            c,
+                            i + 1,
+                            x + 1
+                        ))
+                    }
+                }
+            }
+
+            rows += 1;
+        }
+
+        if rows != height {
+            return Err(format!("{} rows, expected {}", rows, height));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            octile: kind == "octile",
+            obstacles,
+        })
+    }
+}
+
+/// A single start and goal to search between, from a `.scen` file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    /// Scenarios of similar length share a bucket
+    pub bucket: u32,
+    /// File name of the map the scenario is meant for
+    pub map: String,
+    pub width: u32,
+    pub height: u32,
+    pub start: (u32, u32),
+    pub goal: (u32, u32),
+    /// Length of the shortest 8-connected path without corner cutting, diagonals
+    /// costing the square root of 2
+    pub optimal_length: f64,
+}
+
+impl Scenario {
+    pub fn load_all(path: &Path) -> Result<Vec<Self>, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read scenarios {}: {}", path.display(), e))?;
+
+        Self::parse_all(&text).map_err(|e| format!("invalid scenarios {}: {}", path.display(), e))
+    }
+
+    pub fn parse_all(text: &str) -> Result<Vec<Self>, String> {
+        let mut lines = text.lines().enumerate();
+
+        match lines.next() {
+            Some((_, version)) if version.trim().starts_with("version") => {}
+            _ => return Err("expected a version line first".to_string()),
+        }
+
+        lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| Self::parse(line).map_err(|e| format!("line {}: {}", i + 1, e)))
+            .collect()
+    }
+
+    /// `bucket map width height start_x start_y goal_x goal_y optimal_length`, separated
+    /// by tabs. Map names can contain spaces, so nothing else separates the columns.
+    fn parse(line: &str) -> Result<Self, String> {
+        let columns: Vec<_> = line.trim_end().split('\t').collect();
+
+        let [bucket, map, width, height, start_x, start_y, goal_x, goal_y, optimal_length] =
+            columns[..]
+        else {
+            return Err(format!("expected 9 columns, got {}", columns.len()));
+        };
+
+        let number = |n: &str| {
+            n.trim()
+                .parse::<u32>()
+                .map_err(|e| format!("invalid number {:?}: {}", n, e))
+        };
+
+        Ok(Self {
+            bucket: number(bucket)?,
+            map: map.to_string(),
+            width: number(width)?,
+            height: number(height)?,
+            start: (number(start_x)?, number(start_y)?),
+            goal: (number(goal_x)?, number(goal_y)?),
+            optimal_length: optimal_length
+                .trim()
+                .parse()
+                .map_err(|e| format!("invalid length {:?}: {}", optimal_length, e))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_parse_obstacles_row_by_row() {
+        let map = MovingAiMap::parse("type octile\nheight 2\nwidth 3\nmap\n.@T\nSW.\n").unwrap();
+
+        assert_eq!((map.width, map.height), (3, 2));
+        assert!(map.octile);
+        assert_eq!(map.obstacles, vec![(1, 0), (2, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn map_headers_need_a_type_and_a_size() {
+        assert!(MovingAiMap::parse("type octile\nwidth 1\nmap\n.\n").is_err());
+        assert!(MovingAiMap::parse("type octile\nheight 1\nwidth x\nmap\n.\n").is_err());
+        assert!(MovingAiMap::parse("type octile\ndepth 1\nheight 1\nwidth 1\nmap\n.\n").is_err());
+    }
+
+    #[test]
+    fn map_rows_have_to_match_the_size() {
+        let header = "type octile\nheight 2\nwidth 3\nmap\n";
+
+        assert!(MovingAiMap::parse(&format!("{}...\n..\n", header)).is_err());
+        assert!(MovingAiMap::parse(&format!("{}...\n....\n", header)).is_err());
+        assert!(MovingAiMap::parse(&format!("{}...\n", header)).is_err());
+        assert!(MovingAiMap::parse(&format!("{}...\n...\n...\n", header)).is_err());
+        assert!(MovingAiMap::parse(&format!("{}...\n.x.\n", header)).is_err());
+    }
+
+    #[test]
+    fn scenario_map_names_can_contain_spaces() {
+        let scenarios =
+            Scenario::parse_all("version 1\n3\tmaps/my map.map\t4\t5\t0\t1\t3\t4\t4.41421356\n\n")
+                .unwrap();
+
+        assert_eq!(
+            scenarios,
+            vec![Scenario {
+                bucket: 3,
+                map: "maps/my map.map".to_string(),
+                width: 4,
+                height: 5,
+                start: (0, 1),
+                goal: (3, 4),
+                optimal_length: 4.41421356,
+            }]
+        );
+    }
+
+    #[test]
+    fn scenarios_need_a_version_and_every_column() {
+        assert!(Scenario::parse_all("0\ta.map\t4\t5\t0\t1\t3\t4\t4.4\n").is_err());
+        assert!(Scenario::parse_all("version 1\n0\ta.map\t4\t5\t0\t1\t3\t4\n").is_err());
+        assert!(Scenario::parse_all("version 1\n0 a.map 4 5 0 1 3 4 4.4\n").is_err());
+        assert!(Scenario::parse_all("version 1\n0\ta.map\t4\t5\t0\t-1\t3\t4\t4.4\n").is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+/// How much a single click or key press changes the delay and fps
+const DELAY_STEP: Duration = Duration::from_millis(10);
+const FPS_STEP: u32 = 10;
+
+/// How fast the search plays back, changed from the keyboard and the settings bar
+#[derive(Clone, Copy, Debug)]
+pub struct Playback {
+    /// Delay between iterations
+    pub delay: Duration,
+    pub fps: u32,
+    pub paused: bool,
+    /// Single iterations requested while paused, run on the next loop
+    pub steps: u64,
+    /// Virtual time that passed since the last iteration, only used when deterministic
+    lag: Duration,
+}
+
+impl Playback {
+    pub fn new(delay: Duration, fps: u32) -> Self {
+        Self {
+            delay,
+            fps: fps.max(1),
+            paused: false,
+            steps: 0,
+            lag: Duration::ZERO,
+        }
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps as f64)
+    }
+
+    /// Counts a rendered frame as exactly one frame interval of virtual time, unless paused
+    pub fn frame_passed(&mut self) {
+        if !self.paused {
+            self.lag += self.frame_interval();
+        }
+    }
+
+    /// Iterations a `--deterministic` run is due before the next frame: as many delays as
+    /// fit into the virtual time since the last iteration. What is left over carries on to
+    /// the next frame, so over many frames the iterations keep up with the delay even
+    /// when it doesn't divide the frame interval. Without a delay, one a frame.
+    pub fn deterministic_due(&mut self) -> u64 {
+        if self.delay.is_zero() {
+            return u64::from(!std::mem::take(&mut self.lag).is_zero());
+        }
+
+        let due = self.lag.as_nanos() / self.delay.as_nanos();
+        self.lag -= self.delay * due as u32;
+
+        due as u64
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.steps = 0;
+    }
+
+    /// Runs exactly one more iteration, only while paused
+    pub fn step(&mut self) {
+        if self.paused {
+            self.steps += 1;
+        }
+    }
+
+    pub fn decrease_delay(&mut self) {
+        self.delay = self.delay.saturating_sub(DELAY_STEP);
+    }
+
+    pub fn increase_delay(&mut self) {
+        self.delay += DELAY_STEP;
+    }
+
+    /// Halves the delay, in whole milliseconds like the settings bar shows it
+    pub fn halve_delay(&mut self) {
+        self.delay = Duration::from_millis(self.delay.as_millis() as u64 / 2);
+    }
+
+    /// Doubles the delay, starting from a millisecond once it is zero
+    pub fn double_delay(&mut self) {
+        self.delay = (self.delay * 2).max(Duration::from_millis(1));
+    }
+
+    pub fn decrease_fps(&mut self) {
+        self.fps = self.fps.saturating_sub(FPS_STEP).max(1);
+    }
+
+    pub fn increase_fps(&mut self) {
+        self.fps += FPS_STEP;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Iterations due before each of the first `frames` frames, the way the main loop asks
+    fn due_per_frame(delay_ms: u64, fps: u32, frames: usize) -> Vec<u64> {
+        let mut playback = Playback::new(Duration::from_millis(delay_ms), fps);
+
+        (0..frames)
+            .map(|_| {
+                let due = playback.deterministic_due();
+                playback.frame_passed();
+                due
+            })
+            .collect()
+    }
+
+    #[test]
+    fn iterations_per_frame_only_depend_on_delay_and_fps() {
+        assert_eq!(due_per_frame(15, 50, 200), due_per_frame(15, 50, 200));
+        assert_ne!(due_per_frame(15, 50, 200), due_per_frame(15, 40, 200));
+        assert_ne!(due_per_frame(15, 50, 200), due_per_frame(10, 50, 200));
+    }
+
+    #[test]
+    fn leftover_time_carries_over_to_the_next_frame() {
+        // 20ms frames fit one 15ms delay each, the rest adds up to another every third
+        assert_eq!(due_per_frame(15, 50, 7), [0, 1, 1, 2, 1, 1, 2]);
+        // a second of frames runs a second of iterations
+        assert_eq!(due_per_frame(15, 50, 151).iter().sum::<u64>(), 200);
+        // slower than the frames
+        assert_eq!(due_per_frame(50, 50, 6), [0, 0, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn without_a_delay_every_frame_gets_one_iteration() {
+        assert_eq!(due_per_frame(0, 30, 4), [0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn paused_frames_add_no_time() {
+        let mut playback = Playback::new(Duration::from_millis(10), 50);
+        playback.toggle_pause();
+        playback.frame_passed();
+        playback.toggle_pause();
+
+        assert_eq!(playback.deterministic_due(), 0);
+        playback.frame_passed();
+        assert_eq!(playback.deterministic_due(), 2);
+    }
+}
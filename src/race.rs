@@ -0,0 +1,109 @@
+use std::time::{Duration, Instant};
+
+use dijkstra_visual::{
+    search::{format_dist, Algorithm, SearchStatus},
+    Grid,
+};
+
+/// Several algorithms searching copies of the same map, a step each in turn, timed
+/// separately so they can be ranked once all of them are done
+#[derive(Debug)]
+pub struct Race {
+    racers: Vec<Racer>,
+}
+
+#[derive(Debug)]
+struct Racer {
+    grid: Grid,
+    /// Time spent in the racer's own steps
+    time: Duration,
+}
+
+impl Race {
+    pub fn new(grid: &Grid, algorithms: &[Algorithm]) -> Self {
+        Self {
+            racers: algorithms
+                .iter()
+                .map(|&algorithm| Racer {
+                    grid: grid.with_algorithm(algorithm),
+                    time: Duration::ZERO,
+                })
+                .collect(),
+        }
+    }
+
+    /// Gives every racer still searching a step, starting them all over first if the map
+    /// or agents of `grid` changed
+    pub fn step(&mut self, grid: &Grid) {
+        if self
+            .racers
+            .first()
+            .is_some_and(|racer| !racer.grid.same_setup(grid))
+        {
+            for racer in self.racers.iter_mut() {
+                racer.grid = grid.with_algorithm(racer.grid.searches()[0].algorithm());
+                racer.time = Duration::ZERO;
+            }
+        }
+
+        for racer in self.racers.iter_mut().filter(|racer| !racer.finished()) {
+            let started = Instant::now();
+            racer.grid.dijkstra_iteration();
+            // nobody watches the path being colored
+            racer.grid.skip_reveal();
+            racer.time += started.elapsed();
+        }
+    }
+
+    pub fn finished(&self) -> bool {
+        self.racers.iter().all(Racer::finished)
+    }
+
+    /// Ranking table, a header and a line per racer: cheapest path first, racers without
+    /// one last, ties broken by fewer expanded cells and then by less time
+    pub fn standings(&self) -> Vec<String> {
+        let mut racers: Vec<_> = self.racers.iter().collect();
+        racers.sort_by_key(|racer| {
+            (
+                racer.cost().is_none(),
+                racer.cost(),
+                racer.grid.searches()[0].expanded(),
+                racer.time,
+            )
+        });
+
+        let mut lines = vec![format!(
+            "{:<3}{:<24}{:>10}{:>10}{:>12}",
+            "#", "ALGORITHM", "EXPANDED", "COST", "TIME"
+        )];
+
+        lines.extend(racers.iter().enumerate().map(|(i, racer)| {
+            format!(
+                "{:<3}{:<24}{:>10}{:>10}{:>12}",
+                i + 1,
+                racer.grid.searches()[0].algorithm().to_string(),
+                racer.grid.searches()[0].expanded(),
+                racer.cost().map_or("-".to_string(), format_dist),
+                format!("{:.2?}", racer.time)
+            )
+        }));
+
+        lines
+    }
+}
+
+impl Racer {
+    fn finished(&self) -> bool {
+        !matches!(
+            self.grid.status(),
+            SearchStatus::Running | SearchStatus::Revealing
+        )
+    }
+
+    /// Cost of the path found, `None` without one
+    fn cost(&self) -> Option<i64> {
+        self.grid
+            .path_cost()
+            .filter(|_| self.grid.status() == SearchStatus::Found)
+    }
+}
@@ -0,0 +1,204 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Child, ChildStdin, Command, Stdio},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use sdl2::{pixels::PixelFormatEnum, render::Canvas, video::Window};
+
+/// Quantization speed from 1 to 30, faster loses colors but the visualization only has
+/// a handful of them anyway
+const QUANTIZE_SPEED: i32 = 20;
+
+/// Something rendered frames are written into. Recordings have the size of the window
+/// when they started, frames of a resized window get cropped or padded with black.
+pub trait Recorder {
+    /// Adds the frame just rendered to `canvas`. Has to be called before presenting it.
+    /// `frame_interval` is how long it stays on screen.
+    fn capture(&mut self, canvas: &Canvas<Window>, frame_interval: Duration) -> Result<(), String>;
+
+    /// Ends the recording, the file isn't valid before
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+/// The frame rendered to `canvas` as tightly packed RGB, cropped or padded to
+/// `width` x `height`
+fn read_frame(canvas: &Canvas<Window>, width: usize, height: usize) -> Result<Vec<u8>, String> {
+    let (w, h) = canvas.output_size()?;
+    let pixels = canvas.read_pixels(None, PixelFormatEnum::RGB24)?;
+
+    let mut rgb = vec![0; width * height * 3];
+    let row = width.min(w as usize) * 3;
+
+    for y in 0..height.min(h as usize) {
+        let from = y * w as usize * 3;
+        rgb[y * width * 3..][..row].copy_from_slice(&pixels[from..][..row]);
+    }
+
+    Ok(rgb)
+}
+
+/// Writes rendered frames into an animated GIF as they come, so long runs don't pile up
+/// in memory
+pub struct GifRecorder {
+    encoder: gif::Encoder<BufWriter<File>>,
+    width: u16,
+    height: u16,
+    /// Only every `every`th frame is captured
+    every: u32,
+    frames: u32,
+}
+
+impl GifRecorder {
+    pub fn create(path: &Path, (width, height): (u32, u32), every: u32) -> Result<Self, String> {
+        let error =
+            |e: &dyn std::fmt::Display| format!("couldn't record {}: {}", path.display(), e);
+
+        let (width, height) = (
+            u16::try_from(width).map_err(|e| error(&e))?,
+            u16::try_from(height).map_err(|e| error(&e))?,
+        );
+
+        let file = File::create(path).map_err(|e| error(&e))?;
+        let mut encoder =
+            gif::Encoder::new(BufWriter::new(file), width, height, &[]).map_err(|e| error(&e))?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|e| error(&e))?;
+
+        Ok(Self {
+            encoder,
+            width,
+            height,
+            every: every.max(1),
+            frames: 0,
+        })
+    }
+}
+
+impl Recorder for GifRecorder {
+    fn capture(&mut self, canvas: &Canvas<Window>, frame_interval: Duration) -> Result<(), String> {
+        let skipped = !self.frames.is_multiple_of(self.every);
+        self.frames += 1;
+
+        if skipped {
+            return Ok(());
+        }
+
+        let rgb = read_frame(canvas, self.width as usize, self.height as usize)?;
+
+        let mut frame = gif::Frame::from_rgb_speed(self.width, self.height, &rgb, QUANTIZE_SPEED);
+        // in hundredths of a second, browsers slow down anything below 2
+        frame.delay =
+            ((frame_interval * self.every).as_millis() / 10).clamp(2, u16::MAX as u128) as u16;
+
+        self.encoder.write_frame(&frame).map_err(|e| e.to_string())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let mut writer = self.encoder.into_inner().map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// Streams raw frames into an `ffmpeg` process, which encodes them into whatever video
+/// format the file name asks for. Every frame becomes one video frame at a fixed rate.
+pub struct VideoRecorder {
+    ffmpeg: Child,
+    stdin: ChildStdin,
+    width: u32,
+    height: u32,
+}
+
+impl VideoRecorder {
+    pub fn create(path: &Path, (width, height): (u32, u32), fps: u32) -> Result<Self, String> {
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args([
+                "-loglevel",
+                "error",
+                "-y",
+                "-f",
+                "rawvideo",
+                "-pixel_format",
+                "rgb24",
+            ])
+            .args(["-video_size", &format!("{}x{}", width, height)])
+            .args(["-framerate", &fps.to_string(), "-i", "-"])
+            // most players only take yuv420p, which needs an even size
+            .args([
+                "-vf",
+                "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                "-pix_fmt",
+                "yuv420p",
+            ])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("couldn't start ffmpeg to record {}: {}", path.display(), e))?;
+
+        let stdin = ffmpeg.stdin.take().expect("stdin is piped");
+
+        Ok(Self {
+            ffmpeg,
+            stdin,
+            width,
+            height,
+        })
+    }
+}
+
+impl Recorder for VideoRecorder {
+    fn capture(&mut self, canvas: &Canvas<Window>, _: Duration) -> Result<(), String> {
+        let rgb = read_frame(canvas, self.width as usize, self.height as usize)?;
+
+        self.stdin
+            .write_all(&rgb)
+            .map_err(|e| format!("ffmpeg stopped taking frames: {}", e))
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let Self {
+            mut ffmpeg, stdin, ..
+        } = *self;
+
+        // the end of its input is what tells ffmpeg to finish the file
+        drop(stdin);
+
+        match ffmpeg.wait() {
+            Ok(status) if status.success() => Ok(()),
+            Ok(status) => Err(format!("ffmpeg failed with {}", status)),
+            Err(e) => Err(format!("couldn't wait for ffmpeg: {}", e)),
+        }
+    }
+}
+
+/// Saves the frame just rendered to `canvas` as a PNG named after the current time into
+/// `dir`, returns where. Has to be called before presenting it.
+pub fn save_screenshot(canvas: &Canvas<Window>, dir: &Path) -> Result<PathBuf, String> {
+    let (width, height) = canvas.output_size()?;
+    let rgb = read_frame(canvas, width as usize, height as usize)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let path = dir.join(format!(
+        "dijkstra-{}-{:03}.png",
+        now.as_secs(),
+        now.subsec_millis()
+    ));
+
+    let error = |e: &dyn std::fmt::Display| format!("couldn't save {}: {}", path.display(), e);
+
+    let file = File::create(&path).map_err(|e| error(&e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    writer.write_image_data(&rgb).map_err(|e| error(&e))?;
+    writer.finish().map_err(|e| error(&e))?;
+
+    Ok(path)
+}
@@ -0,0 +1,14 @@
+use std::time::Duration;
+
+use dijkstra_visual::Grid;
+
+use crate::view::View;
+
+/// Where the cells of a grid are drawn: a pane of the window, or the terminal for
+/// `--ascii` and `--tui`. What every cell shows comes from [`dijkstra_visual::shown`] and
+/// the colors from the [`View`], whichever draws it.
+pub trait Renderer {
+    /// Draws the cells of `grid` the way `view` colors them, `now` only matters for
+    /// fading cells. Fails once the frame can't go anywhere anymore, like a closed stdout.
+    fn draw_cells(&mut self, view: &mut View, grid: &Grid, now: Duration) -> Result<(), String>;
+}
@@ -0,0 +1,3178 @@
+use std::{
+    collections::{BinaryHeap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    changes::ChangeLog,
+    goal::GoalSpec,
+    grid2d::Grid2D,
+    map::{line, triangle_distance, Map, Portal, Topology, DIAGONAL_COST, UNIT_COST},
+};
+
+/// Most nodes Floyd-Warshall keeps the distances between, its memory grows with the square
+/// and its time with the cube of them. Maps with more free cells run on coarser blocks.
+const MAX_ALL_PAIRS_NODES: usize = 1600;
+
+/// How the search picks and expands cells
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Dijkstra,
+    /// Dijkstra guided by an estimate of the distance to the goal, see [`Heuristic`]
+    #[value(name = "astar")]
+    AStar,
+    /// Lazy Theta*, any-angle paths on the 8-connected grid
+    Theta,
+    /// Breadth-first, expands cells in the order they were found. Fewest steps, but blind
+    /// to terrain costs and diagonals being longer.
+    Bfs,
+    /// Depth-first, always expands the cell found last. Snakes into dead ends and finds
+    /// paths that can be far from the shortest.
+    Dfs,
+    /// Dijkstra from the start and back from the goal at once, done once the two halves
+    /// meet
+    Bidirectional,
+    /// A* from the start towards the goal and from the goal towards the start at once
+    #[value(name = "bidirectional-astar")]
+    #[serde(rename = "bidirectional-astar")]
+    BidirectionalAStar,
+    /// Jump point search, A* on the 8-connected grid that jumps along straight lines and
+    /// only expands cells where the way could turn. Only finds the shortest paths on
+    /// uniform terrain.
+    Jps,
+    /// D* Lite, searches back from the goal and repairs its distances when the map changes
+    /// or the start moves instead of starting over
+    #[value(name = "dstar-lite")]
+    #[serde(rename = "dstar-lite")]
+    DStarLite,
+    /// Lifelong Planning A*, repairs its distances from the start when the map changes
+    /// instead of starting over
+    Lpa,
+    /// Iterative deepening A*, depth-first passes that only follow cells up to a priority
+    /// bound, raised after every pass. Only remembers the path it is on, but walks the same
+    /// cells over and over, so it is only bearable on small maps.
+    Ida,
+    /// Bellman-Ford, relaxes every cell of the grid once per sweep until a sweep changes
+    /// nothing. Slow, but the only search that takes negative terrain into account, and
+    /// tells when it makes a path endlessly cheaper by going in circles.
+    #[value(name = "bellman-ford")]
+    #[serde(rename = "bellman-ford")]
+    BellmanFord,
+    /// Floyd-Warshall, the distances between all pairs of cells at once, allowing one more
+    /// cell on the way between them every iteration. Larger maps run on square blocks of
+    /// cells instead, whose paths are only roughly the shortest.
+    #[value(name = "floyd-warshall")]
+    #[serde(rename = "floyd-warshall")]
+    FloydWarshall,
+}
+
+impl Algorithm {
+    pub fn uses_heuristic(self) -> bool {
+        match self {
+            Algorithm::Dijkstra
+            | Algorithm::Bfs
+            | Algorithm::Dfs
+            | Algorithm::Bidirectional
+            | Algorithm::BellmanFord
+            | Algorithm::FloydWarshall => false,
+            Algorithm::AStar
+            | Algorithm::Theta
+            | Algorithm::BidirectionalAStar
+            | Algorithm::Jps
+            | Algorithm::DStarLite
+            | Algorithm::Lpa
+            | Algorithm::Ida => true,
+        }
+    }
+
+    /// Whether finding a shorter way to a cell in the open set replaces the one it was
+    /// queued with, otherwise the first way found is kept
+    pub fn relaxes(self) -> bool {
+        match self {
+            Algorithm::Dijkstra
+            | Algorithm::AStar
+            | Algorithm::Theta
+            | Algorithm::Bidirectional
+            | Algorithm::BidirectionalAStar
+            | Algorithm::Jps
+            | Algorithm::DStarLite
+            | Algorithm::Lpa
+            | Algorithm::BellmanFord
+            | Algorithm::FloydWarshall => true,
+            Algorithm::Bfs | Algorithm::Dfs | Algorithm::Ida => false,
+        }
+    }
+
+    /// Whether a second search runs back from the goal to meet the one from the start
+    pub fn bidirectional(self) -> bool {
+        matches!(
+            self,
+            Algorithm::Bidirectional | Algorithm::BidirectionalAStar
+        )
+    }
+
+    /// Whether changes to the map are repaired instead of restarting the search
+    pub fn incremental(self) -> bool {
+        matches!(self, Algorithm::DStarLite | Algorithm::Lpa)
+    }
+
+    /// Whether the search grows from the extra starts too, see [`Search::set_extra_starts`]
+    pub fn uses_extra_starts(self) -> bool {
+        !matches!(self, Algorithm::Ida | Algorithm::FloydWarshall)
+    }
+
+    /// Whether found paths are always the shortest possible ones on the grid
+    pub fn finds_shortest(self) -> bool {
+        match self {
+            Algorithm::Dijkstra
+            | Algorithm::AStar
+            | Algorithm::Bidirectional
+            | Algorithm::BidirectionalAStar
+            | Algorithm::DStarLite
+            | Algorithm::Lpa
+            | Algorithm::Ida
+            | Algorithm::BellmanFord
+            | Algorithm::FloydWarshall => true,
+            Algorithm::Theta | Algorithm::Bfs | Algorithm::Dfs | Algorithm::Jps => false,
+        }
+    }
+}
+
+impl std::fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Algorithm::Dijkstra => write!(f, "PURE DIJKSTRA"),
+            Algorithm::AStar => write!(f, "A*"),
+            Algorithm::Theta => write!(f, "THETA*"),
+            Algorithm::Bfs => write!(f, "BFS"),
+            Algorithm::Dfs => write!(f, "DFS"),
+            Algorithm::Bidirectional => write!(f, "BIDIRECTIONAL DIJKSTRA"),
+            Algorithm::BidirectionalAStar => write!(f, "BIDIRECTIONAL A*"),
+            Algorithm::Jps => write!(f, "JPS"),
+            Algorithm::DStarLite => write!(f, "D* LITE"),
+            Algorithm::Lpa => write!(f, "LPA*"),
+            Algorithm::Ida => write!(f, "IDA*"),
+            Algorithm::BellmanFord => write!(f, "BELLMAN-FORD"),
+            Algorithm::FloydWarshall => write!(f, "FLOYD-WARSHALL"),
+        }
+    }
+}
+
+/// How the heuristic measures the distance to the goal, ignoring obstacles and terrain.
+/// Without one, searches pick the one that fits how they move.
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Heuristic {
+    /// The straight line, rounded down. Never more than any way there, but less than the
+    /// steps on a grid take, unless they may go in any angle
+    Euclidean,
+    /// Steps along both axes, exact on an empty 4-connected grid. Too much once steps
+    /// may be diagonal.
+    Manhattan,
+    /// The farther of the two axes, as if diagonal steps were as cheap as straight ones
+    Chebyshev,
+    /// Diagonal steps for as long as both axes are left, then straight ones. Exact on an
+    /// empty 8-connected grid, too much for any-angle paths.
+    Octile,
+    /// Nothing at all, which leaves A* to expand cells like Dijkstra does
+    Zero,
+}
+
+impl Heuristic {
+    /// The estimate for a goal `dx` columns and `dy` rows away
+    fn estimate(self, dx: u32, dy: u32) -> u32 {
+        match self {
+            Heuristic::Euclidean => {
+                let distance = ((dx as f64).powi(2) + (dy as f64).powi(2)).sqrt();
+
+                (distance * UNIT_COST as f64) as u32
+            }
+            Heuristic::Manhattan => (dx + dy) * UNIT_COST,
+            Heuristic::Chebyshev => dx.max(dy) * UNIT_COST,
+            Heuristic::Octile => dx.abs_diff(dy) * UNIT_COST + dx.min(dy) * DIAGONAL_COST,
+            Heuristic::Zero => 0,
+        }
+    }
+
+    /// Whether the estimate never exceeds the cost of a way to the goal, for searches
+    /// stepping diagonally or not, or going in any angle
+    fn admissible(self, diagonal: bool, any_angle: bool) -> bool {
+        match self {
+            Heuristic::Euclidean | Heuristic::Chebyshev | Heuristic::Zero => true,
+            Heuristic::Manhattan => !diagonal && !any_angle,
+            Heuristic::Octile => !any_angle,
+        }
+    }
+}
+
+impl std::fmt::Display for Heuristic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Heuristic::Euclidean => write!(f, "EUCLIDEAN"),
+            Heuristic::Manhattan => write!(f, "MANHATTAN"),
+            Heuristic::Chebyshev => write!(f, "CHEBYSHEV"),
+            Heuristic::Octile => write!(f, "OCTILE"),
+            Heuristic::Zero => write!(f, "ZERO"),
+        }
+    }
+}
+
+/// Formats a fixed point distance in steps, with a decimal only where needed
+pub fn format_dist(dist: impl Into<i64>) -> String {
+    let dist = dist.into();
+
+    if dist % UNIT_COST as i64 == 0 {
+        format!("{}", dist / UNIT_COST as i64)
+    } else {
+        format!("{:.1}", dist as f64 / UNIT_COST as f64)
+    }
+}
+
+/// How far a single search has gotten with a cell. Obstacles live in the [`Map`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellState {
+    Unknown,
+    /// `dist` is the best known distance so far, it may still decrease
+    Unvisited {
+        dist: u32,
+    },
+    Visited {
+        dist: u32,
+    },
+    OnPath,
+    /// Free cell cut off from the start, only marked once there turns out to be no path
+    Unreachable,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+struct UnvisitedState {
+    /// This optionally includes euclidean distance when using a heuristic
+    pub dist: u32,
+    /// This never includes euclidean distance
+    pub actual_dist: u32,
+    pub cell: (u32, u32),
+}
+
+impl Ord for UnvisitedState {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other
+            .dist
+            .cmp(&self.dist)
+            .then(other.actual_dist.cmp(&self.actual_dist))
+            .then_with(|| self.cell.cmp(&other.cell))
+    }
+}
+
+impl PartialOrd for UnvisitedState {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The open set, in the order the algorithm takes cells out of it
+#[derive(Debug)]
+enum OpenSet {
+    /// Lowest `dist` first
+    Heap(BinaryHeap<UnvisitedState>),
+    /// First in, first out
+    Queue(VecDeque<UnvisitedState>),
+    /// Last in, first out
+    Stack(Vec<UnvisitedState>),
+}
+
+impl OpenSet {
+    fn new(algorithm: Algorithm) -> Self {
+        match algorithm {
+            Algorithm::Dijkstra
+            | Algorithm::AStar
+            | Algorithm::Theta
+            | Algorithm::Bidirectional
+            | Algorithm::BidirectionalAStar
+            | Algorithm::Jps
+            | Algorithm::DStarLite
+            | Algorithm::Lpa
+            | Algorithm::Ida
+            | Algorithm::BellmanFord
+            | Algorithm::FloydWarshall => OpenSet::Heap(BinaryHeap::new()),
+            Algorithm::Bfs => OpenSet::Queue(VecDeque::new()),
+            Algorithm::Dfs => OpenSet::Stack(Vec::new()),
+        }
+    }
+
+    fn push(&mut self, entry: UnvisitedState) {
+        match self {
+            OpenSet::Heap(heap) => heap.push(entry),
+            OpenSet::Queue(queue) => queue.push_back(entry),
+            OpenSet::Stack(stack) => stack.push(entry),
+        }
+    }
+
+    fn pop(&mut self) -> Option<UnvisitedState> {
+        match self {
+            OpenSet::Heap(heap) => heap.pop(),
+            OpenSet::Queue(queue) => queue.pop_front(),
+            OpenSet::Stack(stack) => stack.pop(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            OpenSet::Heap(heap) => heap.len(),
+            OpenSet::Queue(queue) => queue.len(),
+            OpenSet::Stack(stack) => stack.len(),
+        }
+    }
+}
+
+/// Where the search currently stands
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchStatus {
+    Running,
+    /// The goal was reached and the path is being colored, a cell per iteration
+    Revealing,
+    /// The goal was reached and the path is colored
+    Found,
+    /// The open set ran empty without reaching the goal, which is trapped in a region
+    /// of `trapped` cells the search couldn't get into. Searches going back from the goal
+    /// count the start's region instead, see [`Search::searches_from_goal`].
+    NoPath {
+        trapped: usize,
+    },
+    /// Bellman-Ford found a loop of negative terrain it could go around forever, making
+    /// every path through it ever cheaper, so there is no shortest one. Its cells are
+    /// colored like a path.
+    NegativeCycle {
+        length: usize,
+    },
+}
+
+/// Where IDA* is in its depth-first passes
+#[derive(Debug)]
+struct Deepening {
+    /// The path being followed: every cell with its distance and the index of its next
+    /// neighbor to try
+    stack: Vec<((u32, u32), u32, usize)>,
+    /// Highest priority followed in this pass
+    bound: u32,
+    /// Lowest priority above `bound` seen in this pass, the bound of the next one
+    next_bound: u32,
+    pass: u32,
+    /// Pass every cell was last reached in, only to draw them
+    passes: Grid2D<Option<u32>>,
+    /// Cells followed over all passes, counting every time again
+    visits: usize,
+}
+
+/// Where Bellman-Ford is in its sweeps over the whole grid
+#[derive(Debug)]
+struct Sweeps {
+    /// Best known distance of every cell, which unlike those of the other searches can
+    /// be negative. `None` if it wasn't reached yet.
+    dist: Grid2D<Option<i64>>,
+    /// Sweeps done so far
+    count: usize,
+    /// Cells whose distance the last sweep lowered
+    lowered: usize,
+}
+
+/// Where Floyd-Warshall is in its iterations over the nodes, which are the free cells or on
+/// larger maps square blocks of them
+#[derive(Debug)]
+struct AllPairs {
+    /// Side of the blocks of cells that make up a node
+    scale: u32,
+    /// Block every node stands for
+    nodes: Vec<(u32, u32)>,
+    /// Node of every block, `None` if all of its cells are obstacles
+    node_at: Grid2D<Option<usize>>,
+    /// Nodes with a goal cell
+    goals: Vec<usize>,
+    /// Distance from every node to every other, row by row, `u32::MAX` if unknown
+    dist: Vec<u32>,
+    /// Nodes allowed on the way between others so far
+    k: usize,
+}
+
+/// A single search from `start` to `goal`. It doesn't own the terrain, every step
+/// is handed the [`Map`] it runs on, so several searches can share one map.
+#[derive(Debug)]
+pub struct Search {
+    algorithm: Algorithm,
+    /// Whether grid searches also step diagonally, Theta* and JPS always do
+    diagonals: bool,
+    /// Factor the heuristic is multiplied by, above 1 trades the shortest path for fewer
+    /// expanded cells
+    heuristic_weight: f64,
+    /// How the heuristic measures, `None` for the one that fits how the search moves
+    heuristic_kind: Option<Heuristic>,
+    /// Whether the search goes on past the goal until every cell it can reach has a
+    /// distance, see [`Self::set_flow_field`]
+    flow_field: bool,
+    status: SearchStatus,
+
+    cells: Grid2D<CellState>,
+    /// The open set. Finding a shorter distance to a queued cell pushes another entry
+    /// instead of updating the old one, since a binary heap can't decrease keys. Only the
+    /// entry matching the distance in `cells` is live, the others are skipped once popped.
+    unvisited: OpenSet,
+    /// Priority every cell was last queued with, its f-value for searches with a
+    /// heuristic. Only that of queued cells is still current.
+    priorities: Grid2D<u32>,
+    /// Cell every reached cell was reached from with its best known distance
+    parents: Grid2D<Option<(u32, u32)>>,
+    /// Corners of the found path from start to goal, only tracked by Theta* and JPS
+    waypoints: Vec<(u32, u32)>,
+    /// Found path from start to goal, every cell of it for grid searches and just the
+    /// waypoints for Theta*
+    path: Vec<(u32, u32)>,
+    /// Cells of the found path still to be colored, from the goal back to the start
+    reveal: VecDeque<(u32, u32)>,
+    /// Cells in the order they were settled, i.e. taken off the open set for good
+    settle_order: Vec<(u32, u32)>,
+    /// Position of every settled cell in `settle_order`
+    settle_index: Grid2D<Option<u32>>,
+
+    start: (u32, u32),
+    /// More cells the search grows from at the same time as from `start`, see
+    /// [`Self::set_extra_starts`]
+    extra_starts: Vec<(u32, u32)>,
+    /// Which start every reached cell was reached from, 0 for `start` and the others
+    /// numbered after it
+    origins: Grid2D<Option<usize>>,
+    current: (u32, u32),
+    current_dist: u32,
+    goal: GoalSpec,
+
+    /// Whether this searches from the goal back to the start, taking every move in reverse
+    reversed: bool,
+    /// The half of a bidirectional search going back from the goal
+    backward: Option<Box<Search>>,
+    /// Cell where the two halves of a bidirectional search met with the shortest path
+    /// through it so far, and that path's length
+    meeting: Option<((u32, u32), u32)>,
+    /// For both halves of a bidirectional A*: the goal of the forward half, and the
+    /// constant their potentials add up to in every cell
+    balance: Option<(GoalSpec, u32)>,
+    /// The map's portals as of the last restart, for the heuristic to take shortcuts
+    /// through
+    portals: Vec<Portal>,
+    /// The map's cell shape as of the last restart, for the heuristic
+    topology: Topology,
+    /// The map's layers and how many rows each has as of the last restart, for the
+    /// heuristic to only measure across a layer
+    layers: (u32, u32),
+
+    /// For incremental searches, the distance every cell was last expanded with and the
+    /// one its neighbors offer it, D* Lite's g and rhs. `u32::MAX` if there is none.
+    lookahead: Grid2D<(u32, u32)>,
+    /// How far the start of D* Lite moved so far by the heuristic, added to every priority
+    /// so those queued before stay valid
+    key_modifier: u32,
+    /// Cells an incremental search expanded again since the map last changed, `None`
+    /// before it first did
+    repaired: Option<Grid2D<bool>>,
+    /// Number of expansions since the map last changed
+    repairs: usize,
+    /// Only for IDA*
+    deepening: Option<Deepening>,
+    /// Only for Bellman-Ford
+    sweeps: Option<Sweeps>,
+    /// Only for Floyd-Warshall
+    all_pairs: Option<AllPairs>,
+    /// Longest distance of any cell so far, only for searches drawn as a distance field
+    farthest: Option<u32>,
+    /// Most entries the open set had at once since the last restart
+    peak_open_set: usize,
+    /// How often the search was started over
+    restarts: u64,
+    /// Cells whose state, or anything else they are drawn by, changed
+    changes: ChangeLog,
+    /// Time spent searching since the last restart, without coloring the path
+    search_time: Duration,
+}
+
+impl Search {
+    pub fn new(map: &Map, start: (u32, u32), goal: GoalSpec, algorithm: Algorithm) -> Self {
+        assert!(map.in_bounds(start), "start isn't in bounds");
+        assert!(!goal.cells(map).is_empty(), "goal isn't in bounds");
+
+        let mut search = Self {
+            algorithm,
+            diagonals: false,
+            heuristic_weight: 1.0,
+            heuristic_kind: None,
+            flow_field: false,
+            status: SearchStatus::Running,
+            cells: Grid2D::default(),
+            unvisited: OpenSet::new(algorithm),
+            priorities: Grid2D::default(),
+            parents: Grid2D::default(),
+            waypoints: Vec::new(),
+            path: Vec::new(),
+            reveal: VecDeque::new(),
+            settle_order: Vec::new(),
+            settle_index: Grid2D::default(),
+            start,
+            extra_starts: Vec::new(),
+            origins: Grid2D::default(),
+            current: start,
+            current_dist: 0,
+            goal,
+            reversed: false,
+            backward: None,
+            meeting: None,
+            balance: None,
+            portals: Vec::new(),
+            topology: Topology::Square,
+            layers: (1, 0),
+            lookahead: Grid2D::default(),
+            key_modifier: 0,
+            repaired: None,
+            repairs: 0,
+            deepening: None,
+            sweeps: None,
+            all_pairs: None,
+            farthest: None,
+            peak_open_set: 0,
+            restarts: 0,
+            changes: ChangeLog::default(),
+            search_time: Duration::ZERO,
+        };
+
+        search.clear(map);
+
+        search
+    }
+
+    pub fn status(&self) -> SearchStatus {
+        self.status
+    }
+
+    pub fn start(&self) -> (u32, u32) {
+        self.start
+    }
+
+    pub fn extra_starts(&self) -> &[(u32, u32)] {
+        &self.extra_starts
+    }
+
+    /// Lets the search grow from `starts` at the same time as from the start, each cell
+    /// from the one closest to it, like a nearest facility map. Restarts the search. IDA*
+    /// and Floyd-Warshall only follow paths from the start itself.
+    pub fn set_extra_starts(&mut self, map: &Map, starts: Vec<(u32, u32)>) {
+        assert!(
+            starts.iter().all(|&start| map.in_bounds(start)),
+            "start isn't in bounds"
+        );
+
+        self.extra_starts = starts;
+        self.clear(map);
+    }
+
+    /// Whether `cell` is the start or one of the extra ones
+    pub fn is_start(&self, cell: (u32, u32)) -> bool {
+        cell == self.start || self.extra_starts.contains(&cell)
+    }
+
+    /// All the starts as a region, to measure distances to them by
+    fn starts(&self) -> GoalSpec {
+        if self.extra_starts.is_empty() {
+            GoalSpec::Cell(self.start)
+        } else {
+            GoalSpec::Cells(
+                std::iter::once(self.start)
+                    .chain(self.extra_starts.iter().copied())
+                    .collect(),
+            )
+        }
+    }
+
+    /// Index of the start `cell` was reached from, 0 for the start and the extra ones
+    /// numbered after it. `None` if it wasn't reached or the search doesn't keep track.
+    pub fn origin(&self, cell: (u32, u32)) -> Option<usize> {
+        self.origins.get(cell).copied().flatten()
+    }
+
+    pub fn goal(&self) -> &GoalSpec {
+        &self.goal
+    }
+
+    pub fn diagonals(&self) -> bool {
+        self.diagonals
+    }
+
+    /// Lets grid searches step diagonally too, which restarts the search
+    pub fn set_diagonals(&mut self, map: &Map, diagonals: bool) {
+        self.diagonals = diagonals;
+        self.clear(map);
+    }
+
+    /// Switches to another algorithm, which restarts the search
+    pub fn set_algorithm(&mut self, map: &Map, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+        self.clear(map);
+    }
+
+    pub fn heuristic_weight(&self) -> f64 {
+        self.heuristic_weight
+    }
+
+    /// Multiplies the heuristic by `weight`, which restarts the search
+    pub fn set_heuristic_weight(&mut self, map: &Map, weight: f64) {
+        self.heuristic_weight = weight;
+        self.clear(map);
+    }
+
+    pub fn heuristic_kind(&self) -> Option<Heuristic> {
+        self.heuristic_kind
+    }
+
+    /// Measures the heuristic like `kind`, or with `None` like it fits how the search
+    /// moves. Restarts the search.
+    pub fn set_heuristic_kind(&mut self, map: &Map, kind: Option<Heuristic>) {
+        self.heuristic_kind = kind;
+        self.clear(map);
+    }
+
+    pub fn flow_field(&self) -> bool {
+        self.flow_field
+    }
+
+    /// Keeps searching past the goal until every cell the search can reach has a distance
+    /// from the start, like the flow fields games steer units with. Only searches expanding
+    /// every cell one at a time from the start do, the path to the goal is colored once
+    /// they are done. Restarts the search.
+    pub fn set_flow_field(&mut self, map: &Map, flow_field: bool) {
+        self.flow_field = flow_field;
+        self.clear(map);
+    }
+
+    /// Whether this search builds a flow field
+    fn floods(&self) -> bool {
+        self.flow_field
+            && !self.reversed
+            && matches!(
+                self.algorithm,
+                Algorithm::Dijkstra
+                    | Algorithm::AStar
+                    | Algorithm::Theta
+                    | Algorithm::Bfs
+                    | Algorithm::Dfs
+            )
+    }
+
+    /// Whether found paths are always the shortest possible ones on the grid
+    pub fn finds_shortest(&self) -> bool {
+        self.algorithm.finds_shortest()
+            && self.heuristic_never_overestimates()
+            && self
+                .all_pairs
+                .as_ref()
+                .is_none_or(|all_pairs| all_pairs.scale == 1)
+    }
+
+    /// Whether the heuristic, weighted, never estimates more than a way to the goal costs.
+    /// Those that can settle cells before the shortest way to them was found.
+    fn heuristic_never_overestimates(&self) -> bool {
+        let admissible = self.heuristic_kind.is_none_or(|kind| {
+            kind.admissible(
+                self.diagonals || self.algorithm == Algorithm::Jps,
+                self.algorithm == Algorithm::Theta,
+            )
+        });
+
+        !self.algorithm.uses_heuristic() || self.heuristic_weight <= 1.0 && admissible
+    }
+
+    /// Moves the start, which restarts the search, except for D* Lite, which keeps every
+    /// distance to the goal it found
+    pub fn set_start(&mut self, map: &Map, start: (u32, u32)) {
+        assert!(map.in_bounds(start), "start isn't in bounds");
+
+        if self.algorithm == Algorithm::DStarLite && self.cells.fits(map.width(), map.height()) {
+            let moved = self.heuristic_to(&GoalSpec::Cell(self.start), start) as f64;
+            self.key_modifier += (moved * self.heuristic_weight) as u32;
+            self.start = start;
+            self.reopen(map);
+            return;
+        }
+
+        self.start = start;
+        self.clear(map);
+    }
+
+    /// Changes the goal, which restarts the search. LPA* keeps the distances it already
+    /// knows, they are measured from the start, and only requeues what it still has to
+    /// look at by the new goal.
+    pub fn set_goal(&mut self, map: &Map, goal: GoalSpec) {
+        assert!(!goal.cells(map).is_empty(), "goal isn't in bounds");
+
+        self.goal = goal;
+
+        if self.algorithm == Algorithm::Lpa && self.cells.fits(map.width(), map.height()) {
+            self.reopen(map);
+            self.requeue(map);
+            return;
+        }
+
+        self.clear(map);
+    }
+
+    pub fn current(&self) -> (u32, u32) {
+        self.current
+    }
+
+    /// Distance of the cell last expanded, its g-value
+    pub fn current_dist(&self) -> u32 {
+        self.current_dist
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Distance from start to goal, once the goal has been reached. Only Bellman-Ford's
+    /// can be negative.
+    pub fn path_cost(&self) -> Option<i64> {
+        (self.status == SearchStatus::Found).then(|| {
+            self.signed_dist(self.current)
+                .unwrap_or(self.current_dist as i64)
+        })
+    }
+
+    /// Corners of the path once Theta* or JPS found and revealed one, empty otherwise
+    pub fn waypoints(&self) -> &[(u32, u32)] {
+        match self.status {
+            SearchStatus::Found => &self.waypoints,
+            _ => &[],
+        }
+    }
+
+    /// The found path from start to goal, empty until one is found and revealed
+    pub fn path(&self) -> &[(u32, u32)] {
+        match self.status {
+            SearchStatus::Found => &self.path,
+            _ => &[],
+        }
+    }
+
+    pub fn get_cell(&self, cell: (u32, u32)) -> Option<CellState> {
+        self.cells.get(cell).copied()
+    }
+
+    fn set_cell(&mut self, cell: (u32, u32), state: CellState) {
+        if let Some(c) = self.cells.get_mut(cell) {
+            if *c != state {
+                *c = state;
+                self.changes.push(cell);
+            }
+        }
+    }
+
+    /// Whether anything derived from `cell` has made it into the search. Floyd-Warshall
+    /// takes in the whole map before its first iteration.
+    pub fn has_reached(&self, cell: (u32, u32)) -> bool {
+        self.all_pairs.is_some()
+            || !matches!(self.get_cell(cell), Some(CellState::Unknown) | None)
+            || self
+                .backward
+                .as_ref()
+                .is_some_and(|backward| backward.has_reached(cell))
+    }
+
+    /// Throws away all search progress, so the search starts over from the start cell
+    /// on the next iteration. Also picks up the size of `map` if it changed.
+    ///
+    /// Calling this repeatedly is harmless.
+    pub fn clear(&mut self, map: &Map) {
+        self.portals = map.portals().to_vec();
+        self.topology = map.topology();
+        self.layers = (map.layers(), map.layer_height());
+        self.cells = Grid2D::new(map.width(), map.height(), CellState::Unknown);
+        self.priorities = Grid2D::new(map.width(), map.height(), 0);
+        self.parents = Grid2D::new(map.width(), map.height(), None);
+        self.waypoints.clear();
+        self.path.clear();
+        self.reveal.clear();
+        self.settle_order.clear();
+        self.settle_index = Grid2D::new(map.width(), map.height(), None);
+
+        self.unvisited = OpenSet::new(self.algorithm);
+        self.current = self.start;
+        self.current_dist = 0;
+        self.status = SearchStatus::Running;
+        self.farthest = None;
+        self.peak_open_set = 0;
+        self.search_time = Duration::ZERO;
+        self.restarts += 1;
+        self.changes.forget();
+
+        self.set_cell(self.start, CellState::Unvisited { dist: 0 });
+        self.settle(self.start);
+
+        self.origins = Grid2D::new(map.width(), map.height(), None);
+        self.origins[self.start] = Some(0);
+        let seeds_extra_starts = !self.algorithm.incremental()
+            && !matches!(
+                self.algorithm,
+                Algorithm::Ida | Algorithm::BellmanFord | Algorithm::FloydWarshall
+            );
+        for (i, &start) in self.extra_starts.clone().iter().enumerate() {
+            if map.is_obstacle(start) || start == self.start {
+                continue;
+            }
+
+            self.origins[start] = Some(i + 1);
+            if seeds_extra_starts {
+                self.relax(start, 0);
+            }
+        }
+
+        self.lookahead = Grid2D::default();
+        self.key_modifier = 0;
+        self.repaired = None;
+        if self.algorithm.incremental() {
+            self.begin_incremental(map);
+        }
+
+        self.deepening = (self.algorithm == Algorithm::Ida).then(|| Deepening {
+            stack: vec![(self.start, 0, 0)],
+            bound: self.get_dist(self.start, 0),
+            next_bound: u32::MAX,
+            pass: 0,
+            passes: Grid2D::new(map.width(), map.height(), None),
+            visits: 0,
+        });
+
+        self.all_pairs =
+            (self.algorithm == Algorithm::FloydWarshall).then(|| self.begin_all_pairs(map));
+
+        self.sweeps = (self.algorithm == Algorithm::BellmanFord).then(|| {
+            let mut dist = Grid2D::new(map.width(), map.height(), None);
+            for start in self.starts().cells(map) {
+                if !map.is_obstacle(start) {
+                    dist[start] = Some(0);
+                }
+            }
+
+            Sweeps {
+                dist,
+                count: 0,
+                lowered: 0,
+            }
+        });
+
+        self.meeting = None;
+        self.balance = (self.algorithm == Algorithm::BidirectionalAStar).then(|| {
+            // no heuristic exceeds the one across the whole map, which keeps potentials
+            // from going negative
+            let across = self.heuristic_to(&GoalSpec::Cell((0, 0)), (map.width(), map.height()));
+            (
+                self.goal.clone(),
+                (across as f64 * self.heuristic_weight).ceil() as u32,
+            )
+        });
+        // without a free goal cell the forward half runs alone, into no path
+        self.backward = self
+            .algorithm
+            .bidirectional()
+            .then(|| self.backward_half(map))
+            .flatten();
+
+        // cells are only checked for being a goal once taken off the open set, or followed
+        // to by IDA*, which the start never is. The other algorithms look for the goal
+        // their own way.
+        let checks_reached_cells = !self.algorithm.incremental()
+            && !matches!(
+                self.algorithm,
+                Algorithm::BellmanFord | Algorithm::FloydWarshall
+            );
+        if checks_reached_cells
+            && self.goal.contains(self.start)
+            && !self.reversed
+            && !self.floods()
+        {
+            tracing::debug!("started on the goal");
+            self.path = vec![self.start];
+            self.status = SearchStatus::Revealing;
+        }
+    }
+
+    /// The half of a bidirectional search going back from every free goal cell to the
+    /// start, `None` if every goal cell is an obstacle
+    fn backward_half(&self, map: &Map) -> Option<Box<Search>> {
+        let goals: Vec<_> = self
+            .goal
+            .cells(map)
+            .into_iter()
+            .filter(|&goal| !map.is_obstacle(goal))
+            .collect();
+        let (&first, rest) = goals.split_first()?;
+
+        let algorithm = if self.algorithm.uses_heuristic() {
+            Algorithm::AStar
+        } else {
+            Algorithm::Dijkstra
+        };
+
+        let mut backward = Search::new(map, first, self.starts(), algorithm);
+        backward.reversed = true;
+        backward.diagonals = self.diagonals;
+        backward.heuristic_weight = self.heuristic_weight;
+        backward.heuristic_kind = self.heuristic_kind;
+        backward.balance.clone_from(&self.balance);
+
+        for &goal in rest {
+            backward.relax(goal, 0);
+        }
+
+        Some(Box::new(backward))
+    }
+
+    /// The half of a bidirectional search going back from the goal
+    pub fn backward(&self) -> Option<&Search> {
+        self.backward.as_deref()
+    }
+
+    /// Cell the halves of a bidirectional search met at with the shortest path so far
+    pub fn meeting(&self) -> Option<(u32, u32)> {
+        self.meeting.map(|(cell, _)| cell)
+    }
+
+    /// Distance from the start, or to the goal for the backward half, `None` if `cell`
+    /// hasn't been reached
+    fn dist(&self, cell: (u32, u32)) -> Option<u32> {
+        match self.get_cell(cell)? {
+            CellState::Unvisited { dist } | CellState::Visited { dist } => Some(dist),
+            CellState::Unknown | CellState::OnPath | CellState::Unreachable => None,
+        }
+    }
+
+    fn settle(&mut self, cell: (u32, u32)) {
+        self.settle_index[cell] = Some(self.settle_order.len() as u32);
+        self.settle_order.push(cell);
+    }
+
+    /// Every cell settled so far, in the order it happened
+    pub fn settle_order(&self) -> &[(u32, u32)] {
+        &self.settle_order
+    }
+
+    /// Number of cells settled so far, by both halves of a bidirectional search
+    pub fn expanded(&self) -> usize {
+        self.settle_order.len()
+            + self
+                .deepening
+                .as_ref()
+                .map_or(0, |deepening| deepening.visits)
+            + self
+                .backward
+                .as_ref()
+                .map_or(0, |backward| backward.expanded())
+    }
+
+    /// Most entries the open set had at once since the search last restarted
+    pub fn peak_open_set(&self) -> usize {
+        self.peak_open_set
+    }
+
+    /// How often the search was started over, because the map, its settings or where it
+    /// goes changed, so whatever follows it can tell a new run began
+    pub fn restarts(&self) -> u64 {
+        self.restarts
+    }
+
+    /// Cells whose state or anything else about them a drawing shows changed. Moves of
+    /// [`Self::current`] and [`Self::meeting`] aren't logged, and the backward half of a
+    /// bidirectional search has its own log.
+    pub fn changes(&self) -> &ChangeLog {
+        &self.changes
+    }
+
+    /// Time spent searching since the last restart. Coloring the found path cell by cell
+    /// doesn't count.
+    pub fn search_time(&self) -> Duration {
+        self.search_time
+    }
+
+    /// How many cells were settled before `cell`, `None` if it isn't settled yet
+    pub fn settle_index(&self, cell: (u32, u32)) -> Option<u32> {
+        self.settle_index.get(cell).copied().flatten()
+    }
+
+    /// Cell `cell` was reached from with its best known distance, `None` for the start
+    /// and cells not reached yet. For Theta* this is any cell in line of sight.
+    pub fn parent(&self, cell: (u32, u32)) -> Option<(u32, u32)> {
+        self.parents.get(cell).copied().flatten()
+    }
+
+    /// Also makes `cell` reached from the same start as `parent`
+    fn set_parent(&mut self, cell: (u32, u32), parent: (u32, u32)) {
+        self.parents[cell] = Some(parent);
+
+        let origin = self.origin(parent);
+        if self.origins[cell] != origin {
+            self.origins[cell] = origin;
+            self.changes.push(cell);
+        }
+    }
+
+    /// Distance to the nearest cell of the goal ignoring obstacles and terrain,
+    /// measured like the [`Heuristic`] it was given. Without one, octile for grid
+    /// searches stepping diagonally, euclidean and rounded down otherwise, and the
+    /// number of steps on an empty map between triangles. Only the cells' positions on
+    /// their layers count, stairs keep that. With portals, going through any of them
+    /// counts too if that's shorter, so it never overestimates.
+    pub fn heuristic(&self, cell: (u32, u32)) -> u32 {
+        self.heuristic_to(&self.goal, cell)
+    }
+
+    /// Like [`Self::heuristic`], but to any region
+    fn heuristic_to(&self, goal: &GoalSpec, cell: (u32, u32)) -> u32 {
+        let direct = self.direct_heuristic(goal, cell);
+        if self.portals.is_empty() {
+            return direct;
+        }
+
+        self.portal_bounds(goal)
+            .into_iter()
+            .map(|(end, bound)| {
+                self.direct_heuristic(&GoalSpec::Cell(end), cell)
+                    .saturating_add(bound)
+            })
+            .fold(direct, u32::min)
+    }
+
+    /// Lowest cost from every portal end to `goal` by the heuristic, walking straight
+    /// between portal ends and the goal and taking any of the portals on the way
+    fn portal_bounds(&self, goal: &GoalSpec) -> Vec<((u32, u32), u32)> {
+        let ends: Vec<_> = self
+            .portals
+            .iter()
+            .flat_map(|portal| [portal.a, portal.b])
+            .collect();
+
+        let mut bounds: Vec<_> = ends
+            .iter()
+            .map(|&end| self.direct_heuristic(goal, end))
+            .collect();
+        let mut done = vec![false; ends.len()];
+
+        // dijkstra on the few portal ends, from the goal outwards
+        while let Some(i) = (0..ends.len())
+            .filter(|&i| !done[i])
+            .min_by_key(|&i| bounds[i])
+        {
+            done[i] = true;
+
+            for j in (0..ends.len()).filter(|&j| !done[j]) {
+                let walk = self.direct_heuristic(&GoalSpec::Cell(ends[i]), ends[j]);
+                // ends of the same portal are next to each other in `ends`
+                let through = if i / 2 == j / 2 {
+                    self.portals[i / 2].cost
+                } else {
+                    u32::MAX
+                };
+
+                bounds[j] = bounds[j].min(bounds[i].saturating_add(walk.min(through)));
+            }
+        }
+
+        ends.into_iter().zip(bounds).collect()
+    }
+
+    /// The heuristic ignoring portals. For several goal cells, the lowest heuristic to any
+    /// of them.
+    fn direct_heuristic(&self, goal: &GoalSpec, cell: (u32, u32)) -> u32 {
+        if let GoalSpec::Cells(cells) = goal {
+            return cells
+                .iter()
+                .map(|&goal| self.direct_heuristic(&GoalSpec::Cell(goal), cell))
+                .min()
+                .unwrap_or(0);
+        }
+
+        let (dx, dy) = self.offset(goal, cell);
+
+        if let Some(kind) = self.heuristic_kind {
+            return kind.estimate(dx, dy);
+        }
+
+        if self.topology == Topology::Triangle {
+            // every step crosses at most a row or a column
+            let steps = match *goal {
+                GoalSpec::Cell(goal) if self.layers.0 == 1 => triangle_distance(cell, goal),
+                _ => dx.max(dy),
+            };
+
+            return steps * UNIT_COST;
+        }
+
+        let octile = match self.algorithm {
+            Algorithm::Theta => false,
+            Algorithm::Jps => true,
+            _ => self.diagonals,
+        };
+
+        if octile {
+            Heuristic::Octile.estimate(dx, dy)
+        } else {
+            Heuristic::Euclidean.estimate(dx, dy)
+        }
+    }
+
+    /// How far `cell` is from `goal` along either axis, as if it were on whichever layer
+    /// is closest to the goal
+    fn offset(&self, goal: &GoalSpec, cell: (u32, u32)) -> (u32, u32) {
+        let (layers, layer_height) = self.layers;
+        if layers == 1 {
+            return goal.offset(cell);
+        }
+
+        let row = cell.1 % layer_height;
+
+        (0..layers)
+            .map(|layer| goal.offset((cell.0, layer * layer_height + row)))
+            .min_by_key(|&(_, dy)| dy)
+            .unwrap_or_default()
+    }
+
+    /// Potential of `cell` for a half of a bidirectional A*: half of how much further it
+    /// is from the goal than from the start by the heuristic, flipped for the backward half.
+    /// The halves' potentials add up to the same in every cell, which makes them stop
+    /// as soon as Dijkstra from both ends would. Rounding down still keeps every move
+    /// from lowering the potential by more than it costs, since costs are integers.
+    fn potential(&self, cell: (u32, u32)) -> u32 {
+        let Some((goal, sum)) = self.balance.as_ref().map(|(goal, sum)| (goal, *sum)) else {
+            return 0;
+        };
+
+        let (to_start, to_goal) = if self.reversed {
+            (self.heuristic(cell), self.heuristic_to(goal, cell))
+        } else {
+            (
+                self.heuristic_to(&self.starts(), cell),
+                self.heuristic(cell),
+            )
+        };
+
+        let further = (to_goal as f64 - to_start as f64) * self.heuristic_weight;
+        let forward = ((further + sum as f64) / 2.0)
+            .floor()
+            .clamp(0.0, sum as f64) as u32;
+
+        if self.reversed {
+            sum - forward
+        } else {
+            forward
+        }
+    }
+
+    fn get_dist(&self, cell: (u32, u32), dist: u32) -> u32 {
+        if self.balance.is_some() {
+            dist + self.potential(cell)
+        } else if self.algorithm.uses_heuristic() {
+            dist + (self.heuristic(cell) as f64 * self.heuristic_weight) as u32
+        } else {
+            dist
+        }
+    }
+
+    /// Priority of `cell` in the open set, if it is in there
+    pub fn open_set_priority(&self, cell: (u32, u32)) -> Option<u32> {
+        // the heap may also hold stale entries for the cell, only the last one queued is
+        // still live
+        match self.get_cell(cell) {
+            Some(CellState::Unvisited { .. }) => Some(self.priorities[cell]),
+            _ => None,
+        }
+    }
+
+    /// Puts `entry` into the open set, remembering its priority for its cell
+    fn queue(&mut self, entry: UnvisitedState) {
+        self.priorities[entry.cell] = entry.dist;
+        self.changes.push(entry.cell);
+        self.unvisited.push(entry);
+    }
+
+    /// Number of entries in the open set, stale ones included, of both halves of a
+    /// bidirectional search. IDA* has none, just the path it follows.
+    pub fn open_set_size(&self) -> usize {
+        self.unvisited.len()
+            + self
+                .deepening
+                .as_ref()
+                .map_or(0, |deepening| deepening.stack.len())
+            + self
+                .backward
+                .as_ref()
+                .map_or(0, |backward| backward.open_set_size())
+    }
+
+    /// Queues `cell` with distance `dist`, unless it is already known to be at most as far.
+    /// Returns whether `dist` is the new best distance.
+    fn relax(&mut self, cell: (u32, u32), dist: u32) -> bool {
+        match self.get_cell(cell) {
+            Some(CellState::Unknown) => (),
+            Some(CellState::Unvisited { dist: known })
+                if dist < known && self.algorithm.relaxes() => {}
+            _ => return false,
+        }
+
+        // An old entry stays in the heap and gets skipped once popped
+        self.set_cell(cell, CellState::Unvisited { dist });
+
+        self.queue(UnvisitedState {
+            dist: self.get_dist(cell, dist),
+            actual_dist: dist,
+            cell,
+        });
+
+        true
+    }
+
+    /// Runs the search until it is done, without revealing the path cell by cell
+    pub fn run_to_completion(&mut self, map: &Map) -> SearchStatus {
+        while self.status == SearchStatus::Running {
+            self.iteration(map);
+        }
+
+        self.skip_reveal();
+        self.status
+    }
+
+    /// Colors the next cell of a found path, finishing the search once all of them are
+    fn reveal_step(&mut self) {
+        match self.reveal.pop_front() {
+            Some(cell) => self.set_cell(cell, CellState::OnPath),
+            None => self.status = SearchStatus::Found,
+        }
+    }
+
+    /// Colors all of the found path at once, instead of a cell per iteration
+    pub fn skip_reveal(&mut self) {
+        while self.status == SearchStatus::Revealing {
+            self.reveal_step();
+        }
+    }
+
+    #[tracing::instrument(skip(self, map))]
+    pub fn iteration(&mut self, map: &Map) {
+        if self.status == SearchStatus::Revealing {
+            self.reveal_step();
+            return;
+        }
+
+        if self.status != SearchStatus::Running {
+            return;
+        }
+
+        let started = Instant::now();
+        self.step(map);
+        self.search_time += started.elapsed();
+        self.peak_open_set = self.peak_open_set.max(self.open_set_size());
+    }
+
+    /// Takes a step of a running search, whichever kind it is
+    fn step(&mut self, map: &Map) {
+        if self.bidirectional_step(map) {
+            return;
+        }
+
+        if self.algorithm.incremental() {
+            self.incremental_step(map);
+            return;
+        }
+
+        if self.algorithm == Algorithm::Ida {
+            self.deepening_step(map);
+            return;
+        }
+
+        if self.algorithm == Algorithm::BellmanFord {
+            self.sweep(map);
+            return;
+        }
+
+        if self.algorithm == Algorithm::FloydWarshall {
+            self.all_pairs_step(map);
+            return;
+        }
+
+        let expanded = match self.algorithm {
+            Algorithm::Dijkstra
+            | Algorithm::AStar
+            | Algorithm::Bfs
+            | Algorithm::Dfs
+            | Algorithm::Bidirectional
+            | Algorithm::BidirectionalAStar => self.expand(map),
+            Algorithm::Theta => self.expand_any_angle(map),
+            // jumps follow the square grid's lines, and can't take stairs
+            Algorithm::Jps if self.topology == Topology::Triangle || self.layers.0 > 1 => {
+                self.expand(map)
+            }
+            Algorithm::Jps => self.expand_jumps(map),
+            Algorithm::DStarLite
+            | Algorithm::Lpa
+            | Algorithm::Ida
+            | Algorithm::BellmanFord
+            | Algorithm::FloydWarshall => {
+                unreachable!("{} takes its own steps", self.algorithm)
+            }
+        };
+
+        if !expanded {
+            return;
+        }
+
+        if self.backward.is_some() {
+            self.update_meeting(map, self.current);
+        }
+
+        self.set_cell(
+            self.current,
+            CellState::Visited {
+                dist: self.current_dist,
+            },
+        );
+
+        loop {
+            let Some(cell) = self.unvisited.pop() else {
+                // the backward half running dry only means the forward one has to go on alone
+                if self.reversed {
+                    self.status = SearchStatus::NoPath { trapped: 0 };
+                    return;
+                }
+
+                if self.floods() {
+                    self.finish_flow_field(map);
+                    return;
+                }
+
+                tracing::debug!("no possible path");
+                self.mark_unreachable(map, self.goal.cells(map));
+                return;
+            };
+
+            match self.get_cell(cell.cell) {
+                Some(CellState::Unvisited { dist })
+                    if dist == cell.actual_dist && !map.is_obstacle(cell.cell) =>
+                {
+                    self.current = cell.cell;
+                    self.current_dist = cell.actual_dist;
+                    self.settle(cell.cell);
+                    break;
+                }
+                // stale entry, a shorter distance was found after it was pushed,
+                // or the cell was turned into an obstacle
+                _ => continue,
+            }
+        }
+
+        if self.algorithm == Algorithm::Theta {
+            self.verify_parent(map);
+        }
+
+        if self.floods() {
+            self.farthest = self.farthest.max(Some(self.current_dist));
+        }
+
+        // the backward half is done once it meets the forward one, not at the start
+        if self.goal.contains(self.current) && !self.reversed && !self.floods() {
+            tracing::debug!("we are done");
+            self.color_found_path();
+        }
+    }
+
+    /// Colors the path to the goal cell just reached
+    fn color_found_path(&mut self) {
+        self.status = SearchStatus::Revealing;
+
+        match self.algorithm {
+            Algorithm::Dijkstra
+            | Algorithm::AStar
+            | Algorithm::Bfs
+            | Algorithm::Dfs
+            | Algorithm::Bidirectional
+            | Algorithm::BidirectionalAStar => self.color_path(),
+            Algorithm::Theta | Algorithm::Jps => self.color_waypoint_path(),
+            Algorithm::DStarLite
+            | Algorithm::Lpa
+            | Algorithm::Ida
+            | Algorithm::BellmanFord
+            | Algorithm::FloydWarshall => {
+                unreachable!("{} finds its own paths", self.algorithm)
+            }
+        }
+    }
+
+    /// Ends a flow field once every reachable cell has its distance, coloring the path to
+    /// the closest goal cell if there is one
+    fn finish_flow_field(&mut self, map: &Map) {
+        let closest = self
+            .goal
+            .cells(map)
+            .into_iter()
+            .filter_map(|cell| match self.get_cell(cell)? {
+                CellState::Visited { dist } => Some((cell, dist)),
+                _ => None,
+            })
+            .min_by_key(|&(_, dist)| dist);
+
+        let Some((cell, dist)) = closest else {
+            tracing::debug!("no possible path");
+            self.mark_unreachable(map, self.goal.cells(map));
+            return;
+        };
+
+        tracing::debug!("we are done");
+        self.current = cell;
+        self.current_dist = dist;
+        self.color_found_path();
+    }
+
+    /// Relaxes the 4-connected, or with diagonals 8-connected, neighbors of the current
+    /// cell, returns false if the search had to be restarted instead
+    fn expand(&mut self, map: &Map) -> bool {
+        let neighbors = if self.diagonals {
+            map.get_neighbors8(self.current)
+        } else {
+            map.get_neighbors(self.current)
+        };
+
+        for n in neighbors {
+            if map.is_obstacle(n) {
+                continue;
+            }
+
+            let Some(state) = self.get_cell(n) else {
+                continue;
+            };
+            let dist = self.current_dist
+                + if self.reversed {
+                    map.step_cost(n, self.current)
+                } else {
+                    map.step_cost(self.current, n)
+                };
+
+            match state {
+                CellState::Unknown | CellState::Unvisited { .. } => {
+                    if self.relax(n, dist) {
+                        self.set_parent(n, self.current);
+                    }
+                }
+                CellState::Visited { dist: settled } => {
+                    // the map changed under the search without it being restarted, unless
+                    // the search never promised the shortest way to settled cells
+                    if settled > dist
+                        && self.algorithm.relaxes()
+                        && self.heuristic_never_overestimates()
+                    {
+                        tracing::warn!("{:?} was settled with a wrong distance, restarting", n);
+                        self.clear(map);
+                        return false;
+                    }
+                }
+                // cells only get these states on completion
+                CellState::OnPath | CellState::Unreachable => {
+                    tracing::warn!("{:?} is {:?} while still searching, restarting", n, state);
+                    self.clear(map);
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Advances the backward half of a bidirectional search instead of the forward one
+    /// while it lags behind, or joins the halves once no shorter path can turn up. Returns
+    /// whether it did either, `false` if the forward half is to be advanced.
+    fn bidirectional_step(&mut self, map: &Map) -> bool {
+        let Some(backward) = self.backward.as_ref() else {
+            return false;
+        };
+
+        let forward_priority = self.get_dist(self.current, self.current_dist);
+        let backward_priority = backward.get_dist(backward.current, backward.current_dist);
+
+        // every path not found yet leaves both settled regions, so it is at least this
+        // long, and a half that ran dry has nothing shorter left at all
+        let backward_running = backward.status == SearchStatus::Running;
+        if let Some((cell, dist)) = self.meeting {
+            let potentials = self.balance.as_ref().map_or(0, |&(_, sum)| sum);
+            let bound = if backward_running {
+                (forward_priority as u64 + backward_priority as u64)
+                    .saturating_sub(potentials as u64)
+            } else {
+                u64::MAX
+            };
+
+            if bound >= dist as u64 {
+                self.meet(cell, dist);
+                return true;
+            }
+        }
+
+        if !backward_running || forward_priority <= backward_priority {
+            return false;
+        }
+
+        let backward = self.backward.as_mut().expect("checked above");
+        let cell = backward.current;
+        backward.iteration(map);
+
+        self.update_meeting(map, cell);
+
+        true
+    }
+
+    /// Checks whether the halves of a bidirectional search met on `cell`, just expanded by
+    /// either of them, or on one of the neighbors expanding it reached
+    fn update_meeting(&mut self, map: &Map, cell: (u32, u32)) {
+        let Some(backward) = self.backward.as_ref() else {
+            return;
+        };
+
+        let neighbors = if self.diagonals {
+            map.get_neighbors8(cell)
+        } else {
+            map.get_neighbors(cell)
+        };
+
+        for n in std::iter::once(cell).chain(neighbors) {
+            let (Some(forward_dist), Some(backward_dist)) = (self.dist(n), backward.dist(n)) else {
+                continue;
+            };
+
+            let dist = forward_dist + backward_dist;
+            if self.meeting.is_none_or(|(_, best)| dist < best) {
+                self.meeting = Some((n, dist));
+            }
+        }
+    }
+
+    /// Finishes a bidirectional search by joining the path from the start to `cell` with
+    /// the one from `cell` to the goal
+    fn meet(&mut self, cell: (u32, u32), dist: u32) {
+        tracing::debug!("we are done");
+
+        let mut path = vec![cell];
+        while let Some(parent) = self.parent(*path.last().unwrap()) {
+            path.push(parent);
+        }
+        path.reverse();
+
+        let backward = self
+            .backward
+            .as_ref()
+            .expect("only bidirectional searches meet");
+        let mut cursor = cell;
+        while let Some(parent) = backward.parent(cursor) {
+            path.push(parent);
+            cursor = parent;
+        }
+
+        self.current = cell;
+        self.current_dist = dist;
+        self.reveal = path
+            .iter()
+            .rev()
+            .filter(|&&cell| cell != self.start)
+            .copied()
+            .collect();
+        self.path = path;
+        self.status = SearchStatus::Revealing;
+    }
+
+    /// Jump point search expansion: follows every direction the way could continue in
+    /// from how the current cell was reached, and only queues the cells the jumps end at
+    fn expand_jumps(&mut self, map: &Map) -> bool {
+        let (x, y) = (self.current.0 as i64, self.current.1 as i64);
+
+        let directions = match self.parent(self.current) {
+            None => vec![
+                (1, 0),
+                (-1, 0),
+                (0, 1),
+                (0, -1),
+                (1, 1),
+                (1, -1),
+                (-1, 1),
+                (-1, -1),
+            ],
+            Some(parent) => match (
+                (x - parent.0 as i64).signum(),
+                (y - parent.1 as i64).signum(),
+            ) {
+                (0, dy) => vec![(0, dy), (1, 0), (-1, 0), (1, dy), (-1, dy)],
+                (dx, 0) => vec![(dx, 0), (0, 1), (0, -1), (dx, 1), (dx, -1)],
+                (dx, dy) => vec![(dx, dy), (dx, 0), (0, dy)],
+            },
+        };
+
+        for (dx, dy) in directions {
+            let Some(to) = self.jump(map, self.current, (dx, dy)) else {
+                continue;
+            };
+
+            let cost: u32 = line(self.current, to)
+                .windows(2)
+                .map(|step| map.step_cost(step[0], step[1]))
+                .sum();
+
+            if self.relax(to, self.current_dist + cost) {
+                self.set_parent(to, self.current);
+            }
+        }
+
+        true
+    }
+
+    /// Steps from `from` in `direction` until a cell worth expanding: a goal cell, one next
+    /// to an obstacle the way could turn around, or for diagonal jumps one a straight jump
+    /// from finds something. `None` if an obstacle or the edge of the map comes first.
+    /// Diagonal steps never squeeze past obstacles.
+    fn jump(&self, map: &Map, from: (u32, u32), (dx, dy): (i64, i64)) -> Option<(u32, u32)> {
+        let free = |x: i64, y: i64| x >= 0 && y >= 0 && !map.is_obstacle((x as u32, y as u32));
+        let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+
+        loop {
+            if !free(x + dx, y + dy) || !free(x + dx, y) || !free(x, y + dy) {
+                return None;
+            }
+
+            x += dx;
+            y += dy;
+            let cell = (x as u32, y as u32);
+
+            let turns = match (dx, dy) {
+                (_, 0) => {
+                    free(x, y - 1) && !free(x - dx, y - 1) || free(x, y + 1) && !free(x - dx, y + 1)
+                }
+                (0, _) => {
+                    free(x - 1, y) && !free(x - 1, y - dy) || free(x + 1, y) && !free(x + 1, y - dy)
+                }
+                _ => {
+                    self.jump(map, cell, (dx, 0)).is_some()
+                        || self.jump(map, cell, (0, dy)).is_some()
+                }
+            };
+
+            if turns || self.goal.contains(cell) {
+                return Some(cell);
+            }
+        }
+    }
+
+    /// Whether this is an incremental search going back from the goal to the start, which
+    /// D* Lite does so the start can move
+    pub fn searches_from_goal(&self) -> bool {
+        self.algorithm == Algorithm::DStarLite
+    }
+
+    /// Whether `cell` is one an incremental search grows from
+    fn is_source(&self, cell: (u32, u32)) -> bool {
+        if self.searches_from_goal() {
+            self.goal.contains(cell)
+        } else {
+            self.is_start(cell)
+        }
+    }
+
+    /// Cells an incremental search grows from
+    fn sources(&self, map: &Map) -> Vec<(u32, u32)> {
+        if self.searches_from_goal() {
+            self.goal.cells(map)
+        } else {
+            self.starts().cells(map)
+        }
+    }
+
+    /// Cells an incremental search has to reach one of
+    fn targets(&self, map: &Map) -> Vec<(u32, u32)> {
+        if self.searches_from_goal() {
+            self.starts().cells(map)
+        } else {
+            self.goal.cells(map)
+        }
+    }
+
+    /// Cost of the move an incremental search makes from `from` into the adjacent `to`,
+    /// which going back from the goal is the step from `to` to `from`
+    fn incremental_cost(&self, map: &Map, from: (u32, u32), to: (u32, u32)) -> u32 {
+        if self.searches_from_goal() {
+            map.step_cost(to, from)
+        } else {
+            map.step_cost(from, to)
+        }
+    }
+
+    /// Free cells an incremental search can move between `cell` and
+    fn incremental_neighbors(&self, map: &Map, cell: (u32, u32)) -> Vec<(u32, u32)> {
+        let neighbors = if self.diagonals {
+            map.get_neighbors8(cell)
+        } else {
+            map.get_neighbors(cell)
+        };
+
+        neighbors
+            .into_iter()
+            .filter(|&n| !map.is_obstacle(n))
+            .collect()
+    }
+
+    fn lookahead(&self, cell: (u32, u32)) -> (u32, u32) {
+        self.lookahead[cell]
+    }
+
+    /// Queue entry of `cell` for an incremental search, ordered by the smaller of its
+    /// distances plus the heuristic to the targets first, and the distance alone second
+    fn incremental_key(&self, cell: (u32, u32)) -> UnvisitedState {
+        let (g, rhs) = self.lookahead(cell);
+        let dist = g.min(rhs);
+
+        let heuristic = if self.searches_from_goal() {
+            self.heuristic_to(&self.starts(), cell)
+        } else {
+            self.heuristic(cell)
+        };
+
+        UnvisitedState {
+            dist: dist
+                .saturating_add((heuristic as f64 * self.heuristic_weight) as u32)
+                .saturating_add(self.key_modifier),
+            actual_dist: dist,
+            cell,
+        }
+    }
+
+    /// Starts an incremental search with only its sources knowing their distance
+    fn begin_incremental(&mut self, map: &Map) {
+        self.lookahead = Grid2D::new(map.width(), map.height(), (u32::MAX, u32::MAX));
+        self.set_cell(self.start, CellState::Unknown);
+        self.settle_order.clear();
+        self.settle_index = Grid2D::new(map.width(), map.height(), None);
+
+        for source in self.sources(map) {
+            self.update_vertex(map, source);
+        }
+    }
+
+    /// Recomputes the distance the neighbors of `cell` offer it, and queues it if that
+    /// differs from the one it was last expanded with
+    fn update_vertex(&mut self, map: &Map, cell: (u32, u32)) {
+        let rhs = if map.is_obstacle(cell) {
+            u32::MAX
+        } else if self.is_source(cell) {
+            0
+        } else {
+            self.incremental_neighbors(map, cell)
+                .into_iter()
+                .map(|n| {
+                    self.lookahead(n)
+                        .0
+                        .saturating_add(self.incremental_cost(map, n, cell))
+                })
+                .min()
+                .unwrap_or(u32::MAX)
+        };
+
+        self.lookahead[cell].1 = rhs;
+
+        if self.lookahead(cell).0 != rhs {
+            self.queue(self.incremental_key(cell));
+        }
+
+        self.refresh(cell);
+    }
+
+    /// Shows the state of `cell` in an incremental search: queued while its distances
+    /// disagree, visited once they agree
+    fn refresh(&mut self, cell: (u32, u32)) {
+        let state = match self.lookahead(cell) {
+            (u32::MAX, u32::MAX) => CellState::Unknown,
+            (g, rhs) if g == rhs => CellState::Visited { dist: g },
+            (g, rhs) => CellState::Unvisited { dist: g.min(rhs) },
+        };
+
+        self.set_cell(cell, state);
+    }
+
+    /// Expands the cell with the lowest priority of an incremental search, or finishes it
+    /// once none can lower the distance of a target anymore
+    fn incremental_step(&mut self, map: &Map) {
+        // the order is reversed for the heap, so the closest target is the greatest
+        let target = self
+            .targets(map)
+            .into_iter()
+            .filter(|&target| !map.is_obstacle(target))
+            .map(|target| self.incremental_key(target))
+            .max();
+
+        loop {
+            let Some(entry) = self.unvisited.pop() else {
+                self.finish_incremental(map, target.map(|target| target.cell));
+                return;
+            };
+
+            let (g, rhs) = self.lookahead(entry.cell);
+            let key = self.incremental_key(entry.cell);
+
+            // consistent cells are done, and if the key changed, another entry holds it
+            if g == rhs || (entry.dist, entry.actual_dist) > (key.dist, key.actual_dist) {
+                continue;
+            }
+            // the start moved since it was queued
+            if (entry.dist, entry.actual_dist) < (key.dist, key.actual_dist) {
+                self.queue(key);
+                continue;
+            }
+
+            if let Some(target) = target {
+                let (g, rhs) = self.lookahead(target.cell);
+                if g == rhs && (key.dist, key.actual_dist) >= (target.dist, target.actual_dist) {
+                    self.queue(key);
+                    self.finish_incremental(map, Some(target.cell));
+                    return;
+                }
+            }
+
+            self.current = entry.cell;
+            self.current_dist = rhs.min(g);
+            self.settle(entry.cell);
+            if let Some(repaired) = &mut self.repaired {
+                repaired[entry.cell] = true;
+                self.repairs += 1;
+                self.changes.push(entry.cell);
+            }
+
+            if g > rhs {
+                self.lookahead[entry.cell].0 = rhs;
+                self.refresh(entry.cell);
+            } else {
+                // the cell got further away, everything reached through it has to be redone
+                self.lookahead[entry.cell].0 = u32::MAX;
+                self.update_vertex(map, entry.cell);
+            }
+
+            for n in self.incremental_neighbors(map, entry.cell) {
+                self.update_vertex(map, n);
+            }
+
+            return;
+        }
+    }
+
+    /// Follows the distances from `target` down to a source to reveal the path, or marks
+    /// the start trapped if it wasn't reached
+    fn finish_incremental(&mut self, map: &Map, target: Option<(u32, u32)>) {
+        let Some(target) = target.filter(|&target| self.lookahead(target).0 != u32::MAX) else {
+            tracing::debug!("no possible path");
+            self.mark_unreachable(map, self.targets(map));
+            return;
+        };
+
+        tracing::debug!("we are done");
+
+        let mut path = vec![target];
+        let mut cursor = target;
+
+        while !self.is_source(cursor) {
+            let next = self
+                .incremental_neighbors(map, cursor)
+                .into_iter()
+                .min_by_key(|&n| {
+                    self.lookahead(n)
+                        .0
+                        .saturating_add(self.incremental_cost(map, n, cursor))
+                });
+
+            let Some(next) = next.filter(|&next| self.lookahead(next).0 < self.lookahead(cursor).0)
+            else {
+                tracing::warn!("path broke off at {:?}", cursor);
+                break;
+            };
+
+            path.push(next);
+            cursor = next;
+        }
+
+        // the path has to run from the start to the goal
+        if !self.searches_from_goal() {
+            path.reverse();
+        }
+
+        self.current = *path.last().unwrap();
+        self.current_dist = self.lookahead(target).0;
+        self.reveal = path
+            .iter()
+            .rev()
+            .filter(|&&cell| cell != self.start)
+            .copied()
+            .collect();
+        self.path = path;
+        self.status = SearchStatus::Revealing;
+    }
+
+    /// Adapts an incremental search to the cells in `changed` having changed, instead of
+    /// restarting it: only cells whose distance the change affects get expanded again
+    pub fn repair(&mut self, map: &Map, changed: &[(u32, u32)]) {
+        if !self.cells.fits(map.width(), map.height()) || !self.algorithm.incremental() {
+            self.clear(map);
+            return;
+        }
+
+        self.reopen(map);
+
+        for &(x, y) in changed {
+            // diagonal moves around a changed cell can change too, not just those into it
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let n = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+                    if map.in_bounds(n) {
+                        self.update_vertex(map, n);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Keeps searching an incremental search that was done, taking back its path
+    fn reopen(&mut self, map: &Map) {
+        self.repaired = Some(Grid2D::new(map.width(), map.height(), false));
+        self.repairs = 0;
+        self.changes.forget();
+
+        if self.status == SearchStatus::Running {
+            return;
+        }
+
+        self.path.clear();
+        self.reveal.clear();
+        self.status = SearchStatus::Running;
+
+        for x in 0..map.width() {
+            for y in 0..map.height() {
+                self.refresh((x, y));
+            }
+        }
+    }
+
+    /// Queues every inconsistent cell of an incremental search again, for when the
+    /// heuristic changed and the keys of the queued ones with it
+    fn requeue(&mut self, map: &Map) {
+        self.unvisited = OpenSet::new(self.algorithm);
+
+        for x in 0..map.width() {
+            for y in 0..map.height() {
+                let (g, rhs) = self.lookahead((x, y));
+                if g != rhs {
+                    self.queue(self.incremental_key((x, y)));
+                }
+            }
+        }
+    }
+
+    /// How many cells an incremental search expanded since the map last changed, `None`
+    /// if it never did
+    pub fn repairs(&self) -> Option<usize> {
+        self.repaired.as_ref().map(|_| self.repairs)
+    }
+
+    /// Whether an incremental search expanded `cell` again since the map last changed
+    pub fn repaired(&self, cell: (u32, u32)) -> bool {
+        self.repaired
+            .as_ref()
+            .is_some_and(|repaired| repaired[cell])
+    }
+
+    /// Follows a single cell further in the current IDA* pass, backing up from dead ends
+    /// and cells beyond the bound on the way. Starts the next pass with a higher bound once
+    /// this one ran out of cells.
+    fn deepening_step(&mut self, map: &Map) {
+        // taken out while stepping, so the rest of the search can change alongside
+        let Some(mut deepening) = self.deepening.take() else {
+            return;
+        };
+
+        // it would try every way there is before giving up, and the map may have changed
+        // since the search was cleared
+        if deepening.pass == 0 && deepening.stack == [(self.start, 0, 0)] && !self.reachable(map) {
+            tracing::debug!("no possible path");
+            self.mark_unreachable(map, self.goal.cells(map));
+        } else {
+            self.deepen(map, &mut deepening);
+        }
+
+        self.deepening = Some(deepening);
+    }
+
+    fn deepen(&mut self, map: &Map, deepening: &mut Deepening) {
+        loop {
+            let Some(&mut (cell, dist, ref mut next)) = deepening.stack.last_mut() else {
+                if deepening.next_bound == u32::MAX {
+                    tracing::debug!("no possible path");
+                    self.mark_unreachable(map, self.goal.cells(map));
+                    return;
+                }
+
+                deepening.bound = deepening.next_bound;
+                deepening.next_bound = u32::MAX;
+                deepening.pass += 1;
+                deepening.stack.push((self.start, 0, 0));
+                self.current = self.start;
+                self.current_dist = 0;
+                self.set_cell(self.start, CellState::Unvisited { dist: 0 });
+                return;
+            };
+
+            let neighbors = if self.diagonals {
+                map.get_neighbors8(cell)
+            } else {
+                map.get_neighbors(cell)
+            };
+
+            let Some(&n) = neighbors.get(*next) else {
+                // every way on from here is done, back up
+                deepening.stack.pop();
+                self.set_cell(cell, CellState::Visited { dist });
+                continue;
+            };
+            *next += 1;
+
+            // cells on the path are the ones still queued
+            if map.is_obstacle(n) || matches!(self.get_cell(n), Some(CellState::Unvisited { .. })) {
+                continue;
+            }
+
+            let n_dist = dist + map.step_cost(cell, n);
+            let priority = self.get_dist(n, n_dist);
+            if priority > deepening.bound {
+                deepening.next_bound = deepening.next_bound.min(priority);
+                continue;
+            }
+
+            deepening.stack.push((n, n_dist, 0));
+            deepening.passes[n] = Some(deepening.pass);
+            self.changes.push(n);
+            deepening.visits += 1;
+
+            self.current = n;
+            self.current_dist = n_dist;
+            self.set_cell(n, CellState::Unvisited { dist: n_dist });
+
+            if self.goal.contains(n) {
+                tracing::debug!("we are done");
+
+                self.path = deepening.stack.iter().map(|&(cell, _, _)| cell).collect();
+                self.reveal = self
+                    .path
+                    .iter()
+                    .rev()
+                    .filter(|&&cell| cell != self.start)
+                    .copied()
+                    .collect();
+                self.status = SearchStatus::Revealing;
+            }
+
+            return;
+        }
+    }
+
+    /// Whether the goal can be reached at all, by a quick breadth-first search
+    fn reachable(&self, map: &Map) -> bool {
+        let mut reference = Search::new(map, self.start, self.goal.clone(), Algorithm::Bfs);
+        reference.diagonals = self.diagonals;
+
+        !matches!(
+            reference.run_to_completion(map),
+            SearchStatus::NoPath { .. }
+        )
+    }
+
+    /// The pass IDA* is in and the priority bound it follows cells up to
+    pub fn deepening_bound(&self) -> Option<(u32, u32)> {
+        self.deepening
+            .as_ref()
+            .map(|deepening| (deepening.pass, deepening.bound))
+    }
+
+    /// The IDA* pass that last reached `cell`
+    pub fn pass(&self, cell: (u32, u32)) -> Option<u32> {
+        self.deepening
+            .as_ref()
+            .and_then(|deepening| deepening.passes[cell])
+    }
+
+    /// Bellman-Ford's distance to `cell`, which can be negative. `None` for other searches
+    /// and cells not reached yet.
+    pub fn signed_dist(&self, cell: (u32, u32)) -> Option<i64> {
+        self.sweeps
+            .as_ref()
+            .and_then(|sweeps| sweeps.dist.get(cell)?.as_ref())
+            .copied()
+    }
+
+    /// How many sweeps Bellman-Ford did and how many distances the last one lowered
+    pub fn sweeps(&self) -> Option<(usize, usize)> {
+        self.sweeps
+            .as_ref()
+            .map(|sweeps| (sweeps.count, sweeps.lowered))
+    }
+
+    /// A Bellman-Ford sweep: relaxes the neighbors of every cell reached so far, in
+    /// column order, so distances lowered early in a sweep already spread further in it.
+    /// Done once a sweep lowers nothing. Without a negative cycle that takes at most one
+    /// sweep per free cell, a distance still lowered after that went around one.
+    fn sweep(&mut self, map: &Map) {
+        let Some(mut sweeps) = self.sweeps.take() else {
+            return;
+        };
+
+        let mut lowered = Grid2D::new(map.width(), map.height(), false);
+        let mut last_lowered = None;
+        sweeps.lowered = 0;
+
+        for x in 0..map.width() {
+            for y in 0..map.height() {
+                let Some(dist) = sweeps.dist[(x, y)] else {
+                    continue;
+                };
+                if map.is_obstacle((x, y)) {
+                    continue;
+                }
+
+                let neighbors = if self.diagonals {
+                    map.get_neighbors8((x, y))
+                } else {
+                    map.get_neighbors((x, y))
+                };
+
+                for n in neighbors {
+                    if map.is_obstacle(n) {
+                        continue;
+                    }
+
+                    let n_dist = dist + map.signed_step_cost((x, y), n);
+                    let known = &mut sweeps.dist[n];
+                    if known.is_some_and(|known| known <= n_dist) {
+                        continue;
+                    }
+
+                    *known = Some(n_dist);
+                    self.set_parent(n, (x, y));
+                    if !lowered[n] {
+                        lowered[n] = true;
+                        sweeps.lowered += 1;
+                    }
+                    last_lowered = Some(n);
+                }
+            }
+        }
+
+        sweeps.count += 1;
+
+        // cells lowered in this sweep may lower others in the next, the rest are settled
+        // unless a negative cycle comes along
+        for x in 0..map.width() {
+            for y in 0..map.height() {
+                let Some(dist) = sweeps.dist[(x, y)] else {
+                    continue;
+                };
+                let dist = dist.clamp(0, u32::MAX as i64) as u32;
+
+                self.set_cell(
+                    (x, y),
+                    if lowered[(x, y)] {
+                        CellState::Unvisited { dist }
+                    } else {
+                        CellState::Visited { dist }
+                    },
+                );
+            }
+        }
+
+        let free_cells = map.width() as usize * map.height() as usize - map.obstacle_count();
+
+        match last_lowered {
+            Some(cell) if sweeps.count >= free_cells => {
+                self.sweeps = Some(sweeps);
+                self.mark_negative_cycle(map, cell);
+            }
+            Some(cell) => {
+                self.current = cell;
+                self.sweeps = Some(sweeps);
+            }
+            None => {
+                let target = self
+                    .goal
+                    .cells(map)
+                    .into_iter()
+                    .filter_map(|cell| Some((cell, sweeps.dist[cell]?)))
+                    .min_by_key(|&(_, dist)| dist);
+                self.sweeps = Some(sweeps);
+
+                let Some((target, dist)) = target else {
+                    tracing::debug!("no possible path");
+                    self.mark_unreachable(map, self.goal.cells(map));
+                    return;
+                };
+
+                tracing::debug!("we are done");
+                self.current = target;
+                self.current_dist = dist.clamp(0, u32::MAX as i64) as u32;
+                self.status = SearchStatus::Revealing;
+                self.color_path();
+            }
+        }
+    }
+
+    /// Colors the negative cycle the parents lead from `lowered` into, a cell whose
+    /// distance was still lowered when it should have been settled long ago
+    fn mark_negative_cycle(&mut self, map: &Map, lowered: (u32, u32)) {
+        tracing::debug!("negative cycle");
+
+        // every cell along the way was lowered through the cycle, so going back as many
+        // steps as there can be cells surely ends up on it
+        let mut cursor = lowered;
+        for _ in 0..map.width() * map.height() {
+            match self.parent(cursor) {
+                Some(parent) => cursor = parent,
+                None => break,
+            }
+        }
+
+        let mut cycle = vec![cursor];
+        while let Some(parent) = self.parent(*cycle.last().unwrap()) {
+            if parent == cursor || cycle.contains(&parent) {
+                break;
+            }
+            cycle.push(parent);
+        }
+
+        for &cell in &cycle {
+            self.set_cell(cell, CellState::OnPath);
+        }
+
+        self.current = cursor;
+        self.status = SearchStatus::NegativeCycle {
+            length: cycle.len(),
+        };
+    }
+
+    /// Floyd-Warshall's nodes with the distances between neighboring ones, on blocks just
+    /// large enough to stay below [`MAX_ALL_PAIRS_NODES`]
+    fn begin_all_pairs(&self, map: &Map) -> AllPairs {
+        let mut scale = 1;
+        let (node_at, nodes) = loop {
+            let (w, h) = (map.width().div_ceil(scale), map.height().div_ceil(scale));
+            let mut node_at = Grid2D::new(w, h, None);
+            let mut nodes = Vec::new();
+
+            for x in 0..w {
+                for y in 0..h {
+                    if block_cells(map, scale, (x, y)).next().is_some() {
+                        node_at[(x, y)] = Some(nodes.len());
+                        nodes.push((x, y));
+                    }
+                }
+            }
+
+            if nodes.len() <= MAX_ALL_PAIRS_NODES {
+                break (node_at, nodes);
+            }
+            scale += 1;
+        };
+
+        let n = nodes.len();
+        let mut all_pairs = AllPairs {
+            scale,
+            nodes,
+            node_at,
+            goals: Vec::new(),
+            dist: vec![u32::MAX; n * n],
+            k: 0,
+        };
+
+        for i in 0..n {
+            all_pairs.dist[i * n + i] = 0;
+
+            for (j, cost) in self.node_edges(map, &all_pairs, i) {
+                let dist = &mut all_pairs.dist[i * n + j];
+                *dist = (*dist).min(cost);
+            }
+        }
+
+        let mut goals: Vec<_> = self
+            .goal
+            .cells(map)
+            .into_iter()
+            .filter(|&cell| !map.is_obstacle(cell))
+            .filter_map(|cell| all_pairs.node_at[(cell.0 / scale, cell.1 / scale)])
+            .collect();
+        goals.sort_unstable();
+        goals.dedup();
+        all_pairs.goals = goals;
+
+        all_pairs
+    }
+
+    /// Nodes `node` is connected to directly, with the cost of getting there: those of the
+    /// neighboring blocks, and those of the blocks portals from its block lead to
+    fn node_edges(&self, map: &Map, all_pairs: &AllPairs, node: usize) -> Vec<(usize, u32)> {
+        let scale = all_pairs.scale;
+        let block = all_pairs.nodes[node];
+
+        let mut edges: Vec<_> = self
+            .block_neighbors(all_pairs, block)
+            .into_iter()
+            .filter_map(|(n, to)| Some((n, self.block_step_cost(map, scale, block, to)?)))
+            .collect();
+
+        for portal in map.portals() {
+            for (from, to) in [(portal.a, portal.b), (portal.b, portal.a)] {
+                if (from.0 / scale, from.1 / scale) != block
+                    || map.is_obstacle(from)
+                    || map.is_obstacle(to)
+                {
+                    continue;
+                }
+
+                if let Some(n) = all_pairs.node_at[(to.0 / scale, to.1 / scale)] {
+                    if n != node {
+                        edges.push((n, portal.cost));
+                    }
+                }
+            }
+        }
+
+        edges
+    }
+
+    /// Nodes of the blocks next to `block` with the blocks themselves, diagonal ones too if
+    /// the search steps diagonally
+    fn block_neighbors(&self, all_pairs: &AllPairs, block: (u32, u32)) -> Vec<(usize, (u32, u32))> {
+        let mut neighbors = Vec::with_capacity(8);
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if (dx, dy) == (0, 0) || dx != 0 && dy != 0 && !self.diagonals {
+                    continue;
+                }
+
+                let n = (
+                    block.0.wrapping_add_signed(dx),
+                    block.1.wrapping_add_signed(dy),
+                );
+                if let Some(&Some(node)) = all_pairs.node_at.get(n) {
+                    neighbors.push((node, n));
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Cost of moving between two neighboring blocks: the cheapest step from a free cell of
+    /// one into a free cell of the other, as many times as the blocks are wide. `None` if
+    /// no step crosses between them.
+    fn block_step_cost(
+        &self,
+        map: &Map,
+        scale: u32,
+        from: (u32, u32),
+        to: (u32, u32),
+    ) -> Option<u32> {
+        block_cells(map, scale, from)
+            .flat_map(|cell| {
+                let neighbors = if self.diagonals {
+                    map.get_neighbors8(cell)
+                } else {
+                    map.get_neighbors(cell)
+                };
+
+                neighbors
+                    .into_iter()
+                    .filter(|&n| (n.0 / scale, n.1 / scale) == to && !map.is_obstacle(n))
+                    .map(move |n| map.step_cost(cell, n))
+            })
+            .min()
+            .map(|cost| cost * scale)
+    }
+
+    /// A Floyd-Warshall iteration: lets the paths between every pair of nodes go through
+    /// the next node too, then colors every cell by the distance of its node to the goal.
+    /// Finds the path once every node was let through.
+    fn all_pairs_step(&mut self, map: &Map) {
+        let Some(mut all_pairs) = self.all_pairs.take() else {
+            return;
+        };
+
+        let n = all_pairs.nodes.len();
+        let k = all_pairs.k;
+        let to_goal = |all_pairs: &AllPairs, i: usize| {
+            all_pairs
+                .goals
+                .iter()
+                .map(|&goal| all_pairs.dist[i * n + goal])
+                .min()
+                .unwrap_or(u32::MAX)
+        };
+
+        let before: Vec<_> = (0..n).map(|i| to_goal(&all_pairs, i)).collect();
+        // row k doesn't change while going through k
+        let through: Vec<_> = all_pairs.dist[k * n..][..n].to_vec();
+
+        for i in 0..n {
+            let to_k = all_pairs.dist[i * n + k];
+            if to_k == u32::MAX {
+                continue;
+            }
+
+            for (j, &from_k) in through.iter().enumerate() {
+                let dist = to_k.saturating_add(from_k);
+                if dist < all_pairs.dist[i * n + j] {
+                    all_pairs.dist[i * n + j] = dist;
+                }
+            }
+        }
+
+        for (i, &before) in before.iter().enumerate() {
+            let dist = to_goal(&all_pairs, i);
+            if dist == u32::MAX {
+                continue;
+            }
+
+            self.farthest = self.farthest.max(Some(dist));
+            let state = if dist < before {
+                CellState::Unvisited { dist }
+            } else {
+                CellState::Visited { dist }
+            };
+
+            for cell in block_cells(map, all_pairs.scale, all_pairs.nodes[i]) {
+                self.set_cell(cell, state);
+            }
+        }
+
+        if let Some(cell) = block_cells(map, all_pairs.scale, all_pairs.nodes[k]).next() {
+            self.current = cell;
+            self.settle(cell);
+        }
+        all_pairs.k += 1;
+
+        if all_pairs.k == n {
+            self.finish_all_pairs(map, &all_pairs);
+        }
+
+        self.all_pairs = Some(all_pairs);
+    }
+
+    /// Follows the distances from the start's node down to the closest goal node, coloring
+    /// every cell of the nodes on the way
+    fn finish_all_pairs(&mut self, map: &Map, all_pairs: &AllPairs) {
+        let n = all_pairs.nodes.len();
+        let scale = all_pairs.scale;
+        let start = all_pairs.node_at[(self.start.0 / scale, self.start.1 / scale)]
+            .expect("the start is free");
+
+        let target = all_pairs
+            .goals
+            .iter()
+            .copied()
+            .min_by_key(|&goal| all_pairs.dist[start * n + goal])
+            .filter(|&goal| all_pairs.dist[start * n + goal] != u32::MAX);
+
+        let Some(target) = target else {
+            tracing::debug!("no possible path");
+            self.mark_unreachable(map, self.goal.cells(map));
+            return;
+        };
+
+        tracing::debug!("we are done");
+
+        let to_target = |node: usize| all_pairs.dist[node * n + target];
+        let mut nodes = vec![start];
+        let mut cursor = start;
+
+        while cursor != target {
+            let next = self
+                .node_edges(map, all_pairs, cursor)
+                .into_iter()
+                .find(|&(node, cost)| cost.saturating_add(to_target(node)) == to_target(cursor));
+
+            let Some((next, _)) = next else {
+                tracing::warn!("path broke off at {:?}", all_pairs.nodes[cursor]);
+                break;
+            };
+
+            nodes.push(next);
+            cursor = next;
+        }
+
+        let cells = |node: usize| block_cells(map, scale, all_pairs.nodes[node]);
+        let goal_cell = cells(target)
+            .find(|&cell| self.goal.contains(cell))
+            .expect("goal nodes have a free goal cell");
+
+        // a cell per node, from the start itself to a goal cell
+        let mut path: Vec<_> = nodes
+            .iter()
+            .map(|&node| cells(node).next().expect("nodes have free cells"))
+            .collect();
+        path[0] = self.start;
+        if cursor == target {
+            *path.last_mut().unwrap() = goal_cell;
+        }
+
+        self.reveal = nodes
+            .iter()
+            .rev()
+            .flat_map(|&node| cells(node))
+            .filter(|&cell| cell != self.start)
+            .collect();
+        self.current = *path.last().unwrap();
+        self.current_dist = to_target(start);
+        self.path = path;
+        self.status = SearchStatus::Revealing;
+    }
+
+    /// How many nodes Floyd-Warshall let through so far, out of how many, and the side of
+    /// the blocks of cells its nodes are
+    pub fn all_pairs_progress(&self) -> Option<(usize, usize, u32)> {
+        self.all_pairs
+            .as_ref()
+            .map(|all_pairs| (all_pairs.k, all_pairs.nodes.len(), all_pairs.scale))
+    }
+
+    /// Longest distance any cell has so far, for searches drawn as a distance field:
+    /// flow fields from the start and Floyd-Warshall to the goal
+    pub fn farthest(&self) -> Option<u32> {
+        self.farthest
+    }
+
+    /// Lazy Theta* expansion: every neighbor is assumed to be visible from the current
+    /// cell's parent, which only gets checked once the neighbor is picked, in
+    /// [`Self::verify_parent`].
+    fn expand_any_angle(&mut self, map: &Map) -> bool {
+        let origin = self.parent(self.current).unwrap_or(self.current);
+        let origin_dist = match self.get_cell(origin) {
+            Some(CellState::Visited { dist }) => dist,
+            _ => self.current_dist,
+        };
+
+        for n in map.get_neighbors8(self.current) {
+            // portals lead straight to their other end, nothing is seen through them
+            if map.portal_cost(self.current, n).is_some()
+                && self.relax(n, self.current_dist + map.step_cost(self.current, n))
+            {
+                self.set_parent(n, self.current);
+            }
+
+            // also stops diagonal steps from squeezing between two obstacles, unless
+            // corner cutting is allowed
+            if !map.line_of_sight(self.current, n) {
+                continue;
+            }
+
+            if self.relax(n, origin_dist + map.segment_cost(origin, n)) {
+                self.set_parent(n, origin);
+            }
+        }
+
+        true
+    }
+
+    /// If the current cell's parent turns out not to be visible, falls back to the best
+    /// visited neighbor as the parent, or one a portal leads from
+    fn verify_parent(&mut self, map: &Map) {
+        let Some(parent) = self.parent(self.current) else {
+            return;
+        };
+
+        if map.line_of_sight(parent, self.current) {
+            return;
+        }
+
+        let best = map
+            .get_neighbors8(self.current)
+            .into_iter()
+            .filter_map(|n| {
+                let Some(CellState::Visited { dist }) = self.get_cell(n) else {
+                    return None;
+                };
+
+                let through_portal = map
+                    .portal_cost(n, self.current)
+                    .map(|_| map.step_cost(n, self.current));
+                let in_sight = map
+                    .line_of_sight(n, self.current)
+                    .then(|| map.segment_cost(n, self.current));
+
+                let cost = match (through_portal, in_sight) {
+                    (Some(a), Some(b)) => a.min(b),
+                    (cost, None) | (None, cost) => cost?,
+                };
+
+                Some((n, dist + cost))
+            })
+            .min_by_key(|(_, dist)| *dist);
+
+        if let Some((parent, dist)) = best {
+            self.set_parent(self.current, parent);
+            self.current_dist = dist;
+            self.set_cell(self.current, CellState::Unvisited { dist });
+        }
+    }
+
+    /// Marks the regions the `trapped` cells, usually the goal, are in, which is everything
+    /// connected to them the search never reached, since every reachable cell is visited
+    /// by now.
+    fn mark_unreachable(&mut self, map: &Map, trapped_cells: Vec<(u32, u32)>) {
+        let mut trapped = 0;
+
+        for goal in trapped_cells {
+            // cells flooded from an earlier goal cell are already marked
+            if map.is_obstacle(goal) || self.get_cell(goal) != Some(CellState::Unknown) {
+                continue;
+            }
+
+            let region = map.flood(goal, |n| {
+                !map.is_obstacle(n) && self.get_cell(n) == Some(CellState::Unknown)
+            });
+
+            for &cell in &region {
+                self.set_cell(cell, CellState::Unreachable);
+            }
+
+            trapped += region.len();
+        }
+
+        self.status = SearchStatus::NoPath { trapped };
+    }
+
+    /// Follows the parents back from the goal to the start it was reached from
+    fn color_path(&mut self) {
+        let mut cursor = self.current;
+        let mut path = vec![cursor];
+
+        while !self.is_start(cursor) {
+            self.reveal.push_back(cursor);
+
+            let Some(parent) = self.parent(cursor) else {
+                tracing::warn!("path broke off at {:?}", cursor);
+                return;
+            };
+
+            cursor = parent;
+            path.push(cursor);
+        }
+
+        path.reverse();
+        self.path = path;
+    }
+
+    /// Follows the parents back from the goal, coloring every cell the straight
+    /// segments between them cross. Theta* paths only keep the waypoints, JPS ones every
+    /// cell, since its segments never leave the grid's 8 directions.
+    fn color_waypoint_path(&mut self) {
+        let mut waypoints = vec![self.current];
+
+        while let Some(parent) = self.parent(*waypoints.last().unwrap()) {
+            waypoints.push(parent);
+        }
+
+        waypoints.reverse();
+
+        for segment in waypoints.windows(2).rev() {
+            // nothing is crossed going through a portal
+            let cells = if self
+                .portals
+                .iter()
+                .any(|p| p.exit(segment[0]) == Some(segment[1]))
+            {
+                segment.to_vec()
+            } else {
+                line(segment[0], segment[1])
+            };
+
+            for cell in cells.into_iter().rev() {
+                // segments share their ends
+                if !self.is_start(cell) && self.reveal.back() != Some(&cell) {
+                    self.reveal.push_back(cell);
+                }
+            }
+        }
+
+        if self.algorithm == Algorithm::Jps {
+            self.path = self.reveal.iter().rev().copied().collect();
+            self.path.insert(0, waypoints[0]);
+        } else {
+            self.path.clone_from(&waypoints);
+        }
+        self.waypoints = waypoints;
+    }
+}
+
+/// The free cells of the `scale` wide square block at `block`, by column
+fn block_cells(map: &Map, scale: u32, block: (u32, u32)) -> impl Iterator<Item = (u32, u32)> + '_ {
+    let (x, y) = (block.0 * scale, block.1 * scale);
+
+    (x..(x + scale).min(map.width()))
+        .flat_map(move |x| (y..(y + scale).min(map.height())).map(move |y| (x, y)))
+        .filter(|&cell| !map.is_obstacle(cell))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map::Material, Grid};
+
+    /// Searched from the top left to the bottom right, around a wall down from the top
+    /// if `walled`
+    fn searched(algorithm: Algorithm, walled: bool) -> Grid {
+        let mut grid = Grid::new(30, 20, (2, 3), GoalSpec::Cell((27, 16)), algorithm);
+        if walled {
+            grid.draw_obstacle((15, 0), (15, 14));
+        }
+
+        assert_eq!(grid.run_to_completion(), SearchStatus::Found);
+        grid
+    }
+
+    fn path_cost(algorithm: Algorithm, walled: bool) -> i64 {
+        searched(algorithm, walled).path_cost().unwrap()
+    }
+
+    #[test]
+    fn theta_star_is_shorter_than_four_connected_dijkstra_on_an_open_map() {
+        let dijkstra = path_cost(Algorithm::Dijkstra, false);
+        let theta = path_cost(Algorithm::Theta, false);
+
+        // 25 across and 13 down, once in steps and once in a straight line
+        assert_eq!(dijkstra, 38 * UNIT_COST as i64);
+        assert!(theta < dijkstra, "{} not shorter than {}", theta, dijkstra);
+        assert!((theta - 25f64.hypot(13.0) as i64 * UNIT_COST as i64).abs() <= UNIT_COST as i64);
+    }
+
+    #[test]
+    fn theta_star_path_keeps_line_of_sight_around_walls() {
+        let dijkstra = path_cost(Algorithm::Dijkstra, true);
+        let theta = path_cost(Algorithm::Theta, true);
+        assert!(theta < dijkstra, "{} not shorter than {}", theta, dijkstra);
+
+        let grid = searched(Algorithm::Theta, true);
+        let waypoints = grid.searches()[0].waypoints();
+        assert!(
+            waypoints.len() >= 3,
+            "straight through the wall: {:?}",
+            waypoints
+        );
+        for pair in waypoints.windows(2) {
+            assert!(line(pair[0], pair[1])
+                .iter()
+                .all(|&cell| !grid.map().is_obstacle(cell)));
+        }
+    }
+
+    /// Every cell of `search` and everything about it a drawing of it shows
+    fn drawn_state(
+        search: &Search,
+        map: &Map,
+    ) -> Vec<((u32, u32), impl PartialEq + std::fmt::Debug)> {
+        (0..map.width())
+            .flat_map(|x| (0..map.height()).map(move |y| (x, y)))
+            .map(|cell| {
+                let state = (
+                    search.get_cell(cell),
+                    search.open_set_priority(cell),
+                    search.origin(cell),
+                    search.pass(cell),
+                    search.repaired(cell),
+                );
+                (cell, state)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_changed_cell_is_logged() {
+        use clap::ValueEnum;
+
+        for &algorithm in Algorithm::value_variants() {
+            let mut grid = Grid::new(12, 8, (1, 1), GoalSpec::Cell((10, 6)), algorithm);
+            grid.set_extra_starts(vec![(1, 6)]);
+
+            for step in 0..400 {
+                // a wall halfway through, for incremental searches to repair around
+                if step == 40 {
+                    grid.draw_obstacle((5, 0), (5, 5));
+                }
+
+                let search = &grid.searches()[0];
+                let before = drawn_state(search, grid.map());
+                let mark = search.changes().mark();
+
+                grid.dijkstra_iteration();
+
+                let search = &grid.searches()[0];
+                // a log that forgot makes everything be drawn again
+                let Some(logged) = search.changes().since(mark) else {
+                    continue;
+                };
+
+                for ((cell, before), (_, after)) in
+                    before.iter().zip(drawn_state(search, grid.map()))
+                {
+                    assert!(
+                        *before == after || logged.contains(cell),
+                        "{:?} didn't log {:?} becoming {:?} in step {}",
+                        algorithm,
+                        cell,
+                        after,
+                        step
+                    );
+                }
+            }
+        }
+    }
+
+    /// Runs `algorithm` on a 7x7 map from (6, 4) to `goal`, across a wall down the middle
+    /// with a gap at the bottom, and with `walls` on top
+    fn across_wall(algorithm: Algorithm, goal: GoalSpec, walls: &[(u32, u32)]) -> Grid {
+        let mut grid = Grid::new(7, 7, (6, 4), goal, algorithm);
+        grid.draw_obstacle((3, 0), (3, 5));
+        grid.paint_cells(walls, Material::Obstacle);
+        grid.run_to_completion();
+        grid
+    }
+
+    /// Checks `algorithm` finds as short a path as Dijkstra to goals that start with a
+    /// wall, and none when every goal cell is one
+    fn reaches_free_goal_cells(algorithm: Algorithm) {
+        let goals = [
+            GoalSpec::Cells(vec![(4, 1), (0, 4)]),
+            GoalSpec::Rect {
+                x: 0,
+                y: 0,
+                w: 2,
+                h: 2,
+            },
+        ];
+
+        for goal in goals {
+            let walls = [goal.cells(&Map::new(7, 7))[0]];
+            let grid = across_wall(algorithm, goal.clone(), &walls);
+            let dijkstra = across_wall(Algorithm::Dijkstra, goal.clone(), &walls);
+
+            assert_eq!(grid.status(), SearchStatus::Found, "{:?}", goal);
+            assert_eq!(grid.path_cost(), dijkstra.path_cost(), "{:?}", goal);
+            assert_eq!(grid.verify(), Ok(()), "{:?}", goal);
+        }
+
+        let walled = across_wall(algorithm, GoalSpec::Cells(vec![(4, 1)]), &[(4, 1)]);
+        assert!(matches!(walled.status(), SearchStatus::NoPath { .. }));
+    }
+
+    #[test]
+    fn bidirectional_dijkstra_reaches_free_goal_cells() {
+        reaches_free_goal_cells(Algorithm::Bidirectional);
+    }
+
+    #[test]
+    fn bidirectional_a_star_reaches_free_goal_cells() {
+        reaches_free_goal_cells(Algorithm::BidirectionalAStar);
+    }
+
+    #[test]
+    fn bidirectional_searches_cost_the_same_as_dijkstra() {
+        let dijkstra = path_cost(Algorithm::Dijkstra, true);
+
+        for algorithm in [Algorithm::Bidirectional, Algorithm::BidirectionalAStar] {
+            let grid = searched(algorithm, true);
+
+            assert_eq!(grid.path_cost(), Some(dijkstra), "{:?}", algorithm);
+            assert_eq!(grid.verify(), Ok(()), "{:?}", algorithm);
+            assert!(grid.searches()[0].meeting().is_some(), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn theta_star_paths_verify_on_diagonal_and_terrain_maps() {
+        let mut diagonal = Grid::new(10, 10, (0, 0), GoalSpec::Cell((9, 9)), Algorithm::Theta);
+        diagonal.set_diagonals(true);
+        diagonal.run_to_completion();
+        assert_eq!(diagonal.verify(), Ok(()));
+
+        // a straight line through expensive terrain costs more than going around it
+        let mut terrain = Grid::new(10, 10, (0, 0), GoalSpec::Cell((9, 9)), Algorithm::Theta);
+        terrain.set_diagonals(true);
+        terrain.paint_cells(&[(4, 4), (5, 5), (3, 4), (4, 3)], Material::Cost(5));
+        terrain.run_to_completion();
+        assert_eq!(terrain.verify(), Ok(()));
+    }
+
+    #[test]
+    fn starting_inside_the_goal_costs_nothing() {
+        use clap::ValueEnum;
+
+        for &algorithm in Algorithm::value_variants() {
+            let goal = GoalSpec::Cells(vec![(2, 1), (2, 8)]);
+            let mut grid = Grid::new(5, 10, (2, 8), goal, algorithm);
+            // the other goal cell can't be reached at all
+            grid.draw_obstacle((0, 4), (4, 4));
+            grid.run_to_completion();
+
+            assert_eq!(grid.status(), SearchStatus::Found, "{:?}", algorithm);
+            assert_eq!(grid.path_cost(), Some(0), "{:?}", algorithm);
+            assert_eq!(grid.verify(), Ok(()), "{:?}", algorithm);
+        }
+    }
+
+    /// 30x20 from (2, 3) to `goal` around a wall down from the top, with costly terrain
+    /// near the goal and `portals` on top
+    fn obstructed(algorithm: Algorithm, goal: GoalSpec, portals: &[Portal]) -> Grid {
+        let mut grid = Grid::new(30, 20, (2, 3), goal, algorithm);
+        grid.draw_obstacle((15, 0), (15, 14));
+        grid.paint_cells(
+            &(18..24)
+                .flat_map(|x| (9..20).map(move |y| (x, y)))
+                .collect::<Vec<_>>(),
+            Material::Cost(4),
+        );
+        for &portal in portals {
+            assert!(grid.add_portal(portal));
+        }
+        grid
+    }
+
+    /// Checks `algorithm` finds as short a path as Dijkstra on the map `setup` makes, with
+    /// and without diagonal steps
+    fn costs_the_same_as_dijkstra(algorithm: Algorithm, setup: fn(Algorithm) -> Grid) {
+        for diagonals in [false, true] {
+            let run = |algorithm| {
+                let mut grid = setup(algorithm);
+                grid.set_diagonals(diagonals);
+                grid.run_to_completion();
+                grid
+            };
+            let (grid, dijkstra) = (run(algorithm), run(Algorithm::Dijkstra));
+
+            assert_eq!(
+                grid.status(),
+                SearchStatus::Found,
+                "diagonals {}",
+                diagonals
+            );
+            assert_eq!(
+                grid.path_cost(),
+                dijkstra.path_cost(),
+                "diagonals {}",
+                diagonals
+            );
+            assert_eq!(grid.verify(), Ok(()), "diagonals {}", diagonals);
+        }
+    }
+
+    /// [`obstructed`] to a single goal cell
+    fn obstructed_cell(algorithm: Algorithm) -> Grid {
+        obstructed(algorithm, GoalSpec::Cell((27, 16)), &[])
+    }
+
+    /// Walls off the way around the bottom of the wall `edited` runs into, once after `steps`
+    /// iterations and once more after it found a path, and checks `algorithm` repairs its
+    /// search to as short a path as Dijkstra finds on the edited map from scratch
+    fn repairs_after_edits(algorithm: Algorithm, goal: GoalSpec, portals: &[Portal]) {
+        let edits = [[(12, 16), (12, 19)], [(17, 8), (17, 17)]];
+
+        let mut grid = obstructed(algorithm, goal.clone(), portals);
+        for _ in 0..60 {
+            grid.dijkstra_iteration();
+        }
+        grid.draw_obstacle(edits[0][0], edits[0][1]);
+        assert_eq!(grid.run_to_completion(), SearchStatus::Found);
+        grid.draw_obstacle(edits[1][0], edits[1][1]);
+        assert_eq!(grid.run_to_completion(), SearchStatus::Found);
+
+        let mut dijkstra = obstructed(Algorithm::Dijkstra, goal, portals);
+        for [from, to] in edits {
+            dijkstra.draw_obstacle(from, to);
+        }
+        dijkstra.run_to_completion();
+
+        assert_eq!(grid.path_cost(), dijkstra.path_cost());
+        assert_eq!(grid.verify(), Ok(()));
+        // repaired, not started over
+        assert!(grid.searches()[0]
+            .repairs()
+            .is_some_and(|repairs| repairs > 0));
+    }
+
+    /// A goal region and the cheapest portal there is, from behind the start to the
+    /// other side of the wall
+    fn region_and_portal() -> (GoalSpec, [Portal; 1]) {
+        let goal = GoalSpec::Rect {
+            x: 25,
+            y: 14,
+            w: 4,
+            h: 4,
+        };
+        let portal = Portal {
+            a: (1, 1),
+            b: (16, 12),
+            cost: 1,
+        };
+
+        (goal, [portal])
+    }
+
+    #[test]
+    fn d_star_lite_costs_the_same_as_dijkstra() {
+        costs_the_same_as_dijkstra(Algorithm::DStarLite, obstructed_cell);
+    }
+
+    #[test]
+    fn d_star_lite_repairs_after_edits() {
+        repairs_after_edits(Algorithm::DStarLite, GoalSpec::Cell((27, 16)), &[]);
+
+        let (goal, portals) = region_and_portal();
+        repairs_after_edits(Algorithm::DStarLite, goal.clone(), &[]);
+        repairs_after_edits(Algorithm::DStarLite, goal, &portals);
+    }
+
+    #[test]
+    fn lpa_star_costs_the_same_as_dijkstra() {
+        costs_the_same_as_dijkstra(Algorithm::Lpa, obstructed_cell);
+    }
+
+    #[test]
+    fn lpa_star_repairs_after_edits() {
+        repairs_after_edits(Algorithm::Lpa, GoalSpec::Cell((27, 16)), &[]);
+
+        let (goal, portals) = region_and_portal();
+        repairs_after_edits(Algorithm::Lpa, goal.clone(), &[]);
+        repairs_after_edits(Algorithm::Lpa, goal, &portals);
+    }
+
+    #[test]
+    fn ida_star_costs_the_same_as_dijkstra() {
+        // every pass starts over, so only a small map finishes in time
+        costs_the_same_as_dijkstra(Algorithm::Ida, |algorithm| {
+            let mut grid = Grid::new(9, 7, (0, 3), GoalSpec::Cell((8, 3)), algorithm);
+            grid.draw_obstacle((4, 0), (4, 4));
+            grid.paint_cells(&[(6, 2), (6, 3), (7, 3), (6, 4)], Material::Cost(3));
+            grid
+        });
+    }
+
+    #[test]
+    fn bellman_ford_costs_the_same_as_dijkstra() {
+        costs_the_same_as_dijkstra(Algorithm::BellmanFord, obstructed_cell);
+    }
+
+    #[test]
+    fn bellman_ford_gains_a_step_on_negative_terrain() {
+        let mut grid = Grid::new(7, 1, (0, 0), GoalSpec::Cell((6, 0)), Algorithm::BellmanFord);
+        grid.paint((3, 0), Material::Negative);
+
+        assert_eq!(grid.run_to_completion(), SearchStatus::Found);
+        assert_eq!(grid.path_cost(), Some(4 * UNIT_COST as i64));
+        assert_eq!(grid.verify(), Ok(()));
+
+        // stepping back and forth between the two gains forever
+        grid.paint((4, 0), Material::Negative);
+        assert!(matches!(
+            grid.run_to_completion(),
+            SearchStatus::NegativeCycle { .. }
+        ));
+    }
+
+    #[test]
+    fn floyd_warshall_costs_the_same_as_dijkstra() {
+        costs_the_same_as_dijkstra(Algorithm::FloydWarshall, obstructed_cell);
+        costs_the_same_as_dijkstra(Algorithm::FloydWarshall, |algorithm| {
+            obstructed(algorithm, region_and_portal().0, &[])
+        });
+    }
+
+    #[test]
+    fn jps_costs_the_same_as_dijkstra() {
+        // two walls touching at a corner, only corner cutting slips between them
+        let walls = |algorithm, corner_cutting| {
+            let mut grid = Grid::new(30, 20, (2, 3), GoalSpec::Cell((27, 3)), algorithm);
+            grid.draw_obstacle((15, 0), (15, 14));
+            grid.draw_obstacle((16, 15), (16, 17));
+            grid.set_diagonals(true).set_corner_cutting(corner_cutting);
+            grid.run_to_completion();
+            grid
+        };
+
+        // JPS never cuts corners, whatever the map allows
+        let dijkstra = walls(Algorithm::Dijkstra, false);
+        assert!(walls(Algorithm::Dijkstra, true).path_cost() < dijkstra.path_cost());
+
+        for corner_cutting in [false, true] {
+            let grid = walls(Algorithm::Jps, corner_cutting);
+
+            assert_eq!(grid.status(), SearchStatus::Found, "{}", corner_cutting);
+            assert_eq!(grid.path_cost(), dijkstra.path_cost(), "{}", corner_cutting);
+            assert_eq!(grid.verify(), Ok(()), "{}", corner_cutting);
+        }
+    }
+
+    #[test]
+    fn bfs_costs_the_same_as_dijkstra_on_uniform_four_connected_maps() {
+        let bfs = searched(Algorithm::Bfs, true);
+
+        assert_eq!(bfs.path_cost(), Some(path_cost(Algorithm::Dijkstra, true)));
+        assert_eq!(bfs.verify(), Ok(()));
+    }
+
+    #[test]
+    fn dfs_paths_are_valid_if_not_the_shortest() {
+        for diagonals in [false, true] {
+            let mut grid = obstructed_cell(Algorithm::Dfs);
+            grid.set_diagonals(diagonals);
+
+            assert_eq!(grid.run_to_completion(), SearchStatus::Found);
+            // every step checks out, only the cost may be off
+            match grid.verify() {
+                Ok(()) => {}
+                Err(e) => assert!(e.starts_with("path costs"), "{}", e),
+            }
+            assert!(grid.path_cost().unwrap() >= grid.shortest_cost().unwrap());
+        }
+    }
+
+    #[test]
+    fn weighted_a_star_stays_within_its_weight_of_dijkstra() {
+        for diagonals in [false, true] {
+            let mut dijkstra = obstructed_cell(Algorithm::Dijkstra);
+            dijkstra.set_diagonals(diagonals);
+            dijkstra.run_to_completion();
+            let optimal = dijkstra.path_cost().unwrap();
+
+            for weight in [1.0, 1.5, 2.0, 5.0] {
+                let mut grid = obstructed_cell(Algorithm::AStar);
+                grid.set_diagonals(diagonals).set_heuristic_weight(weight);
+
+                assert_eq!(grid.run_to_completion(), SearchStatus::Found);
+                let cost = grid.path_cost().unwrap();
+                assert!(
+                    cost as f64 <= optimal as f64 * weight,
+                    "weight {} costs {}, optimal {}, diagonals {}",
+                    weight,
+                    cost,
+                    optimal,
+                    diagonals
+                );
+            }
+        }
+    }
+}
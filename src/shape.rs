@@ -0,0 +1,87 @@
+use crate::map::line;
+
+/// A shape spanned by the cell a drag started at and the one it ended at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shape {
+    /// Straight line between both cells
+    Line,
+    /// Rectangle with both cells as opposite corners
+    Rectangle { filled: bool },
+    /// Circle around the first cell, through the second one
+    Circle { filled: bool },
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hollow = |filled| if filled { "" } else { "Hollow " };
+
+        match *self {
+            Shape::Line => write!(f, "Line"),
+            Shape::Rectangle { filled } => write!(f, "{}Rectangle", hollow(filled)),
+            Shape::Circle { filled } => write!(f, "{}Circle", hollow(filled)),
+        }
+    }
+}
+
+impl Shape {
+    /// The same shape filled in, or only its outline. Lines stay lines.
+    pub fn with_filled(self, filled: bool) -> Self {
+        match self {
+            Shape::Line => Shape::Line,
+            Shape::Rectangle { .. } => Shape::Rectangle { filled },
+            Shape::Circle { .. } => Shape::Circle { filled },
+        }
+    }
+
+    /// Every cell of the shape spanned by `from` and `to`. Cells left of or above the
+    /// first row and column are left out, the rest may still be off the map.
+    pub fn cells(self, from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+        match self {
+            Shape::Line => line(from, to),
+            Shape::Rectangle { filled } => rectangle(from, to, filled),
+            Shape::Circle { filled } => circle(from, to, filled),
+        }
+    }
+}
+
+fn rectangle(from: (u32, u32), to: (u32, u32), filled: bool) -> Vec<(u32, u32)> {
+    let (left, right) = (from.0.min(to.0), from.0.max(to.0));
+    let (top, bottom) = (from.1.min(to.1), from.1.max(to.1));
+
+    (left..=right)
+        .flat_map(|x| (top..=bottom).map(move |y| (x, y)))
+        .filter(|&(x, y)| filled || x == left || x == right || y == top || y == bottom)
+        .collect()
+}
+
+fn circle(center: (u32, u32), through: (u32, u32), filled: bool) -> Vec<(u32, u32)> {
+    let (dx, dy) = (
+        through.0 as f64 - center.0 as f64,
+        through.1 as f64 - center.1 as f64,
+    );
+    let radius = dx.hypot(dy).round() as i64;
+
+    // the extra radius rounds off the points sticking out at the four ends
+    let inside = |dx: i64, dy: i64| dx * dx + dy * dy <= radius * radius + radius;
+
+    let mut cells = Vec::new();
+    for dx in -radius..=radius {
+        for dy in -radius..=radius {
+            if !inside(dx, dy) {
+                continue;
+            }
+
+            // the outline is what has a neighbor outside
+            let edge = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+                .iter()
+                .any(|&(nx, ny)| !inside(dx + nx, dy + ny));
+
+            let (x, y) = (center.0 as i64 + dx, center.1 as i64 + dy);
+            if (filled || edge) && x >= 0 && y >= 0 {
+                cells.push((x as u32, y as u32));
+            }
+        }
+    }
+
+    cells
+}
@@ -0,0 +1,143 @@
+use crate::{search::CellState, Grid};
+
+/// What a cell shows, whatever draws it: the window colors it, `--ascii` picks a
+/// character for it. Agents are numbered like [`Grid::searches`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Shown {
+    Obstacle,
+    /// The agent in the fog
+    Walker,
+    /// A wall the agent in the fog hasn't sensed yet
+    UnsensedObstacle,
+    Start(usize),
+    Goal(usize),
+    /// Where both halves of a bidirectional search met
+    Meeting(usize),
+    /// Where the agent in the fog walked
+    Walked,
+    Path(usize),
+    /// The cell expanded last
+    Current(usize),
+    Queued(usize),
+    /// Settled again by an incremental search after the map changed
+    Repaired(usize),
+    Visited {
+        agent: usize,
+        dist: u32,
+    },
+    /// Cut off from the start, once there turned out to be no path
+    Unreachable,
+    BackwardQueued(usize),
+    BackwardVisited(usize),
+    /// Nothing reached it, only its terrain shows
+    Terrain,
+}
+
+impl Shown {
+    /// Which of the things several agents say about a cell wins, the highest one
+    fn rank(self) -> u8 {
+        match self {
+            Shown::Start(_) => 6,
+            Shown::Goal(_) => 5,
+            Shown::Meeting(_) | Shown::Walked | Shown::Path(_) => 4,
+            Shown::Current(_) => 3,
+            Shown::Queued(_) | Shown::BackwardQueued(_) => 2,
+            Shown::Repaired(_) | Shown::Visited { .. } | Shown::BackwardVisited(_) => 1,
+            Shown::Unreachable | Shown::Terrain => 0,
+            Shown::Obstacle | Shown::Walker | Shown::UnsensedObstacle => u8::MAX,
+        }
+    }
+}
+
+/// What `cell` of `grid` shows. Obstacles and the agent in the fog hide everything else,
+/// otherwise the agent saying the most about the cell wins, the first one on a tie.
+pub fn shown(grid: &Grid, cell: (u32, u32)) -> Shown {
+    if grid.map().is_obstacle(cell) {
+        return Shown::Obstacle;
+    }
+
+    if let Some(fog) = grid.fog() {
+        if cell == fog.position() {
+            return Shown::Walker;
+        } else if fog.world().is_obstacle(cell) {
+            return Shown::UnsensedObstacle;
+        }
+    }
+
+    let mut best = grid
+        .fog()
+        .filter(|fog| fog.walked(cell))
+        .map(|_| Shown::Walked);
+
+    for (agent, search) in grid.searches().iter().enumerate() {
+        let candidate = if search.is_start(cell) {
+            Shown::Start(agent)
+        } else if search.goal().contains(cell) {
+            Shown::Goal(agent)
+        } else if search.meeting() == Some(cell) {
+            Shown::Meeting(agent)
+        } else {
+            match search.get_cell(cell) {
+                Some(CellState::OnPath) => Shown::Path(agent),
+                _ if cell == search.current() => Shown::Current(agent),
+                Some(CellState::Unvisited { .. }) => Shown::Queued(agent),
+                Some(CellState::Visited { .. }) if search.repaired(cell) => Shown::Repaired(agent),
+                Some(CellState::Visited { dist }) => Shown::Visited { agent, dist },
+                Some(CellState::Unreachable) => Shown::Unreachable,
+                Some(CellState::Unknown) | None => {
+                    match search
+                        .backward()
+                        .and_then(|backward| backward.get_cell(cell))
+                    {
+                        Some(CellState::Unvisited { .. }) => Shown::BackwardQueued(agent),
+                        Some(CellState::Visited { .. }) => Shown::BackwardVisited(agent),
+                        _ => continue,
+                    }
+                }
+            }
+        };
+
+        if best.is_none_or(|best| candidate.rank() > best.rank()) {
+            best = Some(candidate);
+        }
+    }
+
+    best.unwrap_or(Shown::Terrain)
+}
+
+/// The grid as text, a character per cell and a line per row
+pub fn frame(grid: &Grid) -> String {
+    let mut text = String::with_capacity((grid.width() as usize + 1) * grid.height() as usize);
+
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            text.push(cell_char(grid, (x, y)));
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+/// `#` obstacle, `%` wall the agent in the fog hasn't sensed, `S` start, `G` goal, `*`
+/// path, `M` where a bidirectional search met, `@` the cell expanded last or the agent in
+/// the fog, `,` where it walked, `o` queued, `.` expanded, `x` cut off from the start, `:`
+/// costly and `-` negative terrain nothing reached yet, blank otherwise
+fn cell_char(grid: &Grid, cell: (u32, u32)) -> char {
+    match shown(grid, cell) {
+        Shown::Obstacle => '#',
+        Shown::UnsensedObstacle => '%',
+        Shown::Start(_) => 'S',
+        Shown::Goal(_) => 'G',
+        Shown::Path(_) => '*',
+        Shown::Meeting(_) => 'M',
+        Shown::Walker | Shown::Current(_) => '@',
+        Shown::Walked => ',',
+        Shown::Queued(_) | Shown::BackwardQueued(_) => 'o',
+        Shown::Repaired(_) | Shown::Visited { .. } | Shown::BackwardVisited(_) => '.',
+        Shown::Unreachable => 'x',
+        Shown::Terrain if grid.map().signed_cost(cell) < 0 => '-',
+        Shown::Terrain if grid.map().cost(cell) > 1 => ':',
+        Shown::Terrain => ' ',
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    fmt::Write as _,
+    io::{Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use dijkstra_visual::{map::Topology, search::SearchStatus, shown::frame, Grid};
+
+use crate::{layout::Layout, renderer::Renderer, view::View};
+
+/// Moves the cursor home and clears the terminal, so every frame is drawn over the last
+const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// Moves the cursor home without clearing, frames of blocks are all the same size
+const HOME: &str = "\x1b[H";
+
+/// Clears from the cursor to the end of the screen, after what a frame left
+const CLEAR_BELOW: &str = "\x1b[J";
+
+/// Back to the terminal's own colors
+const RESET: &str = "\x1b[0m";
+
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+
+/// Switches to the alternate screen and back, which leaves the shell's scrollback alone
+const ENTER_ALTERNATE: &str = "\x1b[?1049h";
+const LEAVE_ALTERNATE: &str = "\x1b[?1049l";
+
+/// Lines kept free below the grid for the footer
+const FOOTER_LINES: u32 = 2;
+
+/// Set by Ctrl+C and termination signals, the animation stops at the next frame
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_stop(_: libc::c_int) {
+    STOP.store(true, Ordering::SeqCst);
+}
+
+/// Columns and lines of the terminal on stdout, `None` if it isn't one
+fn terminal_size() -> Option<(u32, u32)> {
+    // SAFETY: TIOCGWINSZ only writes a winsize
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    let found = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+
+    (found && size.ws_col > 0 && size.ws_row > 0)
+        .then_some((size.ws_col as u32, size.ws_row as u32))
+}
+
+fn is_terminal(fd: libc::c_int) -> bool {
+    // SAFETY: only asks about the file descriptor
+    unsafe { libc::isatty(fd) == 1 }
+}
+
+/// The terminal set up for an animation, put back the way it was when dropped: the
+/// cursor is hidden, keys pressed are read without waiting for Enter or being echoed,
+/// and Ctrl+C stops the animation instead of killing it mid-frame with its colors still
+/// set. With `alternate`, frames go to the alternate screen.
+pub struct Session {
+    /// How stdin was set up before, `None` if it isn't a terminal
+    termios: Option<libc::termios>,
+    /// Whether stdout is a terminal, nothing is written to it otherwise
+    tty: bool,
+    alternate: bool,
+}
+
+impl Session {
+    pub fn start(alternate: bool) -> Self {
+        // SAFETY: the handler only stores to an atomic
+        unsafe {
+            libc::signal(
+                libc::SIGINT,
+                request_stop as *const () as libc::sighandler_t,
+            );
+            libc::signal(
+                libc::SIGTERM,
+                request_stop as *const () as libc::sighandler_t,
+            );
+        }
+
+        let termios = is_terminal(libc::STDIN_FILENO)
+            .then(|| {
+                // SAFETY: tcgetattr only writes the termios, tcsetattr only reads it
+                let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+                if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut termios) } != 0 {
+                    return None;
+                }
+
+                // reads return at once, with whatever was pressed so far
+                let mut keys = termios;
+                keys.c_lflag &= !(libc::ICANON | libc::ECHO);
+                keys.c_cc[libc::VMIN] = 0;
+                keys.c_cc[libc::VTIME] = 0;
+                unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &keys) };
+
+                Some(termios)
+            })
+            .flatten();
+
+        let tty = is_terminal(libc::STDOUT_FILENO);
+        let alternate = alternate && tty;
+        if tty {
+            let enter = if alternate { ENTER_ALTERNATE } else { "" };
+            // a closed stdout ends the animation at its first frame anyway
+            let _ = write_frame(&format!("{}{}", enter, HIDE_CURSOR));
+        }
+
+        Self {
+            termios,
+            tty,
+            alternate,
+        }
+    }
+
+    /// Whether the animation should stop: `q` was pressed or a stop signal came in
+    pub fn stopped(&self) -> bool {
+        if self.termios.is_some() {
+            let mut pressed = [0; 16];
+            if let Ok(read) = std::io::stdin().read(&mut pressed) {
+                if pressed[..read]
+                    .iter()
+                    .any(|&key| key == b'q' || key == b'Q')
+                {
+                    STOP.store(true, Ordering::SeqCst);
+                }
+            }
+        }
+
+        STOP.load(Ordering::SeqCst)
+    }
+
+    /// Keeps the last frame on the alternate screen until `q` is pressed, it goes away
+    /// with the alternate screen otherwise
+    pub fn linger(&self) {
+        if !self.alternate || self.termios.is_none() {
+            return;
+        }
+
+        let _ = write_frame("press q to quit");
+        while !self.stopped() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        STOP.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if let Some(termios) = &self.termios {
+            // SAFETY: puts back what tcgetattr wrote
+            unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, termios) };
+        }
+
+        if self.tty {
+            let leave = if self.alternate { LEAVE_ALTERNATE } else { "" };
+            let _ = write_frame(&format!("{}{}{}", RESET, SHOW_CURSOR, leave));
+        }
+    }
+}
+
+/// Plays the search back on `renderer`, an iteration every `delay` and a frame every
+/// `frame_interval`, or every iteration if that's slower. Returns once the search is
+/// done, its last frame left on screen, or false as soon as `session` is stopped.
+pub fn animate(
+    grid: &mut Grid,
+    renderer: &mut impl Renderer,
+    view: &mut View,
+    delay: Duration,
+    frame_interval: Duration,
+    session: &Session,
+) -> bool {
+    // as many iterations as fit into a frame, at least one
+    let per_frame = (frame_interval.as_nanos() / delay.as_nanos().max(1)).max(1);
+    let pause = frame_interval.max(delay);
+
+    let started = Instant::now();
+    loop {
+        let begin = Instant::now();
+        let done = !matches!(
+            grid.status(),
+            SearchStatus::Running | SearchStatus::Revealing
+        );
+
+        if session.stopped() {
+            return false;
+        }
+
+        // stdout being closed, say by `head`, ends the animation like finishing does
+        if renderer.draw_cells(view, grid, started.elapsed()).is_err() || done {
+            return true;
+        }
+
+        for _ in 0..per_frame {
+            grid.dijkstra_iteration();
+            if !matches!(
+                grid.status(),
+                SearchStatus::Running | SearchStatus::Revealing
+            ) {
+                break;
+            }
+        }
+
+        std::thread::sleep(pause.saturating_sub(begin.elapsed()));
+    }
+}
+
+/// Writes `frame` to stdout at once
+fn write_frame(frame: &str) -> Result<(), String> {
+    let mut stdout = std::io::stdout().lock();
+
+    stdout
+        .write_all(frame.as_bytes())
+        .and_then(|_| stdout.flush())
+        .map_err(|e| format!("couldn't write the frame: {}", e))
+}
+
+/// `--ascii`: a character per cell, see [`frame`], with `footer` under the grid
+pub struct TextRenderer {
+    pub footer: fn(&Grid) -> String,
+}
+
+impl Renderer for TextRenderer {
+    fn draw_cells(&mut self, _: &mut View, grid: &Grid, _: Duration) -> Result<(), String> {
+        let (columns, rows) = shown_cells(grid);
+        let text: String = frame(grid)
+            .lines()
+            .take(rows as usize)
+            .flat_map(|line| line.chars().take(columns as usize).chain(['\n']))
+            .collect();
+
+        write_frame(&format!(
+            "{}{}{}\n",
+            CLEAR,
+            text,
+            footer(grid, self.footer, (columns, rows))
+        ))
+    }
+}
+
+/// Columns and rows of cells that fit into the terminal with the footer below them, all
+/// of them if stdout isn't a terminal. Half blocks fit two rows into a line.
+fn shown_cells(grid: &Grid) -> (u32, u32) {
+    let (w, h) = (grid.width(), grid.height());
+
+    match terminal_size() {
+        Some((columns, lines)) => (
+            w.min(columns),
+            h.min(lines.saturating_sub(FOOTER_LINES).max(1)),
+        ),
+        None => (w, h),
+    }
+}
+
+/// `footer` for `grid`, saying which part of it is shown if not all of it fits
+fn footer(grid: &Grid, footer: fn(&Grid) -> String, shown: (u32, u32)) -> String {
+    if shown == (grid.width(), grid.height()) {
+        return footer(grid);
+    }
+
+    format!(
+        "{}, showing {}x{} of {}x{} cells",
+        footer(grid),
+        shown.0,
+        shown.1,
+        grid.width(),
+        grid.height()
+    )
+}
+
+/// `--tui`: cells in the colors of the window, two rows of them per line of half blocks,
+/// which makes them about square. Needs a terminal with 24-bit colors.
+pub struct BlockRenderer {
+    pub footer: fn(&Grid) -> String,
+    /// A pixel per cell like [`View::paint_cells`] writes them, kept between frames so
+    /// only cells that changed are colored again
+    pixels: Vec<u8>,
+}
+
+impl BlockRenderer {
+    pub fn new(footer: fn(&Grid) -> String) -> Self {
+        Self {
+            footer,
+            pixels: Vec::new(),
+        }
+    }
+}
+
+impl Renderer for BlockRenderer {
+    fn draw_cells(&mut self, view: &mut View, grid: &Grid, now: Duration) -> Result<(), String> {
+        let (w, h) = (grid.width(), grid.height());
+        let pitch = w as usize * 3;
+
+        if self.pixels.len() != pitch * h as usize {
+            self.pixels = vec![0; pitch * h as usize];
+            view.invalidate();
+        }
+
+        // every row at once, only the rows shown and the colors matter
+        let layout = Layout::new(w, h, 0, w, h, true, Topology::Square);
+        view.paint_cells(&mut self.pixels, pitch, grid, &layout, now);
+
+        // half blocks fit two rows of cells into a line
+        let (columns, lines) = terminal_size().unwrap_or((w, h.div_ceil(2)));
+        let shown = (
+            w.min(columns),
+            h.min(lines.saturating_sub(FOOTER_LINES).max(1) * 2),
+        );
+
+        write_frame(&format!(
+            "{}{}{}{}\n{}",
+            HOME,
+            blocks(&self.pixels, pitch, shown),
+            RESET,
+            footer(grid, self.footer, shown),
+            CLEAR_BELOW
+        ))
+    }
+}
+
+/// The top left `columns` x `rows` cells of RGB24 rows `pitch` bytes apart, drawn with `▀`,
+/// the upper row of every pair in the foreground and the lower one in the background. An
+/// odd last row keeps the terminal's background below it.
+fn blocks(pixels: &[u8], pitch: usize, (columns, rows): (u32, u32)) -> String {
+    let color = |row: u32, column: usize| {
+        let i = row as usize * pitch + column * 3;
+        (pixels[i], pixels[i + 1], pixels[i + 2])
+    };
+
+    let mut text = String::new();
+    for top in (0..rows).step_by(2) {
+        for column in 0..columns as usize {
+            let (r, g, b) = color(top, column);
+            write!(text, "\x1b[38;2;{};{};{}m", r, g, b).unwrap();
+
+            if top + 1 < rows {
+                let (r, g, b) = color(top + 1, column);
+                write!(text, "\x1b[48;2;{};{};{}m", r, g, b).unwrap();
+            } else {
+                text.push_str("\x1b[49m");
+            }
+
+            text.push('▀');
+        }
+        text.push_str(RESET);
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_are_cropped_to_the_shown_cells() {
+        // 3x3 cells, red, green and blue columns
+        let pixels: Vec<u8> = (0..3)
+            .flat_map(|_| [255, 0, 0, 0, 255, 0, 0, 0, 255])
+            .collect();
+        let text = blocks(&pixels, 9, (2, 3));
+
+        assert_eq!(text.lines().count(), 2);
+        assert!(text.lines().all(|line| line.matches('▀').count() == 2));
+        assert!(!text.contains("0;0;255"));
+    }
+}
@@ -0,0 +1,104 @@
+use std::f64::consts::TAU;
+
+use crate::{grid2d::Grid2D, map::MAX_COST, rng::Rng};
+
+/// How terrain costs are generated
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum TerrainKind {
+    /// Smooth hills of expensive terrain from layered Perlin noise
+    Noise,
+}
+
+/// Cells between the lattice points of the coarsest noise layer, about the size of a hill
+const FEATURE_SIZE: f64 = 16.0;
+
+/// Noise layers, each one half the size and half as strong as the one before
+const OCTAVES: u32 = 3;
+
+/// Cost of every cell of a `w` x `h` map, all in `1..=MAX_COST`
+pub fn costs(kind: TerrainKind, w: u32, h: u32, rng: &mut Rng) -> Grid2D<u32> {
+    match kind {
+        TerrainKind::Noise => noise_costs(w, h, rng),
+    }
+}
+
+fn noise_costs(w: u32, h: u32, rng: &mut Rng) -> Grid2D<u32> {
+    let mut heights = Grid2D::new(w, h, 0.0);
+
+    for octave in 0..OCTAVES {
+        let size = FEATURE_SIZE / 2f64.powi(octave as i32);
+        let amplitude = 0.5f64.powi(octave as i32);
+        let layer = Perlin::new(w as f64 / size, h as f64 / size, rng);
+
+        for x in 0..w {
+            for y in 0..h {
+                // sampling cell centers keeps clear of the lattice points, where it's always 0
+                let point = ((x as f64 + 0.5) / size, (y as f64 + 0.5) / size);
+                heights[(x, y)] += layer.sample(point) * amplitude;
+            }
+        }
+    }
+
+    let min = heights.values().copied().fold(f64::INFINITY, f64::min);
+    let max = heights.values().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = (max - min).max(f64::EPSILON);
+
+    let mut costs = Grid2D::new(w, h, 1);
+    for x in 0..w {
+        for y in 0..h {
+            let t = (heights[(x, y)] - min) / range;
+            costs[(x, y)] = (1 + (t * MAX_COST as f64) as u32).min(MAX_COST);
+        }
+    }
+
+    costs
+}
+
+/// A single layer of 2D Perlin noise, random unit gradients on an integer lattice
+struct Perlin {
+    gradients: Grid2D<(f64, f64)>,
+}
+
+impl Perlin {
+    /// Covers points from `(0, 0)` to `(w, h)`
+    fn new(w: f64, h: f64, rng: &mut Rng) -> Self {
+        let (columns, rows) = (w.ceil() as u32 + 2, h.ceil() as u32 + 2);
+
+        // column after column, the order the random angles were always drawn in
+        let mut gradients = Grid2D::new(columns, rows, (0.0, 0.0));
+        for x in 0..columns {
+            for y in 0..rows {
+                let angle = rng.unit() * TAU;
+                gradients[(x, y)] = (angle.cos(), angle.sin());
+            }
+        }
+
+        Self { gradients }
+    }
+
+    /// Roughly in `-1.0..1.0`
+    fn sample(&self, (x, y): (f64, f64)) -> f64 {
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+
+        let influence = |dx: u32, dy: u32| {
+            let (gx, gy) = self.gradients[(x0 + dx, y0 + dy)];
+            gx * (fx - dx as f64) + gy * (fy - dy as f64)
+        };
+
+        let (u, v) = (fade(fx), fade(fy));
+        let top = lerp(influence(0, 0), influence(1, 0), u);
+        let bottom = lerp(influence(0, 1), influence(1, 1), u);
+
+        lerp(top, bottom, v)
+    }
+}
+
+/// Perlin's smootherstep, so the slopes line up between lattice cells
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
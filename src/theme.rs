@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+
+use sdl2::pixels::Color;
+
+/// Built-in color schemes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ThemeKind {
+    /// The classic bright colors on grey
+    #[default]
+    Default,
+    /// The Okabe-Ito colors, which stay apart with every common color vision deficiency
+    Colorblind,
+    /// Muted colors on a dark background
+    Dark,
+    /// Pale colors on a white background
+    Light,
+}
+
+/// Every color the grid and the text over it are drawn in. Agents after the first get
+/// their own hues on top of these.
+#[derive(Clone, Copy, Debug)]
+pub struct Theme {
+    /// Behind the cells, showing through the gaps between them
+    pub background: Color,
+    pub obstacle: Color,
+    /// Untouched terrain ramps from the cheapest color to the priciest one by its cost
+    pub cheapest_terrain: Color,
+    pub priciest_terrain: Color,
+    /// Untouched negative terrain, which stands out from the ramp of the others
+    pub negative_terrain: Color,
+    /// Walls the agent in the fog hasn't sensed yet
+    pub unsensed_obstacle: Color,
+    /// The agent in the fog, and the cells it walked through
+    pub walker: Color,
+    pub walked: Color,
+
+    /// The main agent's search
+    pub start: Color,
+    pub goal: Color,
+    pub current: Color,
+    pub unvisited: Color,
+    pub visited: Color,
+    pub path: Color,
+    /// Cells the backward half of a bidirectional search reached
+    pub backward_unvisited: Color,
+    pub backward_visited: Color,
+    /// Where the two halves of a bidirectional search meet
+    pub meeting: Color,
+    /// Cells an incremental search expanded again after the map changed
+    pub repaired: Color,
+    /// Free cells cut off from the start
+    pub unreachable: Color,
+
+    /// Pointers from cells to their parents
+    pub parents: Color,
+    /// Any-angle path segments and the outline of the hovered cell
+    pub lines: Color,
+    pub smooth_path: Color,
+    pub text: Color,
+    /// Text about something that went wrong
+    pub error_text: Color,
+
+    /// The settings bar below the grid, and the buttons on it
+    pub panel: Color,
+    pub button: Color,
+}
+
+impl Theme {
+    /// Names of the colors in the `[colors]` section of the config
+    pub const NAMES: [&'static str; 26] = [
+        "background",
+        "obstacle",
+        "cheapest_terrain",
+        "priciest_terrain",
+        "negative_terrain",
+        "unsensed_obstacle",
+        "walker",
+        "walked",
+        "start",
+        "goal",
+        "current",
+        "unvisited",
+        "visited",
+        "path",
+        "backward_unvisited",
+        "backward_visited",
+        "meeting",
+        "repaired",
+        "unreachable",
+        "parents",
+        "lines",
+        "smooth_path",
+        "text",
+        "error_text",
+        "panel",
+        "button",
+    ];
+
+    pub fn new(kind: ThemeKind) -> Self {
+        match kind {
+            ThemeKind::Default => Self {
+                background: Color::GRAY,
+                obstacle: Color::WHITE,
+                cheapest_terrain: Color::GREY,
+                priciest_terrain: Color::RGB(70, 45, 20),
+                negative_terrain: Color::RGB(40, 150, 140),
+                unsensed_obstacle: Color::RGB(90, 90, 90),
+                walker: Color::MAGENTA,
+                walked: Color::RGB(230, 120, 230),
+                start: Color::BLUE,
+                goal: Color::GREEN,
+                current: Color::CYAN,
+                unvisited: Color::RED,
+                visited: Color::YELLOW,
+                path: Color::MAGENTA,
+                backward_unvisited: Color::RGB(255, 130, 0),
+                backward_visited: Color::RGB(140, 220, 255),
+                meeting: Color::RGB(255, 100, 180),
+                repaired: Color::RGB(150, 90, 255),
+                unreachable: Color::RGB(110, 0, 0),
+                parents: Color::RGB(60, 60, 60),
+                lines: Color::BLACK,
+                smooth_path: Color::RGB(255, 140, 0),
+                text: Color::BLACK,
+                error_text: Color::RED,
+                panel: Color::RGB(200, 200, 200),
+                button: Color::RGB(235, 235, 235),
+            },
+            // blue, orange and yellow carry the states, red and green never have to be
+            // told apart
+            ThemeKind::Colorblind => Self {
+                background: Color::GRAY,
+                obstacle: Color::WHITE,
+                cheapest_terrain: Color::GREY,
+                priciest_terrain: Color::RGB(70, 45, 20),
+                negative_terrain: Color::RGB(0, 158, 115),
+                unsensed_obstacle: Color::RGB(90, 90, 90),
+                walker: Color::RGB(204, 121, 167),
+                walked: Color::RGB(230, 180, 210),
+                start: Color::RGB(0, 114, 178),
+                goal: Color::RGB(0, 158, 115),
+                current: Color::BLACK,
+                unvisited: Color::RGB(213, 94, 0),
+                visited: Color::RGB(240, 228, 66),
+                path: Color::RGB(86, 180, 233),
+                backward_unvisited: Color::RGB(230, 159, 0),
+                backward_visited: Color::RGB(200, 225, 245),
+                meeting: Color::RGB(204, 121, 167),
+                repaired: Color::RGB(150, 130, 230),
+                unreachable: Color::RGB(60, 40, 20),
+                parents: Color::RGB(60, 60, 60),
+                lines: Color::BLACK,
+                smooth_path: Color::RGB(204, 121, 167),
+                text: Color::BLACK,
+                error_text: Color::RGB(213, 94, 0),
+                panel: Color::RGB(200, 200, 200),
+                button: Color::RGB(235, 235, 235),
+            },
+            ThemeKind::Dark => Self {
+                background: Color::RGB(15, 15, 20),
+                obstacle: Color::RGB(150, 150, 160),
+                cheapest_terrain: Color::RGB(40, 40, 48),
+                priciest_terrain: Color::RGB(95, 70, 40),
+                negative_terrain: Color::RGB(20, 90, 85),
+                unsensed_obstacle: Color::RGB(75, 75, 85),
+                walker: Color::RGB(230, 90, 230),
+                walked: Color::RGB(120, 60, 120),
+                start: Color::RGB(80, 130, 255),
+                goal: Color::RGB(70, 210, 100),
+                current: Color::RGB(120, 230, 230),
+                unvisited: Color::RGB(180, 50, 50),
+                visited: Color::RGB(150, 130, 40),
+                path: Color::RGB(235, 90, 235),
+                backward_unvisited: Color::RGB(190, 100, 20),
+                backward_visited: Color::RGB(60, 110, 140),
+                meeting: Color::RGB(240, 110, 180),
+                repaired: Color::RGB(110, 70, 190),
+                unreachable: Color::RGB(70, 10, 10),
+                parents: Color::RGB(170, 170, 170),
+                lines: Color::WHITE,
+                smooth_path: Color::RGB(255, 160, 40),
+                text: Color::WHITE,
+                error_text: Color::RGB(255, 90, 90),
+                panel: Color::RGB(35, 35, 42),
+                button: Color::RGB(60, 60, 70),
+            },
+            ThemeKind::Light => Self {
+                background: Color::WHITE,
+                obstacle: Color::RGB(70, 70, 70),
+                cheapest_terrain: Color::RGB(230, 230, 230),
+                priciest_terrain: Color::RGB(170, 140, 100),
+                negative_terrain: Color::RGB(140, 210, 200),
+                unsensed_obstacle: Color::RGB(170, 170, 170),
+                walker: Color::RGB(200, 50, 200),
+                walked: Color::RGB(240, 190, 240),
+                start: Color::RGB(40, 80, 230),
+                goal: Color::RGB(30, 170, 60),
+                current: Color::RGB(0, 170, 190),
+                unvisited: Color::RGB(250, 140, 130),
+                visited: Color::RGB(255, 240, 160),
+                path: Color::RGB(200, 60, 200),
+                backward_unvisited: Color::RGB(255, 190, 120),
+                backward_visited: Color::RGB(190, 230, 250),
+                meeting: Color::RGB(240, 120, 180),
+                repaired: Color::RGB(190, 160, 250),
+                unreachable: Color::RGB(200, 120, 120),
+                parents: Color::RGB(120, 120, 120),
+                lines: Color::BLACK,
+                smooth_path: Color::RGB(230, 120, 0),
+                text: Color::BLACK,
+                error_text: Color::RGB(200, 0, 0),
+                panel: Color::RGB(235, 235, 235),
+                button: Color::WHITE,
+            },
+        }
+    }
+}
+
+impl Theme {
+    /// This theme with colors replaced by the `[colors]` section of the config. Colors are
+    /// written as `"#rrggbb"` or `"r, g, b"`.
+    pub fn with_overrides(mut self, colors: &BTreeMap<String, String>) -> Result<Self, String> {
+        for (name, value) in colors {
+            let Some(color) = self.color_mut(name) else {
+                return Err(format!(
+                    "unknown color {:?} in [colors], valid colors are: {}",
+                    name,
+                    Self::NAMES.join(", ")
+                ));
+            };
+
+            *color = parse_color(value)
+                .ok_or_else(|| format!("invalid color {:?} for {}", value, name))?;
+        }
+
+        Ok(self)
+    }
+
+    fn color_mut(&mut self, name: &str) -> Option<&mut Color> {
+        let color = match name {
+            "background" => &mut self.background,
+            "obstacle" => &mut self.obstacle,
+            "cheapest_terrain" => &mut self.cheapest_terrain,
+            "priciest_terrain" => &mut self.priciest_terrain,
+            "negative_terrain" => &mut self.negative_terrain,
+            "unsensed_obstacle" => &mut self.unsensed_obstacle,
+            "walker" => &mut self.walker,
+            "walked" => &mut self.walked,
+            "start" => &mut self.start,
+            "goal" => &mut self.goal,
+            "current" => &mut self.current,
+            "unvisited" => &mut self.unvisited,
+            "visited" => &mut self.visited,
+            "path" => &mut self.path,
+            "backward_unvisited" => &mut self.backward_unvisited,
+            "backward_visited" => &mut self.backward_visited,
+            "meeting" => &mut self.meeting,
+            "repaired" => &mut self.repaired,
+            "unreachable" => &mut self.unreachable,
+            "parents" => &mut self.parents,
+            "lines" => &mut self.lines,
+            "smooth_path" => &mut self.smooth_path,
+            "text" => &mut self.text,
+            "error_text" => &mut self.error_text,
+            "panel" => &mut self.panel,
+            "button" => &mut self.button,
+            _ => return None,
+        };
+
+        Some(color)
+    }
+}
+
+/// Parses `"#rrggbb"` or `"r, g, b"`
+fn parse_color(text: &str) -> Option<Color> {
+    let text = text.trim();
+
+    if let Some(hex) = text.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return Some(Color::RGB(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let channels: Vec<_> = text
+        .split(',')
+        .map(|channel| channel.trim().parse::<u8>().ok())
+        .collect::<Option<_>>()?;
+
+    match channels[..] {
+        [r, g, b] => Some(Color::RGB(r, g, b)),
+        _ => None,
+    }
+}
+
+impl std::fmt::Display for Theme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[colors]")?;
+
+        let mut theme = *self;
+        for name in Self::NAMES {
+            if let Some(color) = theme.color_mut(name) {
+                writeln!(
+                    f,
+                    "{} = \"#{:02x}{:02x}{:02x}\"",
+                    name, color.r, color.g, color.b
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ThemeKind::Default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_parse_as_hex_or_channels() {
+        assert_eq!(parse_color("#ff8000"), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(parse_color("#FF8000"), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(parse_color("255, 128, 0"), Some(Color::RGB(255, 128, 0)));
+        assert_eq!(parse_color(" 1,2,3 "), Some(Color::RGB(1, 2, 3)));
+    }
+
+    #[test]
+    fn malformed_colors_dont_parse() {
+        for text in [
+            "",
+            "red",
+            "#ff80",
+            "#ff80000",
+            "#gg0000",
+            "#é0000",
+            "256, 0, 0",
+            "1, 2",
+            "1, 2, 3, 4",
+            "1, -2, 3",
+        ] {
+            assert_eq!(parse_color(text), None, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn overrides_replace_named_colors_only() {
+        let colors: BTreeMap<_, _> = [("text", "#102030"), ("panel", "4, 5, 6")]
+            .into_iter()
+            .map(|(name, color)| (name.to_string(), color.to_string()))
+            .collect();
+        let theme = Theme::default().with_overrides(&colors).unwrap();
+
+        assert_eq!(theme.text, Color::RGB(16, 32, 48));
+        assert_eq!(theme.panel, Color::RGB(4, 5, 6));
+        assert_eq!(theme.background, Theme::default().background);
+
+        let unknown = BTreeMap::from([("txet".to_string(), "#102030".to_string())]);
+        let error = Theme::default().with_overrides(&unknown).unwrap_err();
+        assert!(error.starts_with("unknown color \"txet\""), "{}", error);
+
+        let invalid = BTreeMap::from([("text".to_string(), "#1020".to_string())]);
+        let error = Theme::default().with_overrides(&invalid).unwrap_err();
+        assert!(error.starts_with("invalid color \"#1020\""), "{}", error);
+    }
+
+    #[test]
+    fn every_name_is_a_color() {
+        let mut theme = Theme::default();
+
+        for name in Theme::NAMES {
+            assert!(theme.color_mut(name).is_some(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::{Canvas, RenderTarget, TextureCreator},
+    ttf::Font,
+};
+
+use crate::theme::Theme;
+
+/// Tiny immediate mode widgets: every frame the widgets are drawn and checked against
+/// the last click in the same call, so there is no widget state to keep in sync.
+pub struct Ui<'a, C> {
+    texture_creator: &'a TextureCreator<C>,
+    font: &'a Font<'a, 'static>,
+    theme: Theme,
+    /// Click that hasn't been handed to a widget yet
+    click: Option<Point>,
+}
+
+impl<'a, C> Ui<'a, C> {
+    pub fn new(
+        texture_creator: &'a TextureCreator<C>,
+        font: &'a Font<'a, 'static>,
+        theme: Theme,
+    ) -> Self {
+        Self {
+            texture_creator,
+            font,
+            theme,
+            click: None,
+        }
+    }
+
+    /// Remembers a click for the widgets drawn next frame
+    pub fn click(&mut self, x: i32, y: i32) {
+        self.click = Some(Point::new(x, y));
+    }
+
+    /// Drops a click no widget was under, so it can't trigger one drawn later
+    pub fn end_frame(&mut self) {
+        self.click = None;
+    }
+
+    /// Fills `rect` in the panel color, for widgets to be drawn on
+    pub fn panel<T: RenderTarget>(&self, canvas: &mut Canvas<T>, rect: Rect) {
+        canvas.set_draw_color(self.theme.panel);
+        canvas.fill_rect(rect).unwrap();
+    }
+
+    pub fn label<T: RenderTarget>(&self, canvas: &mut Canvas<T>, text: &str, x: i32, y: i32) {
+        render_colored_text(
+            canvas,
+            self.texture_creator,
+            self.font,
+            text,
+            x,
+            y,
+            self.theme.text,
+        );
+    }
+
+    /// Draws a button with `text` centered in `rect`, returns whether it was clicked
+    pub fn button<T: RenderTarget>(
+        &mut self,
+        canvas: &mut Canvas<T>,
+        rect: Rect,
+        text: &str,
+    ) -> bool {
+        canvas.set_draw_color(self.theme.button);
+        canvas.fill_rect(rect).unwrap();
+        canvas.set_draw_color(self.theme.lines);
+        canvas.draw_rect(rect).unwrap();
+
+        let (text_width, text_height) = self.font.size_of(text).unwrap();
+        self.label(
+            canvas,
+            text,
+            rect.x() + (rect.width() as i32 - text_width as i32) / 2,
+            rect.y() + (rect.height() as i32 - text_height as i32) / 2,
+        );
+
+        let clicked = self.click.is_some_and(|click| rect.contains_point(click));
+        if clicked {
+            self.click = None;
+        }
+
+        clicked
+    }
+}
+
+pub fn render_colored_text<T: RenderTarget, C>(
+    canvas: &mut Canvas<T>,
+    texture_creater: &TextureCreator<C>,
+    font: &Font,
+    text: &str,
+    x: i32,
+    y: i32,
+    color: Color,
+) {
+    let surface = font.render(text).solid(color).unwrap();
+    let mut rect = surface.rect();
+    rect.offset(x, y);
+
+    canvas
+        .copy(&surface.as_texture(texture_creater).unwrap(), None, rect)
+        .unwrap();
+}
@@ -0,0 +1,925 @@
+use std::time::Duration;
+
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::{Canvas, RenderTarget},
+};
+
+use dijkstra_visual::{
+    goal::GoalSpec,
+    grid2d::Grid2D,
+    map::{Portal, Topology, MAX_COST},
+    search::{format_dist, Algorithm, CellState, Heuristic, Search},
+    shown::{shown, Shown},
+    Grid,
+};
+
+use crate::{
+    atlas::GlyphAtlas,
+    fade::Fade,
+    layout::{Layout, CELL_SPACING},
+    theme::Theme,
+};
+
+/// How long cells take to blend into a new color with `--fade`
+const FADE_DURATION: Duration = Duration::from_millis(150);
+
+/// How visited cells are colored
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorBy {
+    /// Only by their state and terrain cost
+    #[default]
+    State,
+    /// From light to dark in the order they were settled
+    Order,
+    /// From blue to red by their distance, across the distances of the search so far or
+    /// a fixed scale
+    Distance,
+    /// Like `State`, but cells in the open set go from bright to dark by their priority,
+    /// the f-value of searches with a heuristic, so the brightest ones are taken next
+    Priority,
+}
+
+/// Colors a single agent's search is drawn in
+struct AgentPalette {
+    start: Color,
+    goal: Color,
+    current: Color,
+    unvisited: Color,
+    visited: Color,
+    path: Color,
+    /// Cells the backward half of a bidirectional search reached
+    backward_unvisited: Color,
+    backward_visited: Color,
+    /// Where the two halves of a bidirectional search meet
+    meeting: Color,
+    /// Cells an incremental search expanded again after the map changed
+    repaired: Color,
+}
+
+impl AgentPalette {
+    /// The first agent keeps the colors of the theme, every further one gets its own hue
+    fn for_agent(index: usize, theme: &Theme) -> Self {
+        if index == 0 {
+            return Self {
+                start: theme.start,
+                goal: theme.goal,
+                current: theme.current,
+                unvisited: theme.unvisited,
+                visited: theme.visited,
+                path: theme.path,
+                backward_unvisited: theme.backward_unvisited,
+                backward_visited: theme.backward_visited,
+                meeting: theme.meeting,
+                repaired: theme.repaired,
+            };
+        }
+
+        // spread the hues by the golden angle, so neighboring agents look different
+        let hue = (index as f64 * 137.5) % 360.0;
+
+        Self {
+            start: hsv(hue, 1.0, 0.45),
+            goal: hsv(hue, 1.0, 0.3),
+            current: hsv(hue, 0.2, 1.0),
+            unvisited: hsv(hue, 0.9, 0.8),
+            visited: hsv(hue, 0.35, 0.95),
+            path: hsv(hue, 1.0, 0.6),
+            backward_unvisited: hsv((hue + 180.0) % 360.0, 0.9, 0.8),
+            backward_visited: hsv((hue + 180.0) % 360.0, 0.35, 0.95),
+            meeting: hsv(hue, 0.5, 0.5),
+            repaired: hsv((hue + 90.0) % 360.0, 0.6, 1.0),
+        }
+    }
+}
+
+/// Converts a hue in degrees, saturation and value in `0..=1` to a color
+fn hsv(hue: f64, saturation: f64, value: f64) -> Color {
+    let c = value * saturation;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - c;
+
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    Color::RGB(
+        ((r + m) * 255.0) as u8,
+        ((g + m) * 255.0) as u8,
+        ((b + m) * 255.0) as u8,
+    )
+}
+
+/// Everything the heuristic of the main search depends on
+#[derive(Clone, Debug, PartialEq)]
+struct OverlayKey {
+    goal: GoalSpec,
+    size: (u32, u32),
+    algorithm: Algorithm,
+    diagonals: bool,
+    heuristic: Option<Heuristic>,
+    weight: f64,
+    portals: Vec<Portal>,
+    topology: Topology,
+    layers: u32,
+}
+
+impl OverlayKey {
+    fn new(grid: &Grid) -> Self {
+        let search = &grid.searches()[0];
+        let map = grid.map();
+
+        Self {
+            goal: search.goal().clone(),
+            size: (grid.width(), grid.height()),
+            algorithm: search.algorithm(),
+            diagonals: search.diagonals(),
+            heuristic: search.heuristic_kind(),
+            weight: search.heuristic_weight(),
+            portals: map.portals().to_vec(),
+            topology: map.topology(),
+            layers: map.layers(),
+        }
+    }
+}
+
+/// What a search's cells were last drawn by: how far its change logs were, and what
+/// they don't log
+#[derive(Clone, Debug, PartialEq)]
+struct SearchMark {
+    changes: usize,
+    backward: Option<usize>,
+    current: (u32, u32),
+    meeting: Option<(u32, u32)>,
+    farthest: Option<u32>,
+    settled: usize,
+}
+
+impl SearchMark {
+    fn new(search: &Search) -> Self {
+        Self {
+            changes: search.changes().mark(),
+            backward: search.backward().map(|backward| backward.changes().mark()),
+            current: search.current(),
+            meeting: search.meeting(),
+            farthest: search.farthest(),
+            settled: search.settle_order().len(),
+        }
+    }
+}
+
+/// How far the grid's and every search's change logs were when cells were last drawn
+#[derive(Clone, Debug)]
+struct CaughtUp {
+    grid: usize,
+    searches: Vec<SearchMark>,
+}
+
+impl CaughtUp {
+    fn new(grid: &Grid) -> Self {
+        Self {
+            grid: grid.changes().mark(),
+            searches: grid.searches().iter().map(SearchMark::new).collect(),
+        }
+    }
+}
+
+/// How a [`Grid`] is drawn, everything about it that isn't part of the search
+#[derive(Debug, Default)]
+pub struct View {
+    /// Whether unknown cells are colored by their heuristic value towards the main goal
+    heuristic_overlay: bool,
+    /// Heuristic overlay color of every cell
+    overlay_colors: Grid2D<Color>,
+    /// What the overlay colors were computed for, so they are recomputed once any of it
+    /// changes
+    overlay_for: Option<OverlayKey>,
+
+    /// Whether every cell the main search reached points to its parent
+    parents: bool,
+
+    /// Whether found paths are also drawn smoothed
+    smooth_paths: bool,
+
+    color_by: ColorBy,
+    /// Distance shaded reddest when coloring by distance, `None` to use the farthest
+    /// visited cell of each search
+    distance_scale: Option<u32>,
+    /// Lowest and highest value cells are shaded by for every search, worked out every
+    /// frame: the distance of visited cells when coloring by distance without a fixed
+    /// scale, the priority of queued ones when coloring by priority
+    ranges: Vec<(u32, u32)>,
+
+    /// Blends cells into their new color, `None` while they snap
+    fade: Option<Fade>,
+
+    /// Color every cell was last filled with by [`View::draw_cells`], and the layout it
+    /// was drawn with. Only cells whose color changed are drawn again, everything is
+    /// once the layout changes.
+    drawn: Grid2D<Option<Color>>,
+    drawn_with: Option<Layout>,
+    /// What cells were last drawn by, so only the ones that changed since are looked at
+    /// again. `None` to look at every cell.
+    caught_up: Option<CaughtUp>,
+    /// Cells to look at in the frame being drawn, `None` for every cell
+    dirty: Option<Vec<(u32, u32)>>,
+
+    theme: Theme,
+}
+
+impl View {
+    pub fn set_smooth_paths(&mut self, smooth: bool) -> &mut View {
+        self.smooth_paths = smooth;
+        self
+    }
+
+    pub fn set_color_by(&mut self, color_by: ColorBy) -> &mut View {
+        self.color_by = color_by;
+        self.caught_up = None;
+        self
+    }
+
+    pub fn set_distance_scale(&mut self, scale: Option<u32>) -> &mut View {
+        self.distance_scale = scale;
+        self.caught_up = None;
+        self
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) -> &mut View {
+        self.theme = theme;
+        self.caught_up = None;
+        self
+    }
+
+    pub fn set_fade(&mut self, fade: bool) -> &mut View {
+        self.fade = fade.then(|| Fade::new(FADE_DURATION));
+        self.caught_up = None;
+        self
+    }
+
+    pub fn show_heuristic_overlay(&mut self, show: bool) {
+        self.heuristic_overlay = show;
+        self.caught_up = None;
+    }
+
+    pub fn heuristic_overlay_shown(&self) -> bool {
+        self.heuristic_overlay
+    }
+
+    pub fn show_parents(&mut self, show: bool) {
+        self.parents = show;
+    }
+
+    pub fn parents_shown(&self) -> bool {
+        self.parents
+    }
+
+    /// Draws every cell on the next call to [`View::draw_cells`], for when whatever it
+    /// draws to lost what was drawn before
+    pub fn invalidate(&mut self) {
+        self.drawn_with = None;
+    }
+
+    /// Finds the range of values every search's cells are shaded by in the [`ColorBy`]
+    /// mode, if it shades by a range
+    fn refresh_ranges(&mut self, grid: &Grid) {
+        let previous = std::mem::take(&mut self.ranges);
+
+        let value = match (self.color_by, self.distance_scale) {
+            (ColorBy::Distance, None) => |search: &Search, cell| match search.get_cell(cell) {
+                Some(CellState::Visited { dist }) => Some(dist),
+                _ => None,
+            },
+            (ColorBy::Priority, _) => |search: &Search, cell| search.open_set_priority(cell),
+            _ => return,
+        };
+
+        for search in grid.searches() {
+            let mut range = (u32::MAX, 0);
+
+            for x in 0..grid.width() {
+                for y in 0..grid.height() {
+                    if let Some(value) = value(search, (x, y)) {
+                        range = (range.0.min(value), range.1.max(value));
+                    }
+                }
+            }
+
+            self.ranges.push(range);
+        }
+
+        // every shaded cell may change with the range
+        if self.ranges != previous {
+            self.caught_up = None;
+        }
+    }
+
+    /// Recomputes the heuristic overlay towards the main goal in a single pass over
+    /// the grid, if it is shown and out of date
+    fn refresh_heuristic_overlay(&mut self, grid: &Grid) {
+        if !self.heuristic_overlay {
+            return;
+        }
+
+        let overlay_for = Some(OverlayKey::new(grid));
+        if self.overlay_for == overlay_for {
+            return;
+        }
+
+        let search = &grid.searches()[0];
+
+        let max = search
+            .heuristic((0, 0))
+            .max(search.heuristic((grid.width() - 1, 0)))
+            .max(search.heuristic((0, grid.height() - 1)))
+            .max(search.heuristic((grid.width() - 1, grid.height() - 1)))
+            .max(1);
+
+        let mut overlay = Grid2D::new(grid.width(), grid.height(), Color::BLACK);
+        for x in 0..grid.width() {
+            for y in 0..grid.height() {
+                // bright near the goal, dark far away from it
+                let t = search.heuristic((x, y)) as f64 / max as f64;
+                let channel = |near: f64, far: f64| (near + (far - near) * t) as u8;
+
+                overlay[(x, y)] = Color::RGB(
+                    channel(210.0, 50.0),
+                    channel(210.0, 50.0),
+                    channel(255.0, 90.0),
+                );
+            }
+        }
+
+        self.overlay_colors = overlay;
+        self.overlay_for = overlay_for;
+        self.caught_up = None;
+    }
+
+    /// Color of a single cell. Where agents overlap, the most interesting state wins:
+    /// endpoints, then paths, current cells, frontiers and finally visited cells.
+    fn cell_color(&self, grid: &Grid, cell: (u32, u32)) -> Color {
+        let palette = |agent| AgentPalette::for_agent(agent, &self.theme);
+
+        let color = match shown(grid, cell) {
+            Shown::Obstacle => return self.theme.obstacle,
+            Shown::Walker => return self.theme.walker,
+            Shown::UnsensedObstacle => return self.theme.unsensed_obstacle,
+            // endpoints aren't shaded
+            Shown::Start(agent) => return palette(agent).start,
+            Shown::Goal(agent) => return palette(agent).goal,
+            Shown::Meeting(agent) => palette(agent).meeting,
+            Shown::Walked => self.theme.walked,
+            Shown::Path(agent) => palette(agent).path,
+            Shown::Current(agent) => palette(agent).current,
+            Shown::Queued(agent) => self.queued_color(
+                agent,
+                &grid.searches()[agent],
+                cell,
+                palette(agent).unvisited,
+            ),
+            Shown::Repaired(agent) => palette(agent).repaired,
+            Shown::Visited { agent, dist } => {
+                let search = &grid.searches()[agent];
+
+                match (search.pass(cell), search.farthest()) {
+                    // every IDA* pass gets its own hue
+                    (Some(pass), _) => hsv((pass as f64 * 137.5) % 360.0, 0.35, 0.95),
+                    // distance fields, from red where they are measured from to blue the
+                    // farthest away
+                    (None, Some(farthest)) => {
+                        hsv(240.0 * dist as f64 / farthest.max(1) as f64, 0.5, 0.95)
+                    }
+                    // every start's region gets its own hue
+                    (None, None) => match search.origin(cell) {
+                        Some(origin) if !search.extra_starts().is_empty() => {
+                            hsv((origin as f64 * 137.5 + 200.0) % 360.0, 0.45, 0.9)
+                        }
+                        _ => self.visited_color(agent, search, cell, palette(agent).visited),
+                    },
+                }
+            }
+            Shown::Unreachable => self.theme.unreachable,
+            Shown::BackwardQueued(agent) => palette(agent).backward_unvisited,
+            Shown::BackwardVisited(agent) => palette(agent).backward_visited,
+            Shown::Terrain => {
+                let cost = grid.map().cost(cell);
+
+                return if grid.map().signed_cost(cell) < 0 {
+                    self.theme.negative_terrain
+                } else if self.heuristic_overlay {
+                    shade_by_cost(self.overlay_colors[cell], cost)
+                } else {
+                    terrain_color(cost, &self.theme)
+                };
+            }
+        };
+
+        shade_by_cost(color, grid.map().cost(cell))
+    }
+
+    /// Color of a cell `search`, the agent numbered `agent`, has visited, according to the
+    /// [`ColorBy`] mode
+    fn visited_color(
+        &self,
+        agent: usize,
+        search: &Search,
+        cell: (u32, u32),
+        color: Color,
+    ) -> Color {
+        if let (ColorBy::Distance, Some(CellState::Visited { dist })) =
+            (self.color_by, search.get_cell(cell))
+        {
+            let (min, max) = match self.distance_scale {
+                Some(scale) => (0, scale),
+                None => self.ranges.get(agent).copied().unwrap_or((dist, dist)),
+            };
+
+            let t = dist.saturating_sub(min) as f64 / max.saturating_sub(min).max(1) as f64;
+            return hsv(240.0 * (1.0 - t.min(1.0)), 0.6, 0.95);
+        }
+
+        let (ColorBy::Order, Some(index)) = (self.color_by, search.settle_index(cell)) else {
+            return color;
+        };
+
+        let factor = 1.0 - index as f64 / search.settle_order().len() as f64 * 0.75;
+
+        Color::RGB(
+            (color.r as f64 * factor) as u8,
+            (color.g as f64 * factor) as u8,
+            (color.b as f64 * factor) as u8,
+        )
+    }
+
+    /// Color of a cell `search`, the agent numbered `agent`, has queued: darker the later
+    /// it is taken out of the open set when coloring by priority
+    fn queued_color(&self, agent: usize, search: &Search, cell: (u32, u32), color: Color) -> Color {
+        let (ColorBy::Priority, Some(priority), Some(&(min, max))) = (
+            self.color_by,
+            search.open_set_priority(cell),
+            self.ranges.get(agent),
+        ) else {
+            return color;
+        };
+
+        let t = priority.saturating_sub(min) as f64 / max.saturating_sub(min).max(1) as f64;
+        let factor = 1.0 - t * 0.7;
+
+        Color::RGB(
+            (color.r as f64 * factor) as u8,
+            (color.g as f64 * factor) as u8,
+            (color.b as f64 * factor) as u8,
+        )
+    }
+
+    /// Draws the cells whose color changed since the last call, onto a canvas that
+    /// still holds what that call drew. The first call, and every one after the layout
+    /// changed or [`View::invalidate`], draws the background and every cell. `now` only
+    /// matters for fading cells.
+    pub fn draw_cells<T: RenderTarget>(
+        &mut self,
+        canvas: &mut Canvas<T>,
+        grid: &Grid,
+        layout: &Layout,
+        now: Duration,
+    ) {
+        if self.begin_cells(grid, layout, now) {
+            canvas.set_draw_color(self.theme.background);
+            canvas.fill_rect(None).unwrap();
+        }
+
+        self.changed_cells(grid, layout, now, |cell, color| {
+            canvas.set_draw_color(color);
+            fill_cell(canvas, layout, cell);
+        });
+    }
+
+    /// Like [`View::draw_cells`], but writes a pixel per cell of the rows `layout` shows
+    /// into `pixels`, RGB24 rows `pitch` bytes apart, to be scaled up to
+    /// [`Layout::texel_rect`]. Returns whether any pixel changed.
+    pub fn paint_cells(
+        &mut self,
+        pixels: &mut [u8],
+        pitch: usize,
+        grid: &Grid,
+        layout: &Layout,
+        now: Duration,
+    ) -> bool {
+        let mut changed = self.begin_cells(grid, layout, now);
+        let first_row = layout.rows().start;
+
+        self.changed_cells(grid, layout, now, |(x, y), color| {
+            let i = (y - first_row) as usize * pitch + x as usize * 3;
+            pixels[i..i + 3].copy_from_slice(&[color.r, color.g, color.b]);
+            changed = true;
+        });
+
+        changed
+    }
+
+    /// Brings everything cells are colored by up to date, works out which cells to look
+    /// at and forgets the colors cells were drawn in if the layout changed. Returns
+    /// whether every cell is drawn anew.
+    fn begin_cells(&mut self, grid: &Grid, layout: &Layout, now: Duration) -> bool {
+        self.refresh_heuristic_overlay(grid);
+        self.refresh_ranges(grid);
+
+        // before fades that are done are forgotten, so those cells get their final color
+        self.dirty = self.dirty_cells(grid);
+
+        let (w, h) = (grid.width(), grid.height());
+        if let Some(fade) = &mut self.fade {
+            fade.begin_frame(w, h, now);
+        }
+
+        if self.drawn_with == Some(*layout) && self.drawn.fits(w, h) {
+            return false;
+        }
+
+        self.drawn = Grid2D::new(w, h, None);
+        self.drawn_with = Some(*layout);
+        self.dirty = None;
+        true
+    }
+
+    /// Cells whose color may have changed since cells were last drawn, `None` if any
+    /// may have
+    fn dirty_cells(&self, grid: &Grid) -> Option<Vec<(u32, u32)>> {
+        let caught_up = self.caught_up.as_ref()?;
+        if caught_up.searches.len() != grid.searches().len() {
+            return None;
+        }
+
+        let mut cells = grid.changes().since(caught_up.grid)?.to_vec();
+
+        for (search, mark) in grid.searches().iter().zip(&caught_up.searches) {
+            // shades that depend on every other cell
+            if search.farthest() != mark.farthest
+                || (self.color_by == ColorBy::Order && search.settle_order().len() != mark.settled)
+            {
+                return None;
+            }
+
+            cells.extend_from_slice(search.changes().since(mark.changes)?);
+            match (search.backward(), mark.backward) {
+                (Some(backward), Some(mark)) => {
+                    cells.extend_from_slice(backward.changes().since(mark)?)
+                }
+                (None, None) => {}
+                _ => return None,
+            }
+
+            cells.extend([mark.current, search.current()]);
+            cells.extend(mark.meeting.into_iter().chain(search.meeting()));
+        }
+
+        if let Some(fade) = &self.fade {
+            cells.extend(fade.fading());
+        }
+
+        cells.sort_unstable();
+        cells.dedup();
+
+        // looking at every cell is cheaper than sorting more of them
+        let all = grid.width() as usize * grid.height() as usize;
+        (cells.len() < all).then_some(cells)
+    }
+
+    /// Calls `paint` with every cell of the rows shown whose color changed since it was
+    /// last drawn, and its new color. Only cells [`View::begin_cells`] found might have
+    /// changed are colored again.
+    fn changed_cells(
+        &mut self,
+        grid: &Grid,
+        layout: &Layout,
+        now: Duration,
+        mut paint: impl FnMut((u32, u32), Color),
+    ) {
+        match self.dirty.take() {
+            Some(cells) => {
+                for &cell in cells.iter().filter(|&&cell| layout.shows(cell)) {
+                    self.repaint(grid, cell, now, &mut paint);
+                }
+            }
+            None => {
+                for x in 0..grid.width() {
+                    for y in layout.rows() {
+                        self.repaint(grid, (x, y), now, &mut paint);
+                    }
+                }
+            }
+        }
+
+        self.caught_up = Some(CaughtUp::new(grid));
+    }
+
+    /// Calls `paint` with `cell` and its color if that isn't what it was drawn in
+    fn repaint(
+        &mut self,
+        grid: &Grid,
+        cell: (u32, u32),
+        now: Duration,
+        paint: &mut impl FnMut((u32, u32), Color),
+    ) {
+        let color = self.cell_color(grid, cell);
+        let color = match &mut self.fade {
+            Some(fade) => fade.color(cell, color, now),
+            None => color,
+        };
+
+        if self.drawn[cell].replace(color) != Some(color) {
+            paint(cell, color);
+        }
+    }
+
+    /// Fills the gaps between squares scaled up from a pixel each with the background
+    pub fn draw_gaps<T: RenderTarget>(&self, canvas: &mut Canvas<T>, layout: &Layout) {
+        canvas.set_draw_color(self.theme.background);
+        canvas.fill_rects(&layout.gaps()).unwrap();
+    }
+
+    /// Draws the portals, parent pointers and paths over the cells
+    pub fn draw_overlays<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        grid: &Grid,
+        layout: &Layout,
+    ) {
+        let w = grid.width();
+
+        // both ends of every portal outlined in its own hue, and joined if both are on the
+        // layer shown. Stairs are crossed by a line going up.
+        for (i, portal) in grid.map().portals().iter().enumerate() {
+            canvas.set_draw_color(hsv((i as f64 * 137.5 + 30.0) % 360.0, 0.8, 1.0));
+
+            for end in [portal.a, portal.b]
+                .into_iter()
+                .filter(|&end| layout.shows(end))
+            {
+                outline_cell(canvas, layout, end);
+
+                if grid.map().is_stairs(portal) {
+                    let rect = layout.cell_rect(end);
+                    canvas
+                        .draw_line(rect.bottom_left(), rect.top_right())
+                        .unwrap();
+                }
+            }
+
+            if layout.shows(portal.a) && layout.shows(portal.b) {
+                canvas
+                    .draw_line(layout.cell_center(portal.a), layout.cell_center(portal.b))
+                    .unwrap();
+            }
+        }
+
+        if self.parents {
+            canvas.set_draw_color(self.theme.parents);
+
+            let search = &grid.searches()[0];
+
+            for x in 0..w {
+                for y in layout.rows() {
+                    let Some(parent) = search.parent((x, y)).filter(|&p| layout.shows(p)) else {
+                        continue;
+                    };
+
+                    // half way towards the parent, so neighboring pointers don't merge
+                    let from = layout.cell_center((x, y));
+                    let to = layout.cell_center(parent);
+                    let tip = from + (to - from) / 2;
+
+                    // with a head, so flow fields show which way is downhill
+                    let back = (from - tip) / 2;
+                    let side = Point::new(-back.y(), back.x()) / 2;
+
+                    canvas.draw_line(from, tip).unwrap();
+                    canvas.draw_line(tip, tip + back + side).unwrap();
+                    canvas.draw_line(tip, tip + back - side).unwrap();
+                }
+            }
+        }
+
+        // any-angle paths are drawn as the actual segments on top of the cells they cross
+        canvas.set_draw_color(self.theme.lines);
+
+        for search in grid.searches() {
+            for segment in search.waypoints().windows(2) {
+                if !layout.shows(segment[0]) || !layout.shows(segment[1]) {
+                    continue;
+                }
+
+                canvas
+                    .draw_line(
+                        layout.cell_center(segment[0]),
+                        layout.cell_center(segment[1]),
+                    )
+                    .unwrap();
+            }
+        }
+
+        if self.smooth_paths {
+            canvas.set_draw_color(self.theme.smooth_path);
+
+            for search in grid.searches() {
+                for segment in grid.smooth_path(search.path()).windows(2) {
+                    if !layout.shows(segment[0]) || !layout.shows(segment[1]) {
+                        continue;
+                    }
+
+                    canvas
+                        .draw_line(
+                            layout.cell_center(segment[0]),
+                            layout.cell_center(segment[1]),
+                        )
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Draws the whole layer `layout` shows scaled into `rect`, a pixel per spot of it,
+    /// with the start, goals and current cell of every agent marked on top. `visible` is
+    /// the part of the grid in view, as fractions of its size from [`Layout::visible`],
+    /// and gets outlined.
+    pub fn draw_minimap<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        grid: &Grid,
+        layout: &Layout,
+        rect: Rect,
+        visible: (f64, f64, f64, f64),
+    ) {
+        let rows = layout.rows();
+        let rows_shown = rows.end - rows.start;
+
+        for px in 0..rect.width() {
+            for py in 0..rect.height() {
+                let cell = (
+                    px * grid.width() / rect.width(),
+                    rows.start + py * rows_shown / rect.height(),
+                );
+
+                canvas.set_draw_color(self.cell_color(grid, cell));
+                canvas
+                    .draw_point(Point::new(rect.x() + px as i32, rect.y() + py as i32))
+                    .unwrap();
+            }
+        }
+
+        // where a cell ends up on the minimap
+        let spot = |cell: (u32, u32)| {
+            Point::new(
+                rect.x() + (cell.0 * rect.width() / grid.width()) as i32,
+                rect.y() + ((cell.1 - rows.start) * rect.height() / rows_shown) as i32,
+            )
+        };
+        let marker = |cell| Rect::from_center(spot(cell), 3, 3);
+
+        for (i, search) in grid.searches().iter().enumerate() {
+            let palette = AgentPalette::for_agent(i, &self.theme);
+
+            canvas.set_draw_color(palette.goal);
+            for cell in search.goal().cells(grid.map()) {
+                if layout.shows(cell) {
+                    canvas.fill_rect(marker(cell)).unwrap();
+                }
+            }
+
+            for (cell, color) in [
+                (search.start(), palette.start),
+                (search.current(), palette.current),
+            ] {
+                if layout.shows(cell) {
+                    canvas.set_draw_color(color);
+                    canvas.fill_rect(marker(cell)).unwrap();
+                }
+            }
+        }
+
+        let (left, top, right, bottom) = visible;
+        let (w, h) = (rect.width() as f64, rect.height() as f64);
+
+        canvas.set_draw_color(self.theme.lines);
+        canvas.draw_rect(rect).unwrap();
+        canvas
+            .draw_rect(Rect::new(
+                rect.x() + (left * w) as i32,
+                rect.y() + (top * h) as i32,
+                (((right - left) * w) as u32).max(1),
+                (((bottom - top) * h) as u32).max(1),
+            ))
+            .unwrap();
+    }
+
+    /// Writes the distance (g), and with A* also the heuristic (h) and their sum (f),
+    /// into every cell the main search has reached on the layer shown.
+    pub fn draw_values<T: RenderTarget>(
+        &self,
+        canvas: &mut Canvas<T>,
+        grid: &Grid,
+        atlas: &GlyphAtlas,
+        layout: &Layout,
+    ) {
+        let search = &grid.searches()[0];
+
+        for x in 0..grid.width() {
+            for y in layout.rows() {
+                let g = match search.get_cell((x, y)) {
+                    // Bellman-Ford's distances can be negative, the states don't show that
+                    Some(CellState::Unvisited { dist } | CellState::Visited { dist }) => {
+                        search.signed_dist((x, y)).unwrap_or(dist as i64)
+                    }
+                    _ => continue,
+                };
+
+                let mut lines = vec![format!("g{}", format_dist(g))];
+                if search.algorithm().uses_heuristic() {
+                    let h = search.heuristic((x, y));
+
+                    lines.push(format!("h{}", format_dist(h)));
+                    lines.push(format!("f{}", format_dist(g + h as i64)));
+                }
+
+                let rect = layout.cell_rect((x, y));
+                let (left, top) = (rect.x() + 2, rect.y() + 2);
+
+                for (i, line) in lines.iter().enumerate() {
+                    atlas.draw_text(
+                        canvas,
+                        line,
+                        left,
+                        top + (i as u32 * atlas.glyph_height()) as i32,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Fills the square or triangle `cell` is drawn as with the current draw color.
+/// Triangles are filled row by row between their slanted sides, a pixel short of them
+/// on either side so neighbors don't run into each other.
+pub fn fill_cell<T: RenderTarget>(canvas: &mut Canvas<T>, layout: &Layout, cell: (u32, u32)) {
+    let Some([apex, left, right]) = layout.triangle(cell) else {
+        canvas.fill_rect(layout.cell_rect(cell)).unwrap();
+        return;
+    };
+
+    let (top, bottom) = (apex.y().min(left.y()), apex.y().max(left.y()));
+    let height = (bottom - top).max(1) as f64;
+    let inset = CELL_SPACING as i32;
+
+    for y in top..=bottom {
+        // how far from the apex towards the base this row is
+        let t = (y - apex.y()).abs() as f64 / height;
+        let from = apex.x() + ((left.x() - apex.x()) as f64 * t).round() as i32 + inset;
+        let to = apex.x() + ((right.x() - apex.x()) as f64 * t).round() as i32 - inset;
+
+        if from <= to {
+            canvas
+                .draw_line(Point::new(from, y), Point::new(to, y))
+                .unwrap();
+        }
+    }
+}
+
+/// Outlines the square or triangle `cell` is drawn as with the current draw color
+pub fn outline_cell<T: RenderTarget>(canvas: &mut Canvas<T>, layout: &Layout, cell: (u32, u32)) {
+    match layout.triangle(cell) {
+        Some([apex, left, right]) => canvas.draw_lines(&[apex, left, right, apex][..]).unwrap(),
+        None => canvas.draw_rect(layout.cell_rect(cell)).unwrap(),
+    }
+}
+
+/// Color ramp for untouched terrain, from the theme's color for normal cells to that
+/// of the most expensive ones
+fn terrain_color(cost: u32, theme: &Theme) -> Color {
+    let (cheapest, priciest) = (theme.cheapest_terrain, theme.priciest_terrain);
+
+    let t = cost.saturating_sub(1) as f64 / (MAX_COST - 1) as f64;
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t) as u8;
+
+    Color::RGB(
+        mix(cheapest.r, priciest.r),
+        mix(cheapest.g, priciest.g),
+        mix(cheapest.b, priciest.b),
+    )
+}
+
+/// Darkens `color` the more expensive a cell is to traverse
+fn shade_by_cost(color: Color, cost: u32) -> Color {
+    let factor = 1.0 - (cost.saturating_sub(1) as f64 / MAX_COST as f64) * 0.6;
+
+    Color::RGB(
+        (color.r as f64 * factor) as u8,
+        (color.g as f64 * factor) as u8,
+        (color.b as f64 * factor) as u8,
+    )
+}